@@ -0,0 +1,93 @@
+//! Read and write 1000 small JPEG files with the PhotosNorm binary.
+//! set does not need image dimensions: image headers are not read.
+//! info reads them, unless --exif-only is used: both are measured by a single run,
+//! cargo bench --bench batch -- info
+//! Compare two versions (like a change and its parent commit) with criterion baselines:
+//! git checkout HEAD~1 && cargo bench --bench batch -- --save-baseline before
+//! git checkout - && cargo bench --bench batch -- --baseline before
+use criterion::{criterion_group, criterion_main, Criterion};
+use image::{DynamicImage, ImageFormat};
+use little_exif::{exif_tag::ExifTag, filetype::FileExtension, metadata::Metadata};
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const FILES: usize = 1000;
+
+// Fill folder with small JPEG files with some EXIF tags
+fn create_images(folder: &Path) {
+    let mut data: Vec<u8> = Vec::new();
+    DynamicImage::new_rgb8(64, 48)
+        .write_to(&mut Cursor::new(&mut data), ImageFormat::Jpeg)
+        .unwrap();
+    let mut metadata = Metadata::new();
+    metadata.set_tag(ExifTag::ImageDescription("Benchmark".to_string()));
+    metadata.set_tag(ExifTag::DateTimeOriginal("2006:10:29 16:27:21".to_string()));
+    metadata
+        .write_to_vec(&mut data, FileExtension::JPEG)
+        .unwrap();
+    for index in 0..FILES {
+        fs::write(folder.join(format!("image_{:04}.jpg", index)), &data).unwrap();
+    }
+}
+
+fn photos_norm(args: &[&str], folder: &Path) {
+    let status = Command::new(env!("CARGO_BIN_EXE_PhotosNorm"))
+        .args(args)
+        .arg(folder)
+        .stdout(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+fn batch(c: &mut Criterion) {
+    let tmpdir = tempfile::tempdir().unwrap();
+    create_images(tmpdir.path());
+
+    let mut group = c.benchmark_group("1000 small JPEG files");
+    group.sample_size(10);
+    group.bench_function("info", |b| {
+        b.iter(|| photos_norm(&["info", "--summary"], tmpdir.path()))
+    });
+    group.bench_function("info details", |b| {
+        b.iter(|| photos_norm(&["info"], tmpdir.path()))
+    });
+    group.bench_function("info exif-only", |b| {
+        b.iter(|| photos_norm(&["info", "--exif-only"], tmpdir.path()))
+    });
+    // A new rating each time: all files are written
+    let mut rating = 0;
+    group.bench_function("set", |b| {
+        b.iter(|| {
+            rating = rating % 5 + 1;
+            photos_norm(
+                &["set", "--force", "--rating", &rating.to_string()],
+                tmpdir.path(),
+            )
+        })
+    });
+    let mut description = 0;
+    group.bench_function("set description", |b| {
+        b.iter(|| {
+            description += 1;
+            photos_norm(
+                &[
+                    "set",
+                    "--force",
+                    "--description",
+                    &format!("Benchmark {}", description),
+                ],
+                tmpdir.path(),
+            )
+        })
+    });
+    group.bench_function("fix dimensions", |b| {
+        b.iter(|| photos_norm(&["fix", "--dimensions"], tmpdir.path()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, batch);
+criterion_main!(benches);
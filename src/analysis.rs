@@ -0,0 +1,79 @@
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+use crate::metadata::Metadata;
+
+/// Files that would be changed by each fixer (see fix command)
+#[derive(Default, Debug)]
+pub struct Analysis {
+    pub files: usize,
+    pub errors: Vec<(PathBuf, String)>,
+    pub dimensions: Vec<PathBuf>,
+    pub names: Vec<PathBuf>,
+    pub orientations: Vec<PathBuf>,
+}
+
+impl Analysis {
+    /// Account for an image
+    pub fn add(&mut self, metadata: &Metadata) {
+        self.files += 1;
+        let path = metadata.path().to_path_buf();
+        if metadata.needs_dimensions_fix() {
+            self.dimensions.push(path.clone());
+        }
+        if metadata.needs_rename() {
+            self.names.push(path.clone());
+        }
+        if metadata.needs_orientation_fix() {
+            self.orientations.push(path);
+        }
+    }
+
+    /// Account for a file that cannot be loaded
+    pub fn add_error(&mut self, path: &Path, error: String) {
+        self.files += 1;
+        self.errors.push((path.to_path_buf(), error));
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "files": self.files,
+            "errors": self.errors
+                .iter()
+                .map(|(file, error)| json!({"file": file, "error": error}))
+                .collect::<Vec<_>>(),
+            "dimensions": self.dimensions,
+            "names": self.names,
+            "orientations": self.orientations,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn analysis() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let normalized = tmpdir
+            .path()
+            .join("2006_10_29-16_27_21 - A fun picture!.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &normalized).is_ok());
+
+        let mut analysis = Analysis::default();
+        analysis.add(&Metadata::new(Path::new("tests/all_tags.jpg")).unwrap());
+        analysis.add(&Metadata::new(Path::new("tests/no_date.jpg")).unwrap());
+        analysis.add(&Metadata::new(&normalized).unwrap());
+        analysis.add_error(Path::new("tests/empty"), "Unknown file type.".to_string());
+
+        assert_eq!(analysis.files, 4);
+        assert_eq!(analysis.errors.len(), 1);
+        assert_eq!(analysis.names, vec![PathBuf::from("tests/all_tags.jpg")]);
+        let value = analysis.to_json();
+        assert_eq!(value["files"], 4);
+        assert_eq!(value["names"][0], "tests/all_tags.jpg");
+        assert_eq!(value["errors"][0]["file"], "tests/empty");
+    }
+}
@@ -0,0 +1,101 @@
+use enumset::EnumSet;
+use serde_json::json;
+use std::fs::{File, OpenOptions};
+use std::io::{Error, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::journal::Entry;
+use crate::metadata::tag::Tag;
+
+/// Append-only log of all modifications (one JSON object per line):
+/// timestamp, file, operation (set or rename), tag, old and new values.
+/// Unlike the journal, it is meant to be read, not replayed.
+pub struct AuditLog {
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    pub fn open(path: &Path) -> Result<AuditLog, Error> {
+        Ok(AuditLog {
+            file: Mutex::new(OpenOptions::new().create(true).append(true).open(path)?),
+        })
+    }
+
+    /// Record saved tags. before and after hold values before and after the save.
+    /// Tags without recorded values (like GPS or orientation) are logged without values.
+    pub fn record(&self, before: &Entry, after: &Entry, tags: &EnumSet<Tag>) -> Result<(), Error> {
+        let timestamp = chrono::Local::now().to_rfc3339();
+        let mut lines = String::new();
+        for tag in tags.iter() {
+            let (operation, old, new) = match tag {
+                Tag::FileName | Tag::Folder => (
+                    "rename",
+                    Some(before.original_path.display().to_string()),
+                    Some(after.original_path.display().to_string()),
+                ),
+                _ => ("set", value(before, tag), value(after, tag)),
+            };
+            let record = json!({
+                "timestamp": timestamp,
+                "file": before.original_path,
+                "operation": operation,
+                "tag": tag,
+                "old": old,
+                "new": new,
+            });
+            lines.push_str(&format!("{}\n", record));
+        }
+        // Records of one save are written at once
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|_| Error::other("Log file lock poisoned."))?;
+        file.write_all(lines.as_bytes())
+    }
+}
+
+// Value of a tag recorded in a journal entry
+fn value(entry: &Entry, tag: Tag) -> Option<String> {
+    match tag {
+        Tag::Description => entry.description.clone(),
+        Tag::Date => entry.date.clone(),
+        Tag::Keywords => Some(entry.keywords.join(", ")),
+        Tag::Rating => entry.rating.map(|rating| rating.to_string()),
+        Tag::Comment => entry.comment.clone(),
+        Tag::Title => entry.title.clone(),
+        Tag::Caption => entry.caption.clone(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::Metadata;
+    use std::fs;
+
+    #[test]
+    fn log_description() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let log_path = tmpdir.path().join("log.jsonl");
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path).is_ok());
+
+        let log = AuditLog::open(&log_path).unwrap();
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        let before = Entry::new(&metadata);
+        metadata.set_description("Description 1");
+        let tags = metadata.save().unwrap();
+        assert!(log.record(&before, &Entry::new(&metadata), &tags).is_ok());
+
+        let content = fs::read_to_string(&log_path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let record: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(record["operation"], "set");
+        assert_eq!(record["tag"], "Description");
+        assert_eq!(record["old"], "A fun picture!");
+        assert_eq!(record["new"], "Description 1");
+    }
+}
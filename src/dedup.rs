@@ -0,0 +1,234 @@
+use chrono::NaiveDateTime;
+use md5::{Digest, Md5};
+use std::collections::HashMap;
+use std::{
+    io::Error,
+    path::{Path, PathBuf},
+};
+
+use crate::metadata::Metadata;
+
+/// Duplicate detection key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    /// ImageUniqueID tag (lowercase): kept by renaming and metadata changes
+    pub unique_id: Option<String>,
+    /// Dimensions, EXIF date and pixel digest: compared when an image has no ImageUniqueID
+    pub content: (u32, u32, Option<NaiveDateTime>, [u8; 16]),
+}
+
+/// Digest of the decoded pixels of an image.
+/// The pixel content is hashed, not the file bytes: copies that only differ
+/// by their metadata (or by a lossless re-encoding) have the same hash.
+/// A lossy re-encoding (like saving again a JPEG) changes the pixels.
+pub fn pixel_hash(path: &Path) -> Result<[u8; 16], Error> {
+    let image = match image::open(path) {
+        Err(e) => return Err(Error::other(e.to_string())),
+        Ok(image) => image,
+    };
+    let mut hasher = Md5::new();
+    hasher.update(image.width().to_le_bytes());
+    hasher.update(image.height().to_le_bytes());
+    hasher.update(image.to_rgba8().as_raw());
+    Ok(hasher.finalize().into())
+}
+
+/// Compute the duplicate detection key of an image: its ImageUniqueID if any, and its content
+pub fn signature(metadata: &Metadata) -> Result<Signature, Error> {
+    let (width, height) = metadata.dimensions()?;
+    Ok(Signature {
+        unique_id: metadata
+            .unique_id()
+            .map(|unique_id| unique_id.to_lowercase()),
+        content: (width, height, metadata.date(), pixel_hash(metadata.path())?),
+    })
+}
+
+/// ImageUniqueID computed from the image content (32 hexadecimal digits, MD5 digest):
+/// copies of an image get the same ID, and are still found as duplicates.
+pub fn content_unique_id(metadata: &Metadata) -> Result<String, Error> {
+    let (width, height) = metadata.dimensions()?;
+    let mut hasher = Md5::new();
+    hasher.update(width.to_le_bytes());
+    hasher.update(height.to_le_bytes());
+    hasher.update(metadata.exif_date().unwrap_or_default().as_bytes());
+    hasher.update(pixel_hash(metadata.path())?);
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Group duplicate images.
+/// Images with an ImageUniqueID are compared by ID, and by content with images
+/// without ID: a copy still matches its original once only one of them has an ID.
+/// Only groups of two or more images are returned, in first seen order.
+pub fn duplicates(images: Vec<(PathBuf, Signature)>) -> Vec<Vec<PathBuf>> {
+    // Each image is linked to the first image of its group
+    let mut first: Vec<usize> = (0..images.len()).collect();
+    let mut by_id: HashMap<&str, usize> = HashMap::new();
+    let mut with_id: HashMap<_, Vec<usize>> = HashMap::new();
+    let mut without_id: HashMap<_, usize> = HashMap::new();
+    for (i, (_, signature)) in images.iter().enumerate() {
+        let content = &signature.content;
+        match &signature.unique_id {
+            Some(unique_id) => {
+                join(&mut first, i, *by_id.entry(unique_id.as_str()).or_insert(i));
+                if let Some(&j) = without_id.get(content) {
+                    join(&mut first, i, j);
+                }
+                with_id.entry(content).or_default().push(i);
+            }
+            None => {
+                for &j in with_id.get(content).into_iter().flatten() {
+                    join(&mut first, i, j);
+                }
+                join(&mut first, i, *without_id.entry(content).or_insert(i));
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<PathBuf>> = Vec::new();
+    let mut index: HashMap<usize, usize> = HashMap::new();
+    for (i, (path, _)) in images.into_iter().enumerate() {
+        let root = find(&mut first, i);
+        match index.get(&root) {
+            Some(&group) => groups[group].push(path),
+            None => {
+                index.insert(root, groups.len());
+                groups.push(vec![path]);
+            }
+        }
+    }
+    groups.retain(|group| group.len() > 1);
+    groups
+}
+
+// First image of the group of image i
+fn find(first: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while first[root] != root {
+        root = first[root];
+    }
+    first[i] = root;
+    root
+}
+
+// Merge the groups of images i and j
+fn join(first: &mut [usize], i: usize, j: usize) {
+    let (i, j) = (find(first, i), find(first, j));
+    first[i.max(j)] = i.min(j);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn pixel_hash_compare_pixels() {
+        let tmpdir = tempfile::tempdir().unwrap();
+
+        let mut image = RgbImage::new(4, 4);
+        assert!(image.save(tmpdir.path().join("a.png")).is_ok());
+        assert!(image.save(tmpdir.path().join("b.bmp")).is_ok());
+        image.put_pixel(1, 1, Rgb([255, 0, 0]));
+        assert!(image.save(tmpdir.path().join("c.png")).is_ok());
+
+        let a = pixel_hash(&tmpdir.path().join("a.png")).unwrap();
+        let b = pixel_hash(&tmpdir.path().join("b.bmp")).unwrap();
+        let c = pixel_hash(&tmpdir.path().join("c.png")).unwrap();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+
+        assert!(pixel_hash(Path::new("tests/empty")).is_err());
+    }
+
+    #[test]
+    fn metadata_change_keeps_signature() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(std::fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.set_description("Another description");
+        assert!(metadata.save().is_ok());
+
+        let original = Metadata::new(Path::new("tests/all_tags.jpg")).unwrap();
+        assert_eq!(signature(&original).unwrap(), signature(&metadata).unwrap());
+    }
+
+    #[test]
+    fn unique_id_signature() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(std::fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path).is_ok());
+
+        // Same content, same ID
+        let original = Metadata::new(Path::new("tests/all_tags.jpg")).unwrap();
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        let unique_id = content_unique_id(&metadata).unwrap();
+        assert_eq!(unique_id.len(), 32);
+        assert_eq!(content_unique_id(&original).unwrap(), unique_id);
+
+        metadata.set_unique_id(&unique_id.to_uppercase());
+        metadata.fix_file_name();
+        assert!(metadata.save().is_ok());
+        assert_eq!(signature(&metadata).unwrap().unique_id, Some(unique_id));
+        // Compared by content with the original, without ID
+        assert_eq!(
+            duplicates(vec![
+                (PathBuf::from("a"), signature(&original).unwrap()),
+                (PathBuf::from("b"), signature(&metadata).unwrap()),
+            ]),
+            vec![vec![PathBuf::from("a"), PathBuf::from("b")]]
+        );
+    }
+
+    #[test]
+    fn group_duplicates() {
+        let image = |path: &str, unique_id: Option<&str>, pixels: u8| {
+            let signature = Signature {
+                unique_id: unique_id.map(String::from),
+                content: (1, 1, None, [pixels; 16]),
+            };
+            (PathBuf::from(path), signature)
+        };
+        let groups = duplicates(vec![
+            image("a", None, 1),
+            image("b", None, 2),
+            image("c", None, 1),
+            image("d", None, 1),
+        ]);
+        assert_eq!(
+            groups,
+            vec![vec![
+                PathBuf::from("a"),
+                PathBuf::from("c"),
+                PathBuf::from("d")
+            ]]
+        );
+
+        // Same ID, or same content when an image has no ID
+        let groups = duplicates(vec![
+            image("a", Some("1"), 1),
+            image("b", Some("2"), 1),
+            image("c", Some("1"), 2),
+            image("d", None, 1),
+            image("e", Some("3"), 3),
+        ]);
+        assert_eq!(
+            groups,
+            vec![vec![
+                PathBuf::from("a"),
+                PathBuf::from("b"),
+                PathBuf::from("c"),
+                PathBuf::from("d")
+            ]]
+        );
+        // Different IDs with the same content are not duplicates
+        let groups = duplicates(vec![image("a", Some("1"), 1), image("b", Some("2"), 1)]);
+        assert!(groups.is_empty());
+    }
+}
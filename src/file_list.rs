@@ -0,0 +1,316 @@
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::metadata::unique_file_path;
+
+/// List images from a file list (aka read folders).
+/// Each folder is replaced by the files within (non-recursive).
+/// Symbolic links to folders are not followed unless follow_symlinks is set.
+/// Glob patterns (like *.jpg) are expanded if there is no file with this name.
+/// Each file is listed once, even if given several times (like a file within a given folder).
+/// Return the image list, the list of skipped symbolic links and the list of
+/// glob patterns that match no file.
+pub fn expand(
+    files: &[PathBuf],
+    follow_symlinks: bool,
+) -> (Vec<PathBuf>, Vec<PathBuf>, Vec<PathBuf>) {
+    let mut images: Vec<PathBuf> = Vec::new();
+    let mut skipped: Vec<PathBuf> = Vec::new();
+    let mut unmatched: Vec<PathBuf> = Vec::new();
+    for file in files.iter() {
+        if !file.exists() && is_glob_pattern(file) {
+            let mut matched = false;
+            if let Ok(paths) = glob::glob(&file.to_string_lossy()) {
+                for path in paths.flatten() {
+                    matched = true;
+                    expand_file(&path, follow_symlinks, &mut images, &mut skipped);
+                }
+            }
+            if !matched {
+                unmatched.push(file.to_path_buf());
+            }
+        } else {
+            expand_file(file, follow_symlinks, &mut images, &mut skipped);
+        }
+    }
+    // Remove duplicates, keeping first seen order
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+    images.retain(|image| seen.insert(fs::canonicalize(image).unwrap_or(image.to_path_buf())));
+    (images, skipped, unmatched)
+}
+
+// Add a file or the files within a folder to the image list
+fn expand_file(
+    file: &Path,
+    follow_symlinks: bool,
+    images: &mut Vec<PathBuf>,
+    skipped: &mut Vec<PathBuf>,
+) {
+    if !file.is_dir() {
+        images.push(file.to_path_buf());
+    } else if !follow_symlinks && is_symlink(file) {
+        skipped.push(file.to_path_buf());
+    } else {
+        match fs::read_dir(file) {
+            // Let open display the error and process next file.
+            Err(_) => images.push(file.to_path_buf()),
+            // Add all files to image list
+            Ok(files) => {
+                for entry in files {
+                    let file = entry.unwrap().path();
+                    // non-recursive
+                    if file.is_file() {
+                        images.push(file.to_path_buf());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Remove images matching one of the patterns (like *_thumb.jpg or backup/*).
+/// Patterns are matched against the file name and the trailing parts of the path.
+pub fn exclude(images: &mut Vec<PathBuf>, patterns: &[glob::Pattern]) {
+    if patterns.is_empty() {
+        return;
+    }
+    images.retain(|image| {
+        let components: Vec<_> = image.components().collect();
+        !(0..components.len()).any(|start| {
+            let path: PathBuf = components[start..].iter().collect();
+            patterns.iter().any(|pattern| pattern.matches_path(&path))
+        })
+    });
+}
+
+// Check if path contains glob metacharacters
+fn is_glob_pattern(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?', '['])
+}
+
+/// Check that a file is within one of the given (canonical) folders.
+/// The file path is canonicalized: ../ and symbolic links cannot escape the folders.
+pub fn check_confined(file: &Path, roots: &[PathBuf]) -> Result<(), Error> {
+    let path = fs::canonicalize(file)?;
+    if roots.iter().any(|root| path.starts_with(root)) {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!("{} is outside of --confine folders.", path.display()),
+        ))
+    }
+}
+
+/// Copy file into folder (created if needed).
+/// The copy may be numbered to prevent erasing a file with same name.
+/// Return the path of the copy.
+pub fn copy_to_folder(file: &Path, folder: &Path) -> Result<PathBuf, Error> {
+    fs::create_dir_all(folder)?;
+    let target = unique_file_path(
+        folder,
+        file.file_stem().unwrap_or(OsStr::new("")),
+        file.extension().unwrap_or(OsStr::new("")),
+    );
+    fs::copy(file, &target)?;
+    Ok(target)
+}
+
+/// Check if path is a symbolic link (without following it)
+pub fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path)
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false)
+}
+
+/// Move a file, even to another file system.
+/// Across file systems, the file is copied (with its permissions) then removed:
+/// unlike a rename, this is not atomic.
+pub fn move_file(from: &Path, to: &Path) -> Result<(), Error> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+            fs::copy(from, to)?;
+            if let Err(e) = fs::remove_file(from) {
+                // Do not keep two copies
+                let _ = fs::remove_file(to);
+                return Err(e);
+            }
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_folder() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        assert!(fs::copy("tests/all_tags.jpg", tmpdir.path().join("a.jpg")).is_ok());
+        assert!(fs::create_dir(tmpdir.path().join("sub")).is_ok());
+        assert!(fs::copy("tests/all_tags.jpg", tmpdir.path().join("sub/b.jpg")).is_ok());
+
+        let (images, skipped, _) = expand(&[tmpdir.path().to_path_buf()], false);
+        assert_eq!(images, vec![tmpdir.path().join("a.jpg")]);
+        assert!(skipped.is_empty());
+    }
+
+    #[test]
+    fn expand_duplicates() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        assert!(fs::copy("tests/all_tags.jpg", tmpdir.path().join("a.jpg")).is_ok());
+        assert!(fs::copy("tests/all_tags.jpg", tmpdir.path().join("b.jpg")).is_ok());
+
+        let file = tmpdir.path().join("b.jpg");
+        let folder = tmpdir.path().to_path_buf();
+        let (mut images, _, _) = expand(&[file.clone(), folder.clone(), folder.join(".")], false);
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0], file);
+        images.sort();
+        assert_eq!(
+            images,
+            vec![tmpdir.path().join("a.jpg"), tmpdir.path().join("b.jpg")]
+        );
+
+        // Missing files are kept (to be reported) but only once
+        let missing = tmpdir.path().join("missing.jpg");
+        let (images, _, _) = expand(&[missing.clone(), missing.clone()], false);
+        assert_eq!(images, vec![missing]);
+    }
+
+    #[test]
+    fn expand_glob() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        assert!(fs::copy("tests/all_tags.jpg", tmpdir.path().join("a.jpg")).is_ok());
+        assert!(fs::copy("tests/all_tags.jpg", tmpdir.path().join("b.jpg")).is_ok());
+        assert!(fs::write(tmpdir.path().join("c.txt"), "").is_ok());
+        assert!(fs::create_dir(tmpdir.path().join("sub.jpg")).is_ok());
+        assert!(fs::copy("tests/all_tags.jpg", tmpdir.path().join("sub.jpg/d.jpg")).is_ok());
+
+        // Matching folders are expanded too
+        let (mut images, skipped, unmatched) = expand(&[tmpdir.path().join("*.jpg")], false);
+        images.sort();
+        assert_eq!(
+            images,
+            vec![
+                tmpdir.path().join("a.jpg"),
+                tmpdir.path().join("b.jpg"),
+                tmpdir.path().join("sub.jpg/d.jpg")
+            ]
+        );
+        assert!(skipped.is_empty());
+        assert!(unmatched.is_empty());
+
+        let pattern = tmpdir.path().join("*.png");
+        let (images, _, unmatched) = expand(&[pattern.clone()], false);
+        assert!(images.is_empty());
+        assert_eq!(unmatched, vec![pattern]);
+
+        // A file with glob metacharacters in its name is not expanded
+        assert!(fs::copy("tests/all_tags.jpg", tmpdir.path().join("[1].jpg")).is_ok());
+        let (images, _, unmatched) = expand(&[tmpdir.path().join("[1].jpg")], false);
+        assert_eq!(images, vec![tmpdir.path().join("[1].jpg")]);
+        assert!(unmatched.is_empty());
+    }
+
+    #[test]
+    fn exclude_patterns() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        assert!(fs::copy("tests/all_tags.jpg", tmpdir.path().join("a.jpg")).is_ok());
+        assert!(fs::copy("tests/all_tags.jpg", tmpdir.path().join("a_thumb.jpg")).is_ok());
+        let backup = tmpdir.path().join("backup");
+        assert!(fs::create_dir(&backup).is_ok());
+        assert!(fs::copy("tests/all_tags.jpg", backup.join("b.jpg")).is_ok());
+
+        let (mut images, _, _) = expand(&[tmpdir.path().to_path_buf(), backup.clone()], false);
+        assert_eq!(images.len(), 3);
+        let patterns = [
+            glob::Pattern::new("*_thumb.jpg").unwrap(),
+            glob::Pattern::new("backup/*").unwrap(),
+        ];
+        exclude(&mut images, &patterns);
+        assert_eq!(images, vec![tmpdir.path().join("a.jpg")]);
+
+        // Relative paths
+        let mut images = vec![PathBuf::from("backup/c.jpg"), PathBuf::from("c.jpg")];
+        exclude(&mut images, &patterns);
+        assert_eq!(images, vec![PathBuf::from("c.jpg")]);
+    }
+
+    #[test]
+    fn confined_files() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let root = tmpdir.path().join("root");
+        assert!(fs::create_dir(&root).is_ok());
+        assert!(fs::copy("tests/all_tags.jpg", root.join("a.jpg")).is_ok());
+        assert!(fs::copy("tests/all_tags.jpg", tmpdir.path().join("b.jpg")).is_ok());
+        let roots = vec![fs::canonicalize(&root).unwrap()];
+
+        assert!(check_confined(&root.join("a.jpg"), &roots).is_ok());
+        assert!(check_confined(&root.join("../root/a.jpg"), &roots).is_ok());
+        // Escape with ..
+        let e = check_confined(&root.join("../b.jpg"), &roots).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::PermissionDenied);
+        assert!(check_confined(&tmpdir.path().join("b.jpg"), &roots).is_err());
+        assert!(check_confined(&root.join("missing.jpg"), &roots).is_err());
+    }
+
+    #[test]
+    fn copy_to_output_folder() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let output = tmpdir.path().join("output");
+
+        let copy = copy_to_folder(Path::new("tests/all_tags.jpg"), &output);
+        assert_eq!(copy.ok(), Some(output.join("all_tags.jpg")));
+        let copy = copy_to_folder(Path::new("tests/all_tags.jpg"), &output);
+        assert_eq!(copy.ok(), Some(output.join("all_tags-1.jpg")));
+        assert!(Path::new("tests/all_tags.jpg").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn expand_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let tmpdir = tempfile::tempdir().unwrap();
+        let folder = tmpdir.path().join("folder");
+        assert!(fs::create_dir(&folder).is_ok());
+        assert!(fs::copy("tests/all_tags.jpg", folder.join("a.jpg")).is_ok());
+        // A loop: folder/loop -> folder
+        assert!(symlink(&folder, folder.join("loop")).is_ok());
+        let link = tmpdir.path().join("link");
+        assert!(symlink(&folder, &link).is_ok());
+
+        // Symbolic links to folders are skipped by default
+        let (images, skipped, _) = expand(&[link.clone()], false);
+        assert!(images.is_empty());
+        assert_eq!(skipped, vec![link.clone()]);
+
+        // ... and followed on demand
+        let (images, skipped, _) = expand(&[link.clone()], true);
+        assert_eq!(images, vec![link.join("a.jpg")]);
+        assert!(skipped.is_empty());
+
+        // Symbolic links to folders within a folder are never followed
+        let (images, _, _) = expand(&[folder.clone()], true);
+        assert_eq!(images, vec![folder.join("a.jpg")]);
+    }
+
+    #[test]
+    fn move_files() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let from = tmpdir.path().join("photo_norm_test.jpg");
+        let to = tmpdir.path().join("photo_norm_test-1.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &from).is_ok());
+
+        assert!(move_file(&from, &to).is_ok());
+        assert!(!from.exists());
+        assert!(to.exists());
+        assert!(move_file(&from, &to).is_err());
+    }
+}
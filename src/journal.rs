@@ -0,0 +1,324 @@
+use enumset::{enum_set, EnumSet};
+use filetime::FileTime;
+use little_exif::exif_tag::ExifTag;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Error, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::file_list;
+use crate::metadata::{
+    named_tag, tag::Tag, tag_value::TagValue, xmp::Xmp, CommentEncoding, Metadata,
+};
+
+// Tags restored from the raw EXIF values of an entry (see Entry::exif)
+const RAW_TAGS: EnumSet<Tag> = enum_set!(Tag::Gps | Tag::Dimensions | Tag::Strings | Tag::Others);
+
+/// A journal entry: one saved file.
+/// Tag values are the ones before modification.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Entry {
+    pub original_path: PathBuf,
+    pub new_path: PathBuf,
+    pub tags: Vec<Tag>,
+    pub description: Option<String>,
+    pub date: Option<String>,
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub rating: Option<u16>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub caption: Option<String>,
+    #[serde(default)]
+    pub orientation: Option<u16>,
+    /// File modification time (seconds since the epoch)
+    #[serde(default)]
+    pub mtime: Option<i64>,
+    #[serde(default)]
+    pub maker_note: Option<Vec<u8>>,
+    /// EXIF tags known by name (see named_tag) and their values,
+    /// kept if GPS, dimensions, strings or other tags are saved
+    #[serde(default)]
+    pub exif: Vec<(String, String)>,
+    /// The file was copied: the original file is left as is
+    #[serde(default)]
+    pub copy: bool,
+}
+
+impl Entry {
+    /// Record metadata values before modification
+    pub fn new(metadata: &Metadata) -> Entry {
+        Entry {
+            original_path: metadata.path().to_path_buf(),
+            new_path: metadata.path().to_path_buf(),
+            tags: Vec::new(),
+            description: metadata.description(),
+            date: metadata.exif_date(),
+            keywords: metadata.keywords(),
+            rating: metadata.rating(),
+            comment: metadata.user_comment(),
+            title: metadata.title(),
+            caption: metadata.caption(),
+            orientation: metadata.orientation(),
+            mtime: fs::metadata(metadata.path())
+                .and_then(|file| file.modified())
+                .ok()
+                .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs() as i64),
+            maker_note: metadata.maker_note(),
+            exif: named_tag::names()
+                .filter_map(|name| {
+                    let value = metadata.tag_value(&named_tag::lookup(name).ok()?)?;
+                    Some((name.to_string(), value.to_string()))
+                })
+                .collect(),
+            copy: false,
+        }
+    }
+
+    /// Record the result of a save()
+    pub fn saved(&mut self, metadata: &Metadata, tags: &EnumSet<Tag>) {
+        self.new_path = metadata.path().to_path_buf();
+        self.tags = tags.iter().collect();
+        self.copy = self.new_path != self.original_path && self.original_path.exists();
+        // Only keep the values that undo will need
+        if !tags.contains(Tag::MakerNote) {
+            self.maker_note = None;
+        }
+        if tags.is_disjoint(RAW_TAGS) {
+            self.exif.clear();
+        }
+    }
+}
+
+/// Append an entry to the journal (one JSON object per line)
+pub fn append(journal: &Path, entry: &Entry) -> Result<(), Error> {
+    let mut file = OpenOptions::new().create(true).append(true).open(journal)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)
+}
+
+/// Read all journal entries
+pub fn read(journal: &Path) -> Result<Vec<Entry>, Error> {
+    let mut entries = Vec::new();
+    for line in BufReader::new(File::open(journal)?).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            entries.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(entries)
+}
+
+/// Undo the modifications recorded in an entry: a copy is removed.
+/// Tags removed by strip that are not known by name (see named_tag) cannot be restored.
+/// Return the list of restored tags.
+pub fn undo(entry: &Entry) -> Result<EnumSet<Tag>, Error> {
+    if !entry.new_path.exists() {
+        return Err(Error::other(format!(
+            "{} does not exist anymore.",
+            entry.new_path.display()
+        )));
+    }
+    if entry.copy {
+        fs::remove_file(&entry.new_path)?;
+        let sidecar = Xmp::sidecar_path(&entry.new_path);
+        if sidecar.exists() && Xmp::sidecar_path(&entry.original_path).exists() {
+            fs::remove_file(sidecar)?;
+        }
+        return Ok(entry.tags.iter().copied().collect());
+    }
+    if entry.new_path != entry.original_path && entry.original_path.exists() {
+        return Err(Error::other(format!(
+            "{} already exists.",
+            entry.original_path.display()
+        )));
+    }
+
+    // Restore tags
+    let mut metadata = Metadata::new(&entry.new_path)?;
+    let tags: EnumSet<Tag> = entry.tags.iter().copied().collect();
+    if !tags.is_disjoint(RAW_TAGS) {
+        restore_exif(&mut metadata, entry, tags & RAW_TAGS)?;
+    }
+    for tag in entry.tags.iter() {
+        match tag {
+            Tag::Description => match &entry.description {
+                Some(description) => metadata.set_description(description),
+                None => metadata.clear_description(),
+            },
+            Tag::Date => match &entry.date {
+                Some(date) => metadata.set_date_from_exif(date.to_string())?,
+                None => metadata.clear_date(),
+            },
+            Tag::Keywords => metadata.set_keywords(&entry.keywords),
+            Tag::Rating => match entry.rating {
+                Some(rating) => metadata.set_rating(rating)?,
+                None => metadata.clear_rating(),
+            },
+            Tag::Comment => match &entry.comment {
+                Some(comment) => metadata.set_user_comment(comment, CommentEncoding::Unicode)?,
+                None => metadata.clear_user_comment(),
+            },
+            Tag::Title => match &entry.title {
+                Some(title) => metadata.set_title(title)?,
+                None => metadata.clear_title(),
+            },
+            Tag::Caption => match &entry.caption {
+                Some(caption) => metadata.set_caption(caption)?,
+                None => metadata.clear_caption(),
+            },
+            Tag::Orientation => {
+                if let Some(orientation) = entry.orientation {
+                    metadata.restore_orientation(orientation)?;
+                }
+            }
+            Tag::MakerNote => {
+                if let Some(maker_note) = &entry.maker_note {
+                    metadata.set_exif_tag(ExifTag::MakerNote(maker_note.clone()));
+                }
+            }
+            _ => (),
+        }
+    }
+    let mut restored = metadata.save()?;
+
+    // Restore file name
+    if entry.new_path != entry.original_path {
+        file_list::move_file(&entry.new_path, &entry.original_path)?;
+        restored.extend(
+            entry
+                .tags
+                .iter()
+                .filter(|tag| matches!(tag, Tag::FileName | Tag::Folder))
+                .copied(),
+        );
+    }
+
+    // Restore modification time
+    if tags.contains(Tag::Mtime) {
+        if let Some(mtime) = entry.mtime {
+            filetime::set_file_mtime(&entry.original_path, FileTime::from_unix_time(mtime, 0))?;
+            restored.insert(Tag::Mtime);
+        }
+    }
+
+    Ok(restored)
+}
+
+// Restore the raw EXIF tags of these categories (Strings: all text tags)
+fn restore_exif(metadata: &mut Metadata, entry: &Entry, tags: EnumSet<Tag>) -> Result<(), Error> {
+    for name in named_tag::names() {
+        let tag = named_tag::lookup(name)?;
+        let current = metadata.tag_value(&tag);
+        let restore = tags.contains(Metadata::tag_category(&tag))
+            || (tags.contains(Tag::Strings) && matches!(current, Some(TagValue::Text(_))));
+        let original = entry
+            .exif
+            .iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value);
+        if !restore || current.map(|value| value.to_string()).as_ref() == original {
+            continue;
+        }
+        match original {
+            Some(value) => metadata.set_exif_tag(named_tag::parse(name, value)?),
+            None => metadata.remove_exif_tag(tag),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_description_and_rename() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let journal = tmpdir.path().join("journal.jsonl");
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        let mut entry = Entry::new(&metadata);
+        metadata.set_description("Description 1");
+        metadata.fix_file_name();
+        let tags = metadata.save().unwrap();
+        entry.saved(&metadata, &tags);
+        assert!(append(&journal, &entry).is_ok());
+        assert!(!tmp_file_path.exists());
+
+        let entries = read(&journal).unwrap();
+        assert_eq!(entries, vec![entry]);
+        assert_eq!(
+            undo(&entries[0]).ok(),
+            Some(Tag::Description | Tag::FileName)
+        );
+        assert!(tmp_file_path.exists());
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.description(), Some("A fun picture!".to_string()));
+
+        // Already undone
+        assert!(undo(&entries[0]).is_err());
+    }
+
+    #[test]
+    fn undo_copy() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        let folder = tmpdir.path().join("sorted");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        let mut entry = Entry::new(&metadata);
+        metadata.move_to_folder(&folder, true);
+        let tags = metadata.save().unwrap();
+        entry.saved(&metadata, &tags);
+        assert!(entry.copy);
+        assert!(folder.join("photo_norm_test.jpg").exists());
+
+        // The copy is removed, the original is kept
+        assert_eq!(undo(&entry).ok(), Some(enum_set!(Tag::Folder)));
+        assert!(!folder.join("photo_norm_test.jpg").exists());
+        assert!(tmp_file_path.exists());
+    }
+
+    #[test]
+    fn undo_raw_tags() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let journal = tmpdir.path().join("journal.jsonl");
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+        let latitude = ExifTag::GPSLatitude(Vec::new());
+        let artist = ExifTag::Artist(String::new());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        let gps = metadata.tag_value(&latitude);
+        let mtime = |path: &Path| {
+            let mtime = fs::metadata(path).unwrap().modified().unwrap();
+            mtime.duration_since(UNIX_EPOCH).unwrap().as_secs()
+        };
+        let original_mtime = mtime(&tmp_file_path);
+        let mut entry = Entry::new(&metadata);
+        metadata.set_gps(43.6045, 1.4440);
+        metadata.set_exif_tag(named_tag::parse("Artist", "John Doe").unwrap());
+        assert!(metadata.set_mtime_from_date());
+        let tags = metadata.save().unwrap();
+        entry.saved(&metadata, &tags);
+        assert!(append(&journal, &entry).is_ok());
+
+        let entries = read(&journal).unwrap();
+        assert_eq!(
+            undo(&entries[0]).ok(),
+            Some(Tag::Gps | Tag::Others | Tag::Mtime)
+        );
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.tag_value(&latitude), gps);
+        assert_eq!(metadata.tag_value(&artist), None);
+        assert_eq!(mtime(&tmp_file_path), original_mtime);
+    }
+}
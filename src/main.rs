@@ -1,10 +1,39 @@
-use clap::{builder::ArgPredicate, Args, CommandFactory, Parser, Subcommand};
+use analysis::Analysis;
+use audit_log::AuditLog;
+use chrono::format::{Item, StrftimeItems};
+use clap::{
+    builder::ArgPredicate, parser::ValueSource, Args, CommandFactory, Parser, Subcommand, ValueEnum,
+};
 use clap_markdown::MarkdownOptions;
 use colored::Colorize;
-use metadata::Metadata;
+use enumset::EnumSet;
+use little_exif::exif_tag::ExifTag;
+use metadata::diff::{DriftSummary, ReferenceFolder, TagDiff};
+use metadata::json_patch::JsonPatches;
+use metadata::{
+    camera_info::FocalUnit, filter::Filter, gps_info::GpsInfo, tag::Tag, template::Template,
+    CommentEncoding, Conflict, DateFormat, DateSource, Dims, FileNameOptions, Metadata,
+};
+use ordered_writer::OrderedWriter;
+use serde_json::json;
 use std::fs;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use summary::Summary;
+use table::{format_size, Grid, Table};
 
+mod analysis;
+mod audit_log;
+mod dedup;
+mod file_list;
+mod journal;
 mod metadata;
+mod ordered_writer;
+mod sequence;
+mod summary;
+mod table;
+mod watch;
 
 const CARGO_PKG_NAME: &str = env!("CARGO_PKG_NAME");
 
@@ -13,11 +42,30 @@ pub const DOC: &str = "PhotosNorm: A simple tool to lossless manipulate images p
                        info: display some EXIF info.\n\
                        set:  Update some EXIF tags. More info below or with set --help.\n\
                        fix:  Fix properties like orientation, file name, ... More info below or with fix --help.\n\
+                       sort: Move images into dated sub-folders. More info below or with sort --help.\n\
+                       dedup: Find duplicated images (same ImageUniqueID, or same dimensions, date and pixels if one has no ImageUniqueID).\n\
+                       analyze: List files that fix would change, by fixer (read-only).\n\
+                       extract-thumbnail: Write EXIF thumbnails as <name>_thumb.jpg.\n\
+                       strip: Remove EXIF tags (--except to keep some, --only to remove some).\n\
+                       undo: Undo modifications recorded with --journal.\n\
+                       diff: Compare EXIF tags of two images.\n\
+                       watch: Run set or fix on each new image of a folder.\n\
                        \n\
                        To each command, you can provide one or more files and/or folders.\n\
                        Each known files (aka images) will be processed, other ones will be ignored.\n\
                        For each folder, all files within will be analysed like described just before. Sub-folders will be \
-                       ignored (this is non-recursive).";
+                       ignored (this is non-recursive).\n\
+                       Glob patterns (like *.jpg) are expanded on all platforms.\n\
+                       RAW files (CR2, NEF, ARW, DNG) and HEIF/HEIC files are read-only: their EXIF tags are never modified.\n\
+                       \n\
+                       Exit codes: 0 on success, 1 if some files cannot be processed, 2 on invalid arguments, \
+                       3 if no input files matched, \
+                       130 if interrupted (Ctrl-C): the current file is completed first.";
+
+const EXIT_FILE_ERRORS: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_NO_INPUT: i32 = 3;
+const EXIT_INTERRUPTED: i32 = 130;
 
 #[derive(Parser)]
 #[command(version, about = DOC, long_about = None)]
@@ -26,6 +74,89 @@ pub const DOC: &str = "PhotosNorm: A simple tool to lossless manipulate images p
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Follow symbolic links to folders (skipped by default)
+    #[arg(long, global = true)]
+    follow_symlinks: bool,
+
+    /// Modify copies of the images in this folder (set, fix and strip only).
+    /// Original images are left untouched.
+    #[arg(long, global = true, value_name = "DIR")]
+    output_dir: Option<std::path::PathBuf>,
+
+    /// Record modifications in a journal to be able to undo them
+    #[arg(long, global = true, value_name = "FILE")]
+    journal: Option<std::path::PathBuf>,
+
+    /// Append every tag change and rename to this log file (one JSON object per line)
+    #[arg(long, global = true, value_name = "FILE")]
+    log_file: Option<std::path::PathBuf>,
+
+    /// Keep file access and modification times of modified images
+    #[arg(long, global = true)]
+    preserve_mtime: bool,
+
+    /// Use XMP sidecar (<name>.xmp) values instead of EXIF ones when both exist
+    #[arg(long, global = true)]
+    prefer_xmp: bool,
+
+    /// Ask before renaming or writing each image
+    #[arg(
+        short,
+        long,
+        global = true,
+        value_enum,
+        value_name = "WHEN",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "auto"
+    )]
+    interactive: Option<Interactive>,
+
+    /// Only process images matching all predicates (comma separated list).
+    /// Like camera~=Canon,date>=2006-01-01,iso>800.
+    /// Fields: camera, description, keyword, date, iso, rating, width, height.
+    /// Operators: ~= (contains), =, !=, <, <=, >, >=.
+    #[arg(long, global = true, value_name = "FILTER", value_parser = Filter::parse)]
+    filter: Option<Filter>,
+
+    /// EXIF tag used as image date
+    #[arg(long, global = true, value_enum, default_value_t = DateSource::Original)]
+    date_source: DateSource,
+
+    /// Dates before this year (or in the future) are implausible,
+    /// like dates of cameras with a reset clock
+    #[arg(long, global = true, value_name = "YEAR", default_value_t = metadata::MIN_YEAR)]
+    min_year: i32,
+
+    /// Two-digit years of EXIF dates (like 06:10:29 16:27:21) below this pivot
+    /// are in the 2000s, other ones in the 1900s
+    #[arg(
+        long,
+        global = true,
+        value_name = "YY",
+        value_parser = clap::value_parser!(i32).range(0..=100),
+        default_value_t = metadata::YEAR_PIVOT
+    )]
+    year_pivot: i32,
+
+    /// Disable colors (also disabled when output is not a terminal or NO_COLOR is set)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Quietly skip files without EXIF data (not reported as errors)
+    #[arg(long, global = true)]
+    skip_no_exif: bool,
+
+    /// Refuse to process files outside of this folder (can be repeated).
+    /// Paths are resolved first: ../ and symbolic links cannot escape it.
+    #[arg(long, global = true, value_name = "DIR")]
+    confine: Vec<std::path::PathBuf>,
+
+    /// Skip files matching this glob pattern, like *_thumb.jpg or backup/* (can be repeated).
+    /// Patterns are matched against the file name and the end of the path.
+    #[arg(long, global = true, value_name = "PATTERN", value_parser = glob::Pattern::new)]
+    exclude: Vec<glob::Pattern>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -39,12 +170,124 @@ enum Commands {
     /// fix: Fix file properties
     Fix(FixArgs),
 
+    /// sort: Move images into dated sub-folders
+    Sort(SortArgs),
+
+    /// dedup: Find duplicated images
+    Dedup(DedupArgs),
+
+    /// analyze: List files that fix would change, by fixer
+    Analyze(AnalyzeArgs),
+
+    /// extract-thumbnail: Write EXIF thumbnails as <name>_thumb.jpg
+    ExtractThumbnail(ExtractThumbnailArgs),
+
+    /// strip: Remove EXIF tags
+    Strip(StripArgs),
+
+    /// undo: Undo modifications recorded in a journal
+    Undo(UndoArgs),
+
+    /// diff: Compare EXIF tags of two images
+    Diff(DiffArgs),
+
+    /// watch: Run set or fix on each new image of a folder
+    Watch(WatchArgs),
+
     #[command(hide = true)]
     GenerateReadmeMd,
 }
 
 #[derive(Args, Debug)]
 struct InfoArgs {
+    /// Only display statistics over all images
+    #[arg(short, long)]
+    summary: bool,
+
+    /// Only read EXIF data: files are not read again for image dimensions
+    #[arg(long)]
+    exif_only: bool,
+
+    /// Also display all EXIF tags (raw values)
+    #[arg(long)]
+    show_all_tags: bool,
+
+    /// Also display where values come from (like the description field), ImageUniqueID
+    /// and maker note presence
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Fields to display, in this order (comma separated list, text format only)
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FIELDS",
+        value_delimiter = ',',
+        default_value = "all"
+    )]
+    fields: Vec<InfoField>,
+
+    /// Dimensions to display (text format only)
+    #[arg(long, value_enum, value_name = "SOURCE", default_value_t = DimensionsSource::Decoded)]
+    dimensions_source: DimensionsSource,
+
+    /// Output format (table: one row per image)
+    #[arg(long, value_enum, default_value_t = InfoFormat::Text)]
+    format: InfoFormat,
+
+    /// Row order of --format table (input order by default)
+    #[arg(long, value_enum, value_name = "KEY", requires = "format")]
+    sort_by: Option<SortBy>,
+
+    /// Output one JSON object per line, as soon as each image is processed
+    #[arg(long, conflicts_with = "format")]
+    json_lines: bool,
+
+    /// Date display format: exif, iso or a strftime format (like %d/%m/%Y %H:%M)
+    #[arg(long, value_parser = DateFormat::parse, default_value = "exif")]
+    date_format: DateFormat,
+
+    /// Display file size in KiB, MiB, ... instead of bytes
+    #[arg(long)]
+    human: bool,
+
+    /// Focal length display unit
+    #[arg(long, value_enum, value_name = "UNIT", default_value_t = FocalUnit::Mm)]
+    focal_unit: FocalUnit,
+
+    /// Software stamp written by set or fix --stamp-software, not displayed as camera software
+    /// (the default stamp never is)
+    #[arg(long, value_name = "SOFTWARE")]
+    stamped_software: Option<String>,
+
+    /// Write info of each image as JSON in <file name>.json (like photo.jpg.json),
+    /// beside the image (existing files are skipped unless --force is used)
+    #[arg(
+        long,
+        conflicts_with_all = ["summary", "format", "json_lines", "compare_to_folder"]
+    )]
+    sidecar_report: bool,
+
+    /// Allows to overwrite existing sidecar reports (see --sidecar-report)
+    #[arg(short, long, requires = "sidecar_report")]
+    force: bool,
+
+    /// Compare images with the images of the same name in this folder (tag differences)
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with_all = ["summary", "format", "json_lines"]
+    )]
+    compare_to_folder: Option<std::path::PathBuf>,
+
+    /// With --compare-to-folder, also match images by EXIF date when names differ
+    #[arg(long, requires = "compare_to_folder")]
+    match_by_date: bool,
+
+    /// Also display this EXIF tag, like Artist or ExposureTime (can be repeated)
+    #[arg(long = "tag", value_name = "NAME", value_parser = metadata::named_tag::lookup)]
+    tags: Vec<ExifTag>,
+
     /// images to load
     #[clap(required = true, value_name = "IMAGES/FOLDERS")]
     files: Vec<std::path::PathBuf>,
@@ -59,6 +302,54 @@ struct SetArgs {
     #[arg(short, long)]
     force: bool,
 
+    /// Only set tags that are not already defined
+    #[arg(long)]
+    if_missing: bool,
+
+    /// Maximum length of the description (in chars)
+    #[arg(
+        long,
+        default_value_t = 255,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    max_description_length: usize,
+
+    /// Truncate too long descriptions instead of failing
+    #[arg(long)]
+    truncate: bool,
+
+    /// Also write --description to IPTC Caption-Abstract (JPEG only)
+    #[arg(long, requires = "description")]
+    iptc: bool,
+
+    /// Also write --description to the other description fields:
+    /// XPTitle and IPTC Caption-Abstract (JPEG only)
+    #[arg(long, requires = "description")]
+    mirror_description: bool,
+
+    /// Also update ModifyDate tag with --date
+    #[arg(long, requires = "date")]
+    also_modify_date: bool,
+
+    /// Also write GPSDateStamp and GPSTimeStamp with --gps:
+    /// image date, converted to UTC if its time offset is known
+    #[arg(long, requires = "gps")]
+    gps_time: bool,
+
+    /// Write Software tag when the image is modified
+    /// (default: PhotosNorm <version>, not displayed as camera software)
+    #[arg(
+        long,
+        value_name = "SOFTWARE",
+        num_args = 0..=1,
+        default_missing_value = metadata::SOFTWARE_STAMP
+    )]
+    stamp_software: Option<String>,
+
+    /// Encoding of the UserComment tag (see --comment)
+    #[arg(long, value_enum, default_value_t = CommentEncoding::Unicode)]
+    encoding: CommentEncoding,
+
     /// images to update
     #[clap(required = true, value_name = "IMAGES/FOLDERS")]
     files: Vec<std::path::PathBuf>,
@@ -66,13 +357,167 @@ struct SetArgs {
 #[derive(Args, Debug)]
 #[group(required = true, multiple = true)]
 struct SetArgsSetters {
-    /// Update ImageDescription tag (-t: title)
-    #[arg(short = 't', long)]
-    description: Option<String>,
+    /// Update ImageDescription tag (-t: title).
+    /// {camera}, {date}, {iso}, {aperture} and {focal} are replaced by the values of each image.
+    #[arg(short = 't', long, value_parser = Template::parse)]
+    description: Option<Template>,
 
-    /// Update DateTimeOriginal and CreateDate tags
+    /// Update DateTimeOriginal and CreateDate tags.
+    /// Accepts EXIF (%Y:%m:%d %H:%M:%S), ISO 8601 (%Y-%m-%d %H:%M:%S), %Y/%m/%d and date only formats,
+    /// now and today (at 00:00:00).
     #[arg(short, long)]
     date: Option<String>,
+
+    /// Shift dates of all images by the delta between the date of this reference image
+    /// and the --date value (the true date of the reference image).
+    #[arg(long, value_name = "FILE", requires = "date")]
+    date_offset_from: Option<std::path::PathBuf>,
+
+    /// Update UserComment tag (some viewers display it instead of ImageDescription)
+    #[arg(long)]
+    comment: Option<String>,
+
+    /// Update IPTC ObjectName (JPEG only)
+    #[arg(long)]
+    title: Option<String>,
+
+    /// Update IPTC Caption-Abstract (JPEG only)
+    #[arg(long)]
+    caption: Option<String>,
+
+    /// Update XPKeywords tag (comma separated list)
+    #[arg(short, long)]
+    keywords: Option<String>,
+
+    /// Update Rating and RatingPercent tags (0 to 5 stars)
+    #[arg(short, long, value_parser = clap::value_parser!(u16).range(0..=5))]
+    rating: Option<u16>,
+
+    /// Update GPS location (decimal degrees, like 43.604,1.444)
+    #[arg(
+        long,
+        value_name = "LAT,LON",
+        allow_hyphen_values = true,
+        value_parser = metadata::gps_info::parse_gps
+    )]
+    gps: Option<(f64, f64)>,
+
+    /// Remove tags (comma separated list)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    clear: Vec<ClearTag>,
+
+    /// Update any common EXIF tag, like Artist=John or ExposureTime=1/250 (can be repeated).
+    /// Orientation only updates the tag: the image is not rotated
+    #[arg(long = "tag", value_name = "NAME=VALUE", value_parser = metadata::named_tag::parse_assignment)]
+    tags: Vec<ExifTag>,
+
+    /// Apply the tags of a JSON file written by info --format json (null values are cleared).
+    /// An object applies to all images, an array to the images of the same name.
+    #[arg(long, value_name = "FILE")]
+    from_json: Option<std::path::PathBuf>,
+
+    /// Set ImageUniqueID from the image content (dimensions, date and pixels) if not already set.
+    /// Copies of an image get the same ID: they are still found by dedup once renamed.
+    #[arg(long)]
+    unique_id: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Interactive {
+    /// Only if standard input is a terminal
+    Auto,
+    /// Even if standard input is not a terminal (answers are read from it)
+    Always,
+}
+
+// Confirmation state of --interactive
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Prompt {
+    Off,
+    Ask,
+    Quit,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum InfoFormat {
+    Text,
+    Json,
+    Table,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum DimensionsSource {
+    /// Dimensions of the image data
+    Decoded,
+    /// ExifImageWidth/Height tags
+    Exif,
+    /// Both, EXIF ones in yellow if they differ (see fix --dimensions)
+    Both,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum SortBy {
+    Name,
+    Date,
+    Iso,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum InfoField {
+    /// All fields, in this order
+    All,
+    Dimensions,
+    Filesize,
+    Date,
+    Description,
+    Comment,
+    Title,
+    Caption,
+    Keywords,
+    Rating,
+    Camera,
+    Gps,
+    Sidecar,
+}
+
+const INFO_FIELDS: [InfoField; 12] = [
+    InfoField::Dimensions,
+    InfoField::Filesize,
+    InfoField::Date,
+    InfoField::Description,
+    InfoField::Comment,
+    InfoField::Title,
+    InfoField::Caption,
+    InfoField::Keywords,
+    InfoField::Rating,
+    InfoField::Camera,
+    InfoField::Gps,
+    InfoField::Sidecar,
+];
+
+// A row of info --format table, with its sort keys
+struct InfoRow {
+    name: String,
+    date: Option<chrono::NaiveDateTime>,
+    iso: Option<u16>,
+    cells: Vec<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ClearTag {
+    Description,
+    Comment,
+    Title,
+    Caption,
+    Date,
+    Gps,
+    Rating,
 }
 
 #[derive(Args, Debug)]
@@ -89,11 +534,94 @@ struct FixArgs {
     #[command(flatten)]
     setters: FixArgsFixers,
 
+    /// What to do when the new file name (see --name) is already used
+    #[arg(long, value_enum, default_value_t = Conflict::Number)]
+    on_conflict: Conflict,
+
+    /// Dimensions written to ExifImageWidth/Height of images rotated by 90° (see --dimensions)
+    #[arg(long, value_enum, default_value_t = Dims::Stored)]
+    dims: Dims,
+
+    /// Keep the original file name as suffix when renaming (see --name):
+    /// DSC_0123.jpg is renamed to %Y_%m_%d-%H_%M_%S[ - %description] - DSC_0123.jpg
+    #[arg(long)]
+    keep_original_name: bool,
+
+    /// Rename to PREFIX_001, PREFIX_002... in date order instead of %Y_%m_%d-%H_%M_%S (see --name).
+    /// The counter width depends on the number of images. Images without date are not renamed.
+    /// When numbers shift (like after adding an earlier image), no image replaces another one.
+    #[arg(long, value_name = "PREFIX")]
+    sequence: Option<String>,
+
+    /// Write the extension in lowercase and canonical form when renaming (see --name):
+    /// PHOTO.JPEG is renamed to %Y_%m_%d-%H_%M_%S[ - %description].jpg
+    #[arg(long)]
+    normalize_ext: bool,
+
+    /// Transliterate descriptions to ASCII in new file names (see --name), like é to e
+    #[arg(long)]
+    ascii_only: bool,
+
+    /// Replace spaces by this character in new file names (see --name)
+    #[arg(long, value_name = "CHAR")]
+    replace_spaces: Option<char>,
+
+    /// Truncate the description part of new file names to this number of characters (see --name)
+    #[arg(long, value_name = "LENGTH")]
+    max_name_description: Option<usize>,
+
+    /// Check orientation against image dimensions before rotating (see --orientation):
+    /// a 90° orientation of an image stored in landscape is suspicious
+    #[arg(
+        long,
+        value_enum,
+        value_name = "ACTION",
+        num_args = 0..=1,
+        default_missing_value = "warn"
+    )]
+    verify_dims: Option<VerifyDims>,
+
+    /// Write rotated images directly, without temporary file (see --orientation):
+    /// less disk writes, but an interrupted write damages the image
+    #[arg(long)]
+    in_memory: bool,
+
+    /// Write Software tag when the image is modified
+    /// (default: PhotosNorm <version>, not displayed as camera software)
+    #[arg(
+        long,
+        value_name = "SOFTWARE",
+        num_args = 0..=1,
+        default_missing_value = metadata::SOFTWARE_STAMP
+    )]
+    stamp_software: Option<String>,
+
+    /// Allows to overwrite existing EXIF date, existing description with --keywords-to-description,
+    /// to remove description with --migrate-keywords (and existing files with --on-conflict overwrite)
+    #[arg(short, long)]
+    force: bool,
+
     /// images to fix
     #[clap(required = true, value_name = "IMAGES/FOLDERS")]
     files: Vec<std::path::PathBuf>,
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum VerifyDims {
+    /// Only warn about suspicious orientations
+    Warn,
+    /// Warn and do not rotate these images
+    Skip,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum ClampDate {
+    /// Date from file name (like IMG_20061029_162721.jpg)
+    Name,
+    /// File modification time (local time)
+    Mtime,
+}
+
 #[derive(Args, Debug)]
 #[group(id = "fixers", required = false, multiple = true)]
 struct FixArgsFixers {
@@ -107,161 +635,1819 @@ struct FixArgsFixers {
     name: bool,
 
     /// Fix image orientation (lossless rotate the image).
-    /// Only JPEG files are supported.
+    /// Only JPEG and TIFF files are supported.
     #[arg(short, long)]
     orientation: bool,
-}
 
-macro_rules! print_table {
-    ($input1:expr, $input2:expr) => {
-        println!("{0:<15} {1:}", $input1, $input2);
-    };
+    /// Convert date to UTC according to the OffsetTimeOriginal tag.
+    /// Not included in --all.
+    #[arg(short, long)]
+    utc: bool,
+
+    /// Set date from file name (like IMG_20061029_162721.jpg) if there is no EXIF date.
+    /// Not included in --all.
+    #[arg(long)]
+    date_from_name: bool,
+
+    /// Set date from file modification time (local time) if there is no EXIF date.
+    /// Not included in --all.
+    #[arg(long, conflicts_with = "date_from_name")]
+    date_from_mtime: bool,
+
+    /// Replace implausible dates (see --min-year) by the date of the file name or
+    /// of the file modification time. Not included in --all.
+    #[arg(long, value_enum, value_name = "SOURCE")]
+    clamp_date: Option<ClampDate>,
+
+    /// Set both DateTimeOriginal and CreateDate to the image date (see --date-source).
+    /// Not included in --all.
+    #[arg(long)]
+    sync_dates: bool,
+
+    /// Remove trailing NUL bytes and spaces of string tags (like Make, Model or ImageDescription).
+    /// Not included in --all.
+    #[arg(long)]
+    trim_strings: bool,
+
+    /// Move ImageDescription into XPKeywords when it looks like a keyword list
+    /// (at least 3 short comma separated keywords, like "beach, 2006, family").
+    /// The description is removed: requires --force. Not included in --all.
+    #[arg(long)]
+    migrate_keywords: bool,
+
+    /// Move XPKeywords into ImageDescription (comma separated list).
+    /// An existing description is only replaced with --force. Not included in --all.
+    #[arg(long, conflicts_with = "migrate_keywords")]
+    keywords_to_description: bool,
+
+    /// Set file modification time to the image date.
+    /// Not included in --all.
+    #[arg(long)]
+    mtime: bool,
 }
 
-fn main() -> Result<(), std::io::Error> {
-    let args = Cli::parse();
+#[derive(Args, Debug)]
+struct SortArgs {
+    /// Target folder
+    #[arg(short = 'o', long, value_name = "DIR")]
+    into: std::path::PathBuf,
 
-    // Parse command and grab file list
-    let files = match &args.command {
-        Commands::Info(args) => &args.files,
-        Commands::Set(args) => &args.files,
-        Commands::Fix(args) => &args.files,
-        Commands::GenerateReadmeMd => {
-            let readme_text = clap_markdown::help_markdown_command_custom(
-                &Cli::command(),
-                &MarkdownOptions::new()
-                    .title(CARGO_PKG_NAME.to_string())
-                    .show_footer(false)
-                    .show_table_of_contents(true),
-            );
-            fs::write("README.md", readme_text).expect("Unable to write README.md");
-            return Ok(());
-        }
-    };
+    /// Sub-folder layout (strftime format). Images without date go into 'undated'
+    #[arg(short, long, default_value = "%Y/%m")]
+    layout: String,
 
-    // list images from file list (aka read folders)
-    let mut images: Vec<std::path::PathBuf> = Vec::new();
-    for file in files.iter() {
-        if !file.is_dir() {
-            images.push(file.to_path_buf());
-        } else {
-            match fs::read_dir(file) {
-                // Let open display the error and process next file.
-                Err(_) => images.push(file.to_path_buf()),
-                // Add all files to image list
-                Ok(files) => {
-                    for entry in files {
-                        let file = entry.unwrap().path();
-                        // non-recursive
-                        if file.is_file() {
-                            images.push(file.to_path_buf());
-                        }
-                    }
-                }
-            }
-        }
-    }
+    /// Also fix file name to %Y_%m_%d-%H_%M_%S[ - %description]
+    #[arg(short, long)]
+    name: bool,
 
-    // Check parameters
-    if let Commands::Set(ref args) = args.command {
-        if !args.force && images.len() != 1 {
-            panic!("{}: Setting same tag values to several images is not allowed unless you use {} option.", "error".red(), "--force".yellow());
-        }
-    }
+    /// Copy images instead of moving them
+    #[arg(short, long)]
+    copy: bool,
 
-    // Process all images
-    for image in images.iter() {
-        print_table!("File:", image.display());
+    /// images to sort
+    #[clap(required = true, value_name = "IMAGES/FOLDERS")]
+    files: Vec<std::path::PathBuf>,
+}
 
-        let result = Metadata::new(image);
-        if result.is_err() {
-            print_table!("Error!".red(), result.err().expect("Unexpected error."));
-            println!();
-            continue;
-        }
+#[derive(Args, Debug)]
+struct AnalyzeArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
 
-        let mut metadata = result.unwrap();
+    /// images to analyze
+    #[clap(required = true, value_name = "IMAGES/FOLDERS")]
+    files: Vec<std::path::PathBuf>,
+}
 
-        match &args.command {
-            //
-            // Command info
-            //
-            Commands::Info(_) => {
-                print_table!(
-                    "Dimensions:",
-                    format!("{}, {}", metadata.width(), metadata.height())
-                );
-                print_table!(
-                    "Date:",
-                    metadata
-                        .exif_date()
-                        .unwrap_or("{No exif date!}".yellow().to_string())
-                );
-                print_table!(
-                    "Desription:",
-                    metadata
-                        .description()
-                        .unwrap_or("{No exif description!}".yellow().to_string())
-                );
-                print_table!("Camera:", metadata.camera_info());
-            }
+#[derive(Args, Debug)]
+struct ExtractThumbnailArgs {
+    /// Target folder
+    #[arg(short = 'o', long, value_name = "DIR")]
+    into: std::path::PathBuf,
 
-            //
-            // Command set
-            //
-            Commands::Set(args) => {
-                if args.setters.description.is_some() {
-                    metadata.set_description(args.setters.description.as_ref().unwrap());
-                }
-                if args.setters.date.is_some() {
-                    let result = metadata
-                        .set_date_from_exif(args.setters.date.as_ref().unwrap().to_string());
-                    if result.is_err() {
-                        panic!(
-                            "{}: Cannot parse date: '{}': {}!",
-                            "error".red(),
-                            args.setters.date.as_ref().unwrap().yellow(),
-                            result.err().unwrap()
-                        );
-                    }
-                }
+    /// Rotate thumbnails according to the image orientation
+    #[arg(short, long)]
+    rotate: bool,
 
-                match metadata.save() {
-                    Err(e) => {
-                        print_table!("Error!".red(), e);
-                    }
-                    Ok(tags) => {
-                        print_table!("Updated tags:", metadata.tags_to_string(&tags));
-                    }
-                }
-            }
-            Commands::Fix(args) => {
-                if args.all || args.setters.dimensions {
-                    metadata.fix_dimentions();
-                }
-                if args.all || args.setters.name {
-                    metadata.fix_file_name();
-                }
-                if args.all || args.setters.orientation {
-                    metadata.fix_orientation();
-                }
-                match metadata.save() {
-                    Err(e) => {
-                        print_table!("Error!".red(), e);
-                    }
-                    Ok(tags) => {
-                        print_table!("Updated tags:", metadata.tags_to_string(&tags));
-                    }
-                }
-            }
+    /// images to read
+    #[clap(required = true, value_name = "IMAGES/FOLDERS")]
+    files: Vec<std::path::PathBuf>,
+}
 
-            Commands::GenerateReadmeMd => {
-                panic!("Cannot reach this code!");
-            }
-        }
+#[derive(Args, Debug)]
+struct StripArgs {
+    /// Keep these tags, remove all other ones (comma separated list)
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        required_unless_present = "only",
+        conflicts_with = "only"
+    )]
+    except: Vec<StripTag>,
 
-        println!();
+    /// Only remove these tags (comma separated list)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    only: Vec<StripTag>,
+
+    /// images to strip
+    #[clap(required = true, value_name = "IMAGES/FOLDERS")]
+    files: Vec<std::path::PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum StripTag {
+    Description,
+    Comment,
+    Keywords,
+    Date,
+    Gps,
+    Rating,
+    Orientation,
+    Dimensions,
+    #[value(name = "makernote")]
+    MakerNote,
+}
+
+impl StripTag {
+    fn tag(self) -> Tag {
+        match self {
+            StripTag::Description => Tag::Description,
+            StripTag::Comment => Tag::Comment,
+            StripTag::Keywords => Tag::Keywords,
+            StripTag::Date => Tag::Date,
+            StripTag::Gps => Tag::Gps,
+            StripTag::Rating => Tag::Rating,
+            StripTag::Orientation => Tag::Orientation,
+            StripTag::Dimensions => Tag::Dimensions,
+            StripTag::MakerNote => Tag::MakerNote,
+        }
     }
+}
 
-    Ok(())
+#[derive(Args, Debug)]
+struct DedupArgs {
+    /// Remove duplicates, keeping the first image of each group
+    #[arg(long)]
+    delete_extras: bool,
+
+    /// images to compare
+    #[clap(required = true, value_name = "IMAGES/FOLDERS")]
+    files: Vec<std::path::PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct UndoArgs {
+    /// journal written with --journal
+    #[clap(required = true, value_name = "JOURNAL")]
+    journal: std::path::PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct DiffArgs {
+    /// first image
+    #[clap(required = true, value_name = "IMAGE_A")]
+    a: std::path::PathBuf,
+
+    /// second image
+    #[clap(required = true, value_name = "IMAGE_B")]
+    b: std::path::PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct WatchArgs {
+    /// Seconds a new file must stay unchanged before being processed
+    /// (files still being copied are not processed)
+    #[arg(long, value_name = "SECONDS", default_value_t = 2)]
+    settle: u64,
+
+    /// folder to watch (non-recursive)
+    #[clap(required = true, value_name = "FOLDER")]
+    folder: std::path::PathBuf,
+
+    /// set or fix command with its options, run on each new image, like: -- fix --name.
+    /// Global options (like --journal) are also given to it.
+    #[arg(last = true, required = true, value_name = "COMMAND")]
+    command: Vec<String>,
+}
+
+macro_rules! print_table {
+    ($input1:expr, $input2:expr) => {
+        table::print_row($input1, $input2);
+    };
+}
+
+// Colors are disabled by --no-color, NO_COLOR environment variable (https://no-color.org)
+// or when output is not a terminal
+fn use_colors(no_color: bool) -> bool {
+    !no_color
+        && !std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        && std::io::stdout().is_terminal()
+}
+
+// Setting the same tag values to several images requires force
+fn validate_set_targets(force: bool, count: usize) -> Result<(), String> {
+    if !force && count > 1 {
+        return Err(format!(
+            "Setting same tag values to several images is not allowed unless you use {} option.",
+            "--force".yellow()
+        ));
+    }
+    Ok(())
+}
+
+// Report an argument error and exit
+fn usage_error(message: String) -> ! {
+    eprintln!("{}: {}", "error".red(), message);
+    std::process::exit(EXIT_USAGE);
+}
+
+// Metadata as displayed by info --format json
+fn info_to_json(metadata: &Metadata) -> serde_json::Value {
+    // null if dimensions cannot be read (or with --exif-only)
+    let dimensions = metadata.dimensions().ok();
+    json!({
+        "file": metadata.path(),
+        "width": dimensions.map(|(width, _)| width),
+        "height": dimensions.map(|(_, height)| height),
+        "bit_depth": metadata.bit_depth(),
+        "date": metadata.exif_date(),
+        "offset": metadata.offset().map(|offset| offset.to_string()),
+        "description": metadata.description(),
+        "comment": metadata.user_comment(),
+        "title": metadata.title(),
+        "caption": metadata.caption(),
+        "keywords": metadata.keywords(),
+        "rating": metadata.rating(),
+        "camera": metadata.camera_info().to_string(),
+        "gps": gps_to_json(metadata.gps_info()),
+    })
+}
+
+// GPS location in decimal degrees (meters for altitude), null if undefined
+fn gps_to_json(gps_info: &GpsInfo) -> serde_json::Value {
+    if gps_info.is_empty() {
+        return serde_json::Value::Null;
+    }
+    json!({
+        "latitude": gps_info.latitude,
+        "longitude": gps_info.longitude,
+        "altitude": gps_info.altitude,
+        "direction": gps_info.direction,
+    })
+}
+
+// Output a JSON object: immediately (JSON Lines) or at the end (JSON array)
+fn output_json(
+    value: serde_json::Value,
+    json_lines: bool,
+    json_output: &mut Vec<serde_json::Value>,
+) -> Result<(), std::io::Error> {
+    if json_lines {
+        // One write per object, lines are never interleaved
+        writeln!(std::io::stdout().lock(), "{}", value)?;
+    } else {
+        json_output.push(value);
+    }
+    Ok(())
+}
+
+// Display planned changes and ask for confirmation: y(es), a(ll), n(o) or q(uit).
+// Return true if changes can be saved.
+fn confirm(metadata: &Metadata, prompt: &mut Prompt) -> bool {
+    if *prompt != Prompt::Ask {
+        return *prompt == Prompt::Off;
+    }
+    let (tags, target) = metadata.planned_changes();
+    if tags.is_empty() {
+        return true;
+    }
+    print_table!("Planned tags:", metadata.tags_to_string(&tags));
+    if let Some(target) = target {
+        print_table!(
+            "Planned path:",
+            format!("{} -> {}", metadata.path().display(), target.display())
+        );
+    }
+    loop {
+        print!("Apply? [y/a/n/q] ");
+        let _ = std::io::stdout().flush();
+        let mut answer = String::new();
+        if !matches!(std::io::stdin().read_line(&mut answer), Ok(n) if n > 0) {
+            // End of input: stop asking and do nothing more
+            *prompt = Prompt::Quit;
+            return false;
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => return true,
+            "a" | "all" => {
+                *prompt = Prompt::Off;
+                return true;
+            }
+            "n" | "no" => return false,
+            "q" | "quit" => {
+                *prompt = Prompt::Quit;
+                return false;
+            }
+            _ => (),
+        }
+    }
+}
+
+// Ask a yes/no question. Return false on end of input or if standard input
+// is not a terminal (nobody to answer).
+fn ask(question: &str) -> bool {
+    if !std::io::stdin().is_terminal() {
+        return false;
+    }
+    loop {
+        print!("{} ", question);
+        let _ = std::io::stdout().flush();
+        let mut answer = String::new();
+        if !matches!(std::io::stdin().read_line(&mut answer), Ok(n) if n > 0) {
+            return false;
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => return true,
+            "n" | "no" => return false,
+            _ => (),
+        }
+    }
+}
+
+// Save metadata, display updated tags and record them in journal and log.
+// Return false on error.
+fn save(
+    metadata: &mut Metadata,
+    mut entry: journal::Entry,
+    journal: &Option<std::path::PathBuf>,
+    log: &Option<AuditLog>,
+    prompt: &mut Prompt,
+) -> bool {
+    if !confirm(metadata, prompt) {
+        print_table!("Skipped:".yellow(), "Changes not confirmed.");
+        return true;
+    }
+    match metadata.save() {
+        Err(e) => {
+            print_table!("Error!".red(), e);
+            false
+        }
+        Ok(tags) => {
+            print_table!("Updated tags:", metadata.tags_to_string(&tags));
+            if metadata.maker_note_dropped() {
+                print_table!(
+                    "Warning:".yellow(),
+                    "Maker notes have not been written back, camera specific data is lost."
+                );
+            }
+            if metadata.compression_dropped() {
+                print_table!(
+                    "Warning:".yellow(),
+                    "Rotated TIFF image has been written back uncompressed."
+                );
+            }
+            // The file is saved: record it for undo first, the log is informative only
+            if let Some(journal) = journal {
+                if !tags.is_empty() {
+                    entry.saved(metadata, &tags);
+                    if let Err(e) = journal::append(journal, &entry) {
+                        print_table!("Error!".red(), format!("Cannot write journal: {}", e));
+                        return false;
+                    }
+                }
+            }
+            if let Some(log) = log {
+                if let Err(e) = log.record(&entry, &journal::Entry::new(metadata), &tags) {
+                    print_table!("Warning:".yellow(), format!("Cannot write log: {}", e));
+                }
+            }
+            true
+        }
+    }
+}
+
+// Display tags that differ between two images
+fn diff(a: &std::path::Path, b: &std::path::Path) -> Result<(), std::io::Error> {
+    let diffs = metadata::diff::diff_files(a, b)?;
+    println!(
+        "{0:<2}{1:<28} {2:<30} {3:}",
+        "",
+        "Tag",
+        a.display(),
+        b.display()
+    );
+    print_tag_diffs(&diffs);
+    if diffs.is_empty() {
+        println!("No differences.");
+    }
+    Ok(())
+}
+
+// Display tag differences: added (+), removed (-) and changed (~) tags
+fn print_tag_diffs(diffs: &[TagDiff]) {
+    for diff in diffs.iter() {
+        let undefined = "-".to_string();
+        let line = format!(
+            "{0:<2}{1:<28} {2:<30} {3:}",
+            if diff.is_added() {
+                "+"
+            } else if diff.is_removed() {
+                "-"
+            } else {
+                "~"
+            },
+            diff.name,
+            diff.a.as_ref().unwrap_or(&undefined),
+            diff.b.as_ref().unwrap_or(&undefined)
+        );
+        if diff.is_added() {
+            println!("{}", line.green());
+        } else if diff.is_removed() {
+            println!("{}", line.red());
+        } else {
+            println!("{}", line.yellow());
+        }
+    }
+}
+
+// Undo all journal modifications, last one first
+fn undo(journal: &std::path::Path) -> Result<(), std::io::Error> {
+    let entries = journal::read(journal)?;
+    for entry in entries.iter().rev() {
+        print_table!("File:", entry.new_path.display());
+        match journal::undo(entry) {
+            Err(e) => {
+                print_table!("Skipped:".yellow(), e);
+            }
+            Ok(tags) => {
+                print_table!(
+                    "Restored tags:",
+                    if tags.is_empty() {
+                        "None".to_string()
+                    } else {
+                        tags.iter()
+                            .map(|t| t.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    }
+                );
+            }
+        }
+        println!();
+    }
+    Ok(())
+}
+
+// Run the command on each new image of the folder, until Ctrl-C
+// Global options given on the command line, to be given again to another command
+fn global_options() -> Vec<std::ffi::OsString> {
+    let command = Cli::command();
+    let matches = command.clone().get_matches();
+    let mut options = Vec::new();
+    for arg in command.get_arguments().filter(|arg| arg.is_global_set()) {
+        let id = arg.get_id().as_str();
+        if matches.value_source(id) != Some(ValueSource::CommandLine) {
+            continue;
+        }
+        let Some(long) = arg.get_long() else {
+            continue;
+        };
+        match matches.get_raw(id) {
+            Some(values) if arg.get_action().takes_values() => {
+                for value in values {
+                    let mut option = std::ffi::OsString::from(format!("--{}=", long));
+                    option.push(value);
+                    options.push(option);
+                }
+            }
+            _ => options.push(format!("--{}", long).into()),
+        }
+    }
+    options
+}
+
+fn watch(args: &WatchArgs) -> Result<(), std::io::Error> {
+    // Global options (like --journal) are given to the command
+    let options = global_options();
+
+    // Check the command once, as it will be run for each image
+    let check = std::iter::once(CARGO_PKG_NAME.into())
+        .chain(args.command.iter().map(std::ffi::OsString::from))
+        .chain(options.iter().cloned())
+        .chain(std::iter::once(args.folder.clone().into_os_string()));
+    match Cli::try_parse_from(check) {
+        Err(e) => usage_error(format!("Invalid watch command: {}", e)),
+        Ok(Cli {
+            command: Commands::Set(_) | Commands::Fix(_),
+            ..
+        }) => (),
+        Ok(_) => usage_error("Only set and fix commands can be watched.".to_string()),
+    }
+
+    // Images are processed by a new process, like from the command line:
+    // Ctrl-C is also received by this process that completes the current file
+    let exe = std::env::current_exe()?;
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let handler_interrupted = interrupted.clone();
+    ctrlc::set_handler(move || handler_interrupted.store(true, Ordering::SeqCst))
+        .map_err(std::io::Error::other)?;
+    let mut watcher =
+        watch::FolderWatcher::new(&args.folder, std::time::Duration::from_secs(args.settle))?;
+    print_table!("Watching:", args.folder.display());
+    println!();
+
+    let mut processed = 0;
+    let mut errors = 0;
+    while !interrupted.load(Ordering::SeqCst) {
+        for image in watcher.ready(std::time::Duration::from_millis(500))? {
+            if interrupted.load(Ordering::SeqCst) {
+                break;
+            }
+            // Files written by the command are not new files
+            let before = watcher.files()?;
+            let status = std::process::Command::new(&exe)
+                .args(&args.command)
+                .args(&options)
+                .arg(&image)
+                .status()?;
+            watcher.ignore_changes(&before)?;
+            processed += 1;
+            if !status.success() {
+                errors += 1;
+            }
+        }
+    }
+    print_table!("Processed:", processed);
+    if errors > 0 {
+        print_table!("Errors:".red(), errors);
+    }
+    std::process::exit(EXIT_INTERRUPTED);
+}
+
+fn main() -> Result<(), std::io::Error> {
+    let args = Cli::parse();
+    if !use_colors(args.no_color) {
+        colored::control::set_override(false);
+    }
+
+    // Parse command and grab file list
+    let files = match &args.command {
+        Commands::Info(args) => &args.files,
+        Commands::Set(args) => &args.files,
+        Commands::Fix(args) => &args.files,
+        Commands::Sort(args) => &args.files,
+        Commands::Dedup(args) => &args.files,
+        Commands::Analyze(args) => &args.files,
+        Commands::ExtractThumbnail(args) => &args.files,
+        Commands::Strip(args) => &args.files,
+        Commands::Undo(args) => return undo(&args.journal),
+        Commands::Diff(args) => return diff(&args.a, &args.b),
+        Commands::Watch(args) => return watch(args),
+        Commands::GenerateReadmeMd => {
+            let readme_text = clap_markdown::help_markdown_command_custom(
+                &Cli::command(),
+                &MarkdownOptions::new()
+                    .title(CARGO_PKG_NAME.to_string())
+                    .show_footer(false)
+                    .show_table_of_contents(true),
+            );
+            fs::write("README.md", readme_text).expect("Unable to write README.md");
+            return Ok(());
+        }
+    };
+
+    // Canonical --confine folders
+    let confine: Vec<std::path::PathBuf> = args
+        .confine
+        .iter()
+        .map(|root| {
+            fs::canonicalize(root).unwrap_or_else(|e| {
+                usage_error(format!(
+                    "Invalid --confine folder '{}': {}!",
+                    root.display().to_string().yellow(),
+                    e
+                ))
+            })
+        })
+        .collect();
+
+    // list images from file list (aka read folders)
+    let (mut images, skipped, unmatched) = file_list::expand(files, args.follow_symlinks);
+    file_list::exclude(&mut images, &args.exclude);
+    let json_lines = matches!(&args.command, Commands::Info(args) if args.json_lines);
+    let json = json_lines
+        || matches!(&args.command, Commands::Info(args) if args.format == InfoFormat::Json)
+        || matches!(&args.command, Commands::Analyze(args) if args.format == OutputFormat::Json);
+    let mut json_output: Vec<serde_json::Value> = Vec::new();
+    for pattern in unmatched.iter() {
+        if json {
+            output_json(
+                json!({"file": pattern, "error": "No files matched."}),
+                json_lines,
+                &mut json_output,
+            )?;
+            continue;
+        }
+        print_table!("File:", pattern.display());
+        print_table!("Skipped:".yellow(), "No files matched.");
+        println!();
+    }
+    for link in skipped.iter() {
+        if json {
+            output_json(
+                json!({"file": link, "error": "Symbolic link to a folder."}),
+                json_lines,
+                &mut json_output,
+            )?;
+            continue;
+        }
+        print_table!("File:", link.display());
+        print_table!(
+            "Skipped:".yellow(),
+            format!(
+                "Symbolic link to a folder. Use {} to process it.",
+                "--follow-symlinks".yellow()
+            )
+        );
+        println!();
+    }
+    // Refused files are reported once filtered out
+    let mut refused = Vec::new();
+    if !confine.is_empty() {
+        images.retain(|image| match file_list::check_confined(image, &confine) {
+            Ok(()) => true,
+            Err(e) => {
+                refused.push((image.clone(), e));
+                false
+            }
+        });
+    }
+    for (image, e) in refused.iter() {
+        if json {
+            output_json(
+                json!({"file": image, "error": e.to_string()}),
+                json_lines,
+                &mut json_output,
+            )?;
+        } else {
+            print_table!("File:", image.display());
+            print_table!("Error!".red(), e);
+            println!();
+        }
+    }
+    let refused = refused.len();
+    if images.is_empty() {
+        if json && !json_lines {
+            println!("{}", serde_json::Value::from(json_output));
+        }
+        if refused > 0 {
+            std::process::exit(EXIT_FILE_ERRORS);
+        }
+        eprintln!("{}: No input files.", "error".red());
+        std::process::exit(EXIT_NO_INPUT);
+    }
+
+    // Check parameters
+    let json_patches = match &args.command {
+        Commands::Set(SetArgs {
+            setters:
+                SetArgsSetters {
+                    from_json: Some(path),
+                    ..
+                },
+            ..
+        }) => Some(
+            JsonPatches::load(path)
+                .unwrap_or_else(|e| usage_error(format!("Cannot read {}: {}", path.display(), e))),
+        ),
+        _ => None,
+    };
+    let args_date_source = args.date_source;
+    let args_min_year = args.min_year;
+    let args_year_pivot = args.year_pivot;
+    let mut date_offset = None;
+    let mut set_date = None;
+    if let Commands::Set(ref args) = args.command {
+        let shift_only = args.setters.description.is_none()
+            && args.setters.comment.is_none()
+            && args.setters.title.is_none()
+            && args.setters.caption.is_none()
+            && args.setters.keywords.is_none()
+            && args.setters.rating.is_none()
+            && args.setters.gps.is_none();
+        if let Some(reference) = &args.setters.date_offset_from {
+            let result = Metadata::new_with_date_source(reference, args_date_source).and_then(
+                |mut metadata| {
+                    metadata.set_year_pivot(args_year_pivot);
+                    metadata.date_offset_to(args.setters.date.as_ref().unwrap())
+                },
+            );
+            match result {
+                Err(e) => usage_error(format!(
+                    "Invalid reference image '{}': {}!",
+                    reference.display().to_string().yellow(),
+                    e
+                )),
+                Ok(delta) => date_offset = Some(delta),
+            }
+        } else if let Some(date) = &args.setters.date {
+            match metadata::parse_date_flexible(date) {
+                Err(e) => usage_error(format!("Cannot parse date: '{}': {}!", date.yellow(), e)),
+                Ok(date) => set_date = Some(date),
+            }
+        }
+        if let Some(comment) = &args.setters.comment {
+            if let Err(e) = Metadata::check_user_comment(comment, args.encoding) {
+                usage_error(e.to_string());
+            }
+        }
+        // Descriptions with placeholders are checked for each image
+        if let Some(description) = args.setters.description.as_ref().and_then(Template::text) {
+            if !args.truncate {
+                if let Err(e) =
+                    Metadata::check_description_length(&description, args.max_description_length)
+                {
+                    usage_error(format!("{} Use {} option.", e, "--truncate".yellow()));
+                }
+            }
+        }
+        // A JSON array holds the values of each image, unique IDs are computed for each image
+        let per_image = shift_only
+            && args.setters.date.is_none()
+            && args.setters.tags.is_empty()
+            && args.setters.clear.is_empty()
+            && json_patches
+                .as_ref()
+                .map_or(args.setters.unique_id, JsonPatches::per_image);
+        let force = args.force || (date_offset.is_some() && shift_only) || per_image;
+        if let Err(e) = validate_set_targets(force, images.len()) {
+            usage_error(e);
+        }
+    }
+
+    // --sequence is a --name mode (included in --all)
+    if let Commands::Fix(ref args) = args.command {
+        if args.sequence.is_some() && !args.all && !args.setters.name {
+            usage_error(format!(
+                "{} requires {} or {}.",
+                "--sequence".yellow(),
+                "--name".yellow(),
+                "--all".yellow()
+            ));
+        }
+    }
+
+    if let Commands::Sort(ref args) = args.command {
+        if StrftimeItems::new(&args.layout).any(|item| matches!(item, Item::Error)) {
+            usage_error(format!("Invalid layout: '{}'!", args.layout.yellow()));
+        }
+    }
+
+    // Process all images
+    let args_journal = args.journal.clone();
+    let log = args.log_file.as_ref().map(|path| {
+        AuditLog::open(path).unwrap_or_else(|e| {
+            usage_error(format!("Cannot open log file {}: {}", path.display(), e))
+        })
+    });
+    let mut prompt = match args.interactive {
+        None => Prompt::Off,
+        // Answers would be mixed with the JSON output
+        Some(_) if json => usage_error(format!(
+            "{} cannot be used with JSON output.",
+            "--interactive".yellow()
+        )),
+        Some(Interactive::Always) => Prompt::Ask,
+        Some(Interactive::Auto) if std::io::stdin().is_terminal() => Prompt::Ask,
+        Some(Interactive::Auto) => {
+            eprintln!(
+                "{}: Standard input is not a terminal, {} ignored.",
+                "warning".yellow(),
+                "--interactive".yellow()
+            );
+            Prompt::Off
+        }
+    };
+    let mut signatures: Vec<(std::path::PathBuf, dedup::Signature)> = Vec::new();
+    let mut summary = Summary::default();
+    let summary_only = matches!(&args.command, Commands::Info(args) if args.summary);
+    let exif_only = matches!(&args.command, Commands::Info(args) if args.exif_only);
+    let load = |image: &std::path::Path| {
+        if exif_only {
+            Metadata::new_exif_only(image, args.date_source)
+        } else {
+            Metadata::new_with_date_source(image, args.date_source)
+        }
+    };
+    // Options applied to each loaded image, before filtering it
+    let prepare = |mut metadata: Metadata| {
+        metadata.set_min_year(args.min_year);
+        metadata.set_year_pivot(args.year_pivot);
+        if args.prefer_xmp {
+            metadata.prefer_xmp();
+        }
+        if let Commands::Info(InfoArgs {
+            stamped_software: Some(software),
+            ..
+        }) = &args.command
+        {
+            metadata.hide_software(software);
+        }
+        metadata
+    };
+    let grid = matches!(&args.command, Commands::Info(args) if args.format == InfoFormat::Table);
+    let mut info_rows: Vec<InfoRow> = Vec::new();
+    let mut analysis = Analysis::default();
+    let analyze = matches!(&args.command, Commands::Analyze(_));
+    let mut errors = refused;
+    let reference = match &args.command {
+        Commands::Info(InfoArgs {
+            compare_to_folder: Some(folder),
+            match_by_date,
+            ..
+        }) => Some(ReferenceFolder::load(
+            folder,
+            *match_by_date,
+            args.year_pivot,
+        )),
+        _ => None,
+    };
+    let mut drift = DriftSummary::default();
+    // On Ctrl-C, stop after the current file: a file is never left half-written
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let handler_interrupted = interrupted.clone();
+    if let Err(e) = ctrlc::set_handler(move || handler_interrupted.store(true, Ordering::SeqCst)) {
+        eprintln!("{}: Cannot handle Ctrl-C: {}", "warning".yellow(), e);
+    }
+    let mut processed = images.len();
+
+    // Processing is sequential: results are written as soon as available.
+    // Each image is read into memory and its file closed before being processed:
+    // a few files at most are open at once, whatever the number of images.
+    let mut lines = OrderedWriter::new(std::io::stdout(), false);
+
+    // fix --sequence numbers the whole set in date order: images are loaded first
+    // (and kept to be processed). When an image is numbered to the current name
+    // of another image (like after adding an earlier image), the latter is renamed first.
+    let mut loaded: Vec<Option<Result<Metadata, std::io::Error>>> = Vec::new();
+    let mut sequence_names = Vec::new();
+    let mut sequence_blocked = Vec::new();
+    let mut order: Vec<usize> = (0..images.len()).collect();
+    if let Commands::Fix(FixArgs {
+        sequence: Some(prefix),
+        normalize_ext,
+        ..
+    }) = &args.command
+    {
+        loaded = images
+            .iter()
+            .map(|image| Some(load(image).map(prepare)))
+            .collect();
+        let dates: Vec<_> = loaded
+            .iter()
+            .map(|result| match (result, &args.filter) {
+                (Some(Ok(metadata)), Some(filter)) if !metadata.matches_filter(filter) => None,
+                (Some(Ok(metadata)), _) => metadata.date(),
+                _ => None,
+            })
+            .collect();
+        sequence_names = sequence::plan(&dates, prefix);
+
+        // Copies are renamed in the output folder: input images are never in the way
+        let indexes: std::collections::HashMap<&std::path::Path, usize> = images
+            .iter()
+            .enumerate()
+            .map(|(index, image)| (image.as_path(), index))
+            .collect();
+        let conflicts: Vec<Option<usize>> = loaded
+            .iter_mut()
+            .zip(&sequence_names)
+            .map(|(result, sequence_name)| {
+                let (Some(Ok(metadata)), Some(sequence_name)) = (result, sequence_name) else {
+                    return None;
+                };
+                if args.output_dir.is_some() || metadata.is_symlink() {
+                    return None;
+                }
+                metadata.set_sequence_name(sequence_name);
+                if *normalize_ext {
+                    metadata.normalize_extension();
+                }
+                metadata.fix_file_name();
+                indexes.get(metadata.rename_conflict()?.as_path()).copied()
+            })
+            .collect();
+        (order, sequence_blocked) = sequence::rename_order(&conflicts);
+    }
+
+    for (position, &index) in order.iter().enumerate() {
+        let image = &images[index];
+        if interrupted.load(Ordering::SeqCst) {
+            processed = position;
+            break;
+        }
+        let result = match loaded.get_mut(index).and_then(Option::take) {
+            Some(result) => result,
+            None => load(image).map(prepare),
+        };
+
+        // Skip images that do not match filter (errors are reported below)
+        if let (Some(filter), Ok(metadata)) = (&args.filter, &result) {
+            if !metadata.matches_filter(filter) {
+                continue;
+            }
+        }
+
+        // Quietly skip files without EXIF data (errors are reported below)
+        if let (true, Err(e)) = (args.skip_no_exif, &result) {
+            if Metadata::is_no_exif_error(e) {
+                summary.add_skipped_no_exif();
+                continue;
+            }
+        }
+
+        if summary_only {
+            match result {
+                Err(e) => {
+                    print_table!("File:", image.display());
+                    print_table!("Error!".red(), e);
+                    println!();
+                    summary.add_error();
+                    errors += 1;
+                }
+                Ok(metadata) => summary.add(&metadata),
+            }
+            continue;
+        }
+
+        if analyze {
+            match result {
+                Err(e) => {
+                    errors += 1;
+                    analysis.add_error(image, e.to_string());
+                }
+                Ok(metadata) => analysis.add(&metadata),
+            }
+            continue;
+        }
+
+        if !json && !grid {
+            print_table!("File:", image.display());
+        }
+
+        if result.is_err() {
+            errors += 1;
+            let e = result.err().expect("Unexpected error.");
+            if json {
+                let value = json!({"file": image, "error": e.to_string()});
+                if json_lines {
+                    lines.push(index, value.to_string())?;
+                } else {
+                    json_output.push(value);
+                }
+            } else {
+                if grid {
+                    print_table!("File:", image.display());
+                }
+                // File size does not require EXIF data
+                if let Commands::Info(args) = &args.command {
+                    if Metadata::is_no_exif_error(&e) {
+                        if let Ok(file) = fs::metadata(image) {
+                            print_table!("Filesize:", format_size(file.len(), args.human));
+                        }
+                    }
+                }
+                print_table!("Error!".red(), e);
+                println!();
+            }
+            continue;
+        }
+
+        let mut metadata = result.unwrap();
+
+        // Work on a copy of the (valid) image
+        if let (Some(output_dir), Commands::Set(_) | Commands::Fix(_) | Commands::Strip(_)) =
+            (&args.output_dir, &args.command)
+        {
+            let copy = file_list::copy_to_folder(image, output_dir).and_then(|path| {
+                print_table!("Copy:", path.display());
+                load(&path).map(prepare)
+            });
+            match copy {
+                Err(e) => {
+                    print_table!("Error!".red(), e);
+                    println!();
+                    errors += 1;
+                    continue;
+                }
+                Ok(copy) => metadata = copy,
+            }
+        }
+        if args.preserve_mtime {
+            metadata.preserve_mtime();
+        }
+        let entry = journal::Entry::new(&metadata);
+
+        match &args.command {
+            //
+            // Command info
+            //
+            Commands::Info(_) if reference.is_some() => {
+                match reference
+                    .as_ref()
+                    .and_then(|reference| reference.find(&metadata))
+                {
+                    None => {
+                        print_table!("Skipped:".yellow(), "No reference image.");
+                        drift.add_no_reference();
+                    }
+                    Some(reference) => match metadata::diff::diff_files(reference, image) {
+                        Err(e) => {
+                            print_table!("Error!".red(), e);
+                            errors += 1;
+                        }
+                        Ok(diffs) => {
+                            print_table!("Reference:", reference.display());
+                            if diffs.is_empty() {
+                                print_table!("Differences:", "None");
+                            } else {
+                                print_tag_diffs(&diffs);
+                            }
+                            drift.add(&diffs);
+                        }
+                    },
+                }
+            }
+            Commands::Info(args) if args.sidecar_report => {
+                // photo.jpg.json: photo.jpg and photo.png reports do not collide
+                let mut report = image.as_os_str().to_owned();
+                report.push(".json");
+                let report = std::path::PathBuf::from(report);
+                if report.exists() && !args.force {
+                    print_table!(
+                        "Skipped:".yellow(),
+                        format!(
+                            "{} already exists. Use {} to overwrite it.",
+                            report.display(),
+                            "--force".yellow()
+                        )
+                    );
+                } else {
+                    let mut value = info_to_json(&metadata);
+                    value["camera_info"] = json!(metadata.camera_info());
+                    match fs::write(&report, format!("{:#}\n", value)) {
+                        Err(e) => {
+                            print_table!("Error!".red(), e);
+                            errors += 1;
+                        }
+                        Ok(()) => print_table!("Report:", report.display()),
+                    }
+                }
+            }
+            Commands::Info(args) if json => {
+                let mut value = info_to_json(&metadata);
+                if args.show_all_tags {
+                    value["tags"] = metadata
+                        .all_tags()
+                        .into_iter()
+                        .map(|(name, value)| json!({"name": name, "value": value}))
+                        .collect();
+                }
+                if json_lines {
+                    lines.push(index, value.to_string())?;
+                } else {
+                    json_output.push(value);
+                }
+                continue;
+            }
+            Commands::Info(args) if grid => {
+                let camera_info = metadata.camera_info();
+                let text = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+                info_rows.push(InfoRow {
+                    name: image.display().to_string(),
+                    date: metadata.date(),
+                    iso: camera_info.iso,
+                    cells: vec![
+                        image.display().to_string(),
+                        text(metadata.date().map(|date| args.date_format.format(date))),
+                        match metadata.dimensions() {
+                            Err(_) => "-".to_string(),
+                            Ok((width, height)) => format!("{}x{}", width, height),
+                        },
+                        text(camera_info.camera.clone()),
+                        text(camera_info.iso.map(|iso| iso.to_string())),
+                        text(metadata.description()),
+                    ],
+                });
+                continue;
+            }
+            Commands::Info(args) => {
+                metadata.set_focal_unit(args.focal_unit);
+                let fields: &[InfoField] = if args.fields.contains(&InfoField::All) {
+                    &INFO_FIELDS
+                } else {
+                    &args.fields
+                };
+                let mut table = Table::default();
+                if let Some(e) = metadata.exif_error() {
+                    table.row("Warning:".yellow(), format!("Cannot read EXIF data: {}", e));
+                }
+                for field in fields.iter() {
+                    match field {
+                        InfoField::All => (),
+                        InfoField::Dimensions => {
+                            let decoded = match (metadata.dimensions(), metadata.bit_depth()) {
+                                (Err(_), _) if args.exif_only => "{Not read}".yellow().to_string(),
+                                (Err(e), _) => e.to_string().red().to_string(),
+                                (Ok((width, height)), Some(bits)) => {
+                                    format!("{}, {} ({} bits)", width, height, bits)
+                                }
+                                (Ok((width, height)), None) => format!("{}, {}", width, height),
+                            };
+                            let exif = match metadata.exif_dimensions() {
+                                Some((width, height)) => format!("{}, {}", width, height),
+                                None => "{No EXIF dimensions}".yellow().to_string(),
+                            };
+                            table.row(
+                                "Dimensions:",
+                                match args.dimensions_source {
+                                    DimensionsSource::Decoded => decoded,
+                                    DimensionsSource::Exif => exif,
+                                    DimensionsSource::Both => {
+                                        let exif = format!("(EXIF: {})", exif);
+                                        if metadata.needs_dimensions_fix() {
+                                            format!("{} {}", decoded, exif.yellow())
+                                        } else {
+                                            format!("{} {}", decoded, exif)
+                                        }
+                                    }
+                                },
+                            )
+                        }
+                        InfoField::Filesize => {
+                            if let Ok(file) = fs::metadata(image) {
+                                table.row("Filesize:", format_size(file.len(), args.human));
+                            }
+                        }
+                        InfoField::Date => {
+                            table.row(
+                                "Date:",
+                                match (metadata.date(), metadata.offset()) {
+                                    (Some(date), Some(offset)) => {
+                                        format!("{} {}", args.date_format.format(date), offset)
+                                    }
+                                    (Some(date), None) => args.date_format.format(date),
+                                    (None, _) => "{No exif date!}".yellow().to_string(),
+                                },
+                            );
+                            if let Some(e) = metadata.date_error() {
+                                table.row("Warning:".yellow(), e);
+                            }
+                            if metadata.has_inconsistent_dates() {
+                                table.row(
+                                    "Warning:".yellow(),
+                                    "DateTimeOriginal and CreateDate differ",
+                                );
+                            }
+                            if metadata.has_date_mismatch() {
+                                table.row(
+                                    "Warning:".yellow(),
+                                    "Filename date differs from EXIF date",
+                                );
+                            }
+                            if !metadata.date_plausible() {
+                                table.row(
+                                    "Warning:".yellow(),
+                                    format!(
+                                        "Implausible date (before {} or in the future)",
+                                        args_min_year
+                                    ),
+                                );
+                            }
+                        }
+                        InfoField::Description => {
+                            table.row(
+                                "Desription:",
+                                metadata
+                                    .description()
+                                    .unwrap_or("{No exif description!}".yellow().to_string()),
+                            );
+                            if args.verbose {
+                                if let Some(source) = metadata.description_source() {
+                                    table.row("From:", source);
+                                }
+                            }
+                        }
+                        InfoField::Comment => {
+                            if let Some(comment) = metadata.user_comment() {
+                                table.row("Comment:", comment);
+                            }
+                        }
+                        InfoField::Title => {
+                            if let Some(title) = metadata.title() {
+                                table.row("Title:", title);
+                            }
+                        }
+                        InfoField::Caption => {
+                            if let Some(caption) = metadata.caption() {
+                                table.row("Caption:", caption);
+                            }
+                        }
+                        InfoField::Keywords => {
+                            let keywords = metadata.keywords();
+                            table.row(
+                                "Keywords:",
+                                if keywords.is_empty() {
+                                    "{No keywords}".yellow().to_string()
+                                } else {
+                                    keywords.join(", ")
+                                },
+                            );
+                        }
+                        InfoField::Rating => {
+                            if let Some(rating) = metadata.rating() {
+                                table.row("Rating:", rating);
+                            }
+                        }
+                        InfoField::Camera => table.row("Camera:", metadata.camera_info()),
+                        InfoField::Gps => {
+                            if !metadata.gps_info().is_empty() {
+                                table.row("GPS:", metadata.gps_info());
+                            }
+                        }
+                        InfoField::Sidecar => {
+                            if let Some(xmp) = metadata.xmp() {
+                                table.row("Sidecar:", xmp.path().display());
+                            }
+                        }
+                    }
+                }
+                if args.verbose {
+                    if let Some(unique_id) = metadata.unique_id() {
+                        table.row("Unique ID:", unique_id);
+                    }
+                    if metadata.has_maker_note() {
+                        table.row("Maker note:", "Yes");
+                    }
+                }
+                for tag in args.tags.iter() {
+                    table.row(
+                        format!("{}:", metadata::diff::tag_name(tag)),
+                        match metadata.tag_value(tag) {
+                            Some(value) => value.to_string(),
+                            None => "{Not set}".yellow().to_string(),
+                        },
+                    );
+                }
+                if args.show_all_tags {
+                    table.row("All tags:", "");
+                }
+                table.print();
+                if args.show_all_tags {
+                    for (name, value) in metadata.all_tags() {
+                        println!("{0:<2}{1:<28} {2:}", "", name, value);
+                    }
+                }
+            }
+
+            //
+            // Command set
+            //
+            Commands::Set(args) => {
+                if let Some(patches) = &json_patches {
+                    match patches.find(image) {
+                        None => print_table!("Skipped:".yellow(), "No JSON object for this image."),
+                        Some(patch) => {
+                            if let Err(e) = patch.apply(&mut metadata) {
+                                print_table!("Error!".red(), e);
+                                println!();
+                                errors += 1;
+                                continue;
+                            }
+                        }
+                    }
+                }
+                for tag in args.setters.clear.iter() {
+                    match tag {
+                        ClearTag::Description => metadata.clear_description(),
+                        ClearTag::Comment => metadata.clear_user_comment(),
+                        ClearTag::Title => metadata.clear_title(),
+                        ClearTag::Caption => metadata.clear_caption(),
+                        ClearTag::Date => metadata.clear_date(),
+                        ClearTag::Gps => metadata.clear_gps(),
+                        ClearTag::Rating => metadata.clear_rating(),
+                    }
+                }
+                let mut result = Ok(());
+                for tag in args.setters.tags.iter() {
+                    if !(args.if_missing && metadata.tag_value(tag).is_some()) {
+                        result = metadata.set_named_tag(tag.clone());
+                        if result.is_err() {
+                            break;
+                        }
+                    }
+                }
+                if let Err(e) = result {
+                    print_table!("Error!".red(), e);
+                    println!();
+                    errors += 1;
+                    continue;
+                }
+                if args.setters.description.is_some()
+                    && !(args.if_missing && metadata.description().is_some())
+                {
+                    let description = args.setters.description.as_ref().unwrap().render(&metadata);
+                    let result = metadata.set_description_limited(
+                        &description,
+                        args.max_description_length,
+                        args.truncate,
+                    );
+                    if let Err(e) = result {
+                        print_table!(
+                            "Error!".red(),
+                            format!("{} Use {} option.", e, "--truncate".yellow())
+                        );
+                        println!();
+                        errors += 1;
+                        continue;
+                    }
+                    if args.iptc {
+                        let description = metadata.description().unwrap_or_default();
+                        if let Err(e) = metadata.set_caption(&description) {
+                            print_table!("Skipped:".yellow(), e);
+                        }
+                    }
+                    if args.mirror_description {
+                        if let Err(e) = metadata.mirror_description() {
+                            print_table!("Error!".red(), e);
+                            println!();
+                            errors += 1;
+                            continue;
+                        }
+                    }
+                }
+                if let Some(comment) = &args.setters.comment {
+                    if !(args.if_missing && metadata.user_comment().is_some()) {
+                        if let Err(e) = metadata.set_user_comment(comment, args.encoding) {
+                            print_table!("Error!".red(), e);
+                            println!();
+                            errors += 1;
+                            continue;
+                        }
+                    }
+                }
+                if let Some(title) = &args.setters.title {
+                    if !(args.if_missing && metadata.title().is_some()) {
+                        if let Err(e) = metadata.set_title(title) {
+                            print_table!("Skipped:".yellow(), e);
+                        }
+                    }
+                }
+                if let Some(caption) = &args.setters.caption {
+                    if !(args.if_missing && metadata.caption().is_some()) {
+                        if let Err(e) = metadata.set_caption(caption) {
+                            print_table!("Skipped:".yellow(), e);
+                        }
+                    }
+                }
+                if args.setters.keywords.is_some()
+                    && !(args.if_missing && !metadata.keywords().is_empty())
+                {
+                    metadata.set_keywords(&Metadata::split_keywords(
+                        args.setters.keywords.as_ref().unwrap(),
+                        ',',
+                    ));
+                }
+                if let Some(rating) = args.setters.rating {
+                    if !(args.if_missing && metadata.rating().is_some()) {
+                        if let Err(e) = metadata.set_rating(rating) {
+                            print_table!("Error!".red(), e);
+                            println!();
+                            errors += 1;
+                            continue;
+                        }
+                    }
+                }
+                if let Some((latitude, longitude)) = args.setters.gps {
+                    if !(args.if_missing && !metadata.gps_info().is_empty()) {
+                        metadata.set_gps(latitude, longitude);
+                        if args.gps_time && !metadata.set_gps_time() {
+                            print_table!("Skipped:".yellow(), "No date, GPS time not set.");
+                        }
+                    }
+                }
+                if args.setters.unique_id && metadata.unique_id().is_none() {
+                    match dedup::content_unique_id(&metadata) {
+                        Err(e) => {
+                            print_table!("Error!".red(), e);
+                            println!();
+                            errors += 1;
+                            continue;
+                        }
+                        Ok(unique_id) => metadata.set_unique_id(&unique_id),
+                    }
+                }
+                if args.also_modify_date {
+                    metadata.also_modify_date();
+                }
+                if let Some(delta) = date_offset {
+                    if !metadata.shift_date(delta) {
+                        print_table!("Skipped:".yellow(), "No date to shift.");
+                    }
+                } else if let Some(date) = set_date {
+                    if !(args.if_missing && metadata.date().is_some()) {
+                        metadata.set_date(date);
+                    }
+                }
+
+                if let Some(software) = &args.stamp_software {
+                    metadata.stamp_software(software);
+                }
+                if !save(&mut metadata, entry, &args_journal, &log, &mut prompt) {
+                    errors += 1;
+                }
+            }
+            Commands::Fix(args) => {
+                if args.setters.date_from_name && (args.force || metadata.date().is_none()) {
+                    match metadata.date_from_filename() {
+                        Some(date) => metadata.set_date(date),
+                        None => {
+                            print_table!("Skipped:".yellow(), "No date found in file name.");
+                        }
+                    }
+                }
+                if args.setters.date_from_mtime && (args.force || metadata.date().is_none()) {
+                    match metadata.date_from_mtime() {
+                        Some(date) => metadata.set_date(date),
+                        None => {
+                            print_table!(
+                                "Skipped:".yellow(),
+                                "Cannot read file modification time."
+                            );
+                        }
+                    }
+                }
+                if let Some(source) = args.setters.clamp_date {
+                    if !metadata.date_plausible() {
+                        let date = match source {
+                            ClampDate::Name => metadata.date_from_filename(),
+                            ClampDate::Mtime => metadata.date_from_mtime(),
+                        };
+                        match date.filter(|date| metadata.is_plausible_date(*date)) {
+                            Some(date) => metadata.set_date(date),
+                            None => {
+                                print_table!("Skipped:".yellow(), "No plausible date found.");
+                            }
+                        }
+                    }
+                }
+                if args.all || args.setters.dimensions {
+                    match metadata.dimensions() {
+                        Err(e) => {
+                            print_table!("Error!".red(), e);
+                            errors += 1;
+                        }
+                        Ok(_) => {
+                            metadata.set_dims(args.dims);
+                            metadata.fix_dimentions();
+                        }
+                    }
+                }
+                let sequence_name = sequence_names.get(index).cloned().flatten();
+                let unnumbered = args.sequence.is_some() && sequence_name.is_none();
+                if unnumbered {
+                    print_table!("Skipped:".yellow(), "No date, file not renamed.");
+                }
+                if (args.all || args.setters.name) && !unnumbered {
+                    if metadata.is_symlink() {
+                        print_table!(
+                            "Warning:".yellow(),
+                            "Symbolic link, file will not be renamed."
+                        );
+                    }
+                    metadata.set_on_conflict(args.on_conflict);
+                    if args.keep_original_name {
+                        metadata.keep_original_name();
+                    }
+                    if args.normalize_ext {
+                        metadata.normalize_extension();
+                    }
+                    if let Some(sequence_name) = &sequence_name {
+                        metadata.set_sequence_name(sequence_name);
+                    }
+                    metadata.set_file_name_options(FileNameOptions {
+                        ascii_only: args.ascii_only,
+                        replace_spaces: args.replace_spaces,
+                        max_description_length: args.max_name_description,
+                    });
+                    metadata.fix_file_name();
+                    if sequence_blocked.get(index) == Some(&true) {
+                        // The image at the new path cannot be renamed first
+                        metadata.set_on_conflict(Conflict::Skip);
+                        print_table!(
+                            "Error!".red(),
+                            "Numbering would replace another numbered image, file not renamed."
+                        );
+                        errors += 1;
+                    } else if let Some(existing) = metadata.rename_conflict() {
+                        let existing = existing.display();
+                        let rename = match args.on_conflict {
+                            Conflict::Number => true,
+                            Conflict::Skip => false,
+                            Conflict::Overwrite => {
+                                args.force || ask(&format!("Overwrite {}? [y/n]", existing))
+                            }
+                        };
+                        if !rename {
+                            metadata.set_on_conflict(Conflict::Skip);
+                            print_table!(
+                                "Warning:".yellow(),
+                                format!("{} already exists, file will not be renamed.", existing)
+                            );
+                        }
+                    }
+                }
+                let mut rotate = args.all || args.setters.orientation;
+                if rotate && args.verify_dims.is_some() && !metadata.orientation_consistent() {
+                    print_table!(
+                        "Warning:".yellow(),
+                        "Orientation is not consistent with dimensions (already rotated?)."
+                    );
+                    rotate = args.verify_dims != Some(VerifyDims::Skip);
+                }
+                if rotate {
+                    let result = if args.in_memory {
+                        metadata.fix_orientation_in_memory()
+                    } else {
+                        metadata.fix_orientation()
+                    };
+                    match result {
+                        Err(e) => {
+                            print_table!("Skipped:".yellow(), e);
+                        }
+                        Ok(Some(orientation)) => {
+                            print_table!("Rotated:", metadata::orientation_to_string(orientation));
+                        }
+                        Ok(None) => (),
+                    }
+                }
+                if args.setters.sync_dates && !metadata.sync_dates() {
+                    print_table!("Skipped:".yellow(), "No date to synchronize.");
+                }
+                if args.setters.utc && !metadata.normalize_to_utc() {
+                    print_table!(
+                        "Skipped:".yellow(),
+                        "No date or time offset, date not converted to UTC."
+                    );
+                }
+                if args.setters.trim_strings {
+                    metadata.trim_strings();
+                }
+                if args.setters.migrate_keywords {
+                    if args.force {
+                        metadata.migrate_description_to_keywords();
+                    } else if metadata.description_looks_like_keywords() {
+                        print_table!(
+                            "Skipped:".yellow(),
+                            format!(
+                                "Description looks like keywords, use {} to move it into keywords.",
+                                "--force".yellow()
+                            )
+                        );
+                    }
+                }
+                if args.setters.keywords_to_description
+                    && !metadata.keywords().is_empty()
+                    && !metadata.migrate_keywords_to_description(args.force)
+                {
+                    print_table!(
+                        "Skipped:".yellow(),
+                        format!(
+                            "Description already set, use {} to replace it by keywords.",
+                            "--force".yellow()
+                        )
+                    );
+                }
+                if args.setters.mtime && !metadata.set_mtime_from_date() {
+                    print_table!("Skipped:".yellow(), "No date, modification time not set.");
+                }
+                if let Some(software) = &args.stamp_software {
+                    metadata.stamp_software(software);
+                }
+                if !save(&mut metadata, entry, &args_journal, &log, &mut prompt) {
+                    errors += 1;
+                }
+            }
+
+            //
+            // Command sort
+            //
+            Commands::Sort(args) => {
+                let folder = match metadata.date() {
+                    Some(date) => args.into.join(date.format(&args.layout).to_string()),
+                    None => args.into.join("undated"),
+                };
+                if args.name {
+                    metadata.fix_file_name();
+                }
+                metadata.move_to_folder(&folder, args.copy);
+                if !save(&mut metadata, entry, &args_journal, &log, &mut prompt) {
+                    errors += 1;
+                }
+            }
+
+            //
+            // Command dedup
+            //
+            Commands::Dedup(_) => match dedup::signature(&metadata) {
+                Err(e) => {
+                    print_table!("Error!".red(), e);
+                    errors += 1;
+                }
+                Ok(signature) => signatures.push((image.to_path_buf(), signature)),
+            },
+
+            //
+            // Command extract-thumbnail
+            //
+            Commands::ExtractThumbnail(args) => {
+                match metadata.extract_thumbnail(&args.into, args.rotate) {
+                    Err(e) => {
+                        print_table!("Error!".red(), e);
+                        errors += 1;
+                    }
+                    Ok(None) => {
+                        print_table!("Skipped:".yellow(), "No thumbnail.");
+                    }
+                    Ok(Some(path)) => {
+                        print_table!("Thumbnail:", path.display());
+                    }
+                }
+            }
+
+            //
+            // Command strip
+            //
+            Commands::Strip(args) => {
+                let only: EnumSet<Tag> = args.only.iter().map(|tag| tag.tag()).collect();
+                let keep = if only.is_empty() {
+                    args.except.iter().map(|tag| tag.tag()).collect()
+                } else {
+                    EnumSet::all() - only
+                };
+                metadata.strip(&keep);
+                if !save(&mut metadata, entry, &args_journal, &log, &mut prompt) {
+                    errors += 1;
+                }
+            }
+
+            Commands::Analyze(_)
+            | Commands::Undo(_)
+            | Commands::Diff(_)
+            | Commands::Watch(_)
+            | Commands::GenerateReadmeMd => {
+                panic!("Cannot reach this code!");
+            }
+        }
+
+        println!();
+        if prompt == Prompt::Quit {
+            break;
+        }
+    }
+
+    if analyze && json {
+        // Files not found are reported with the images that cannot be loaded
+        let mut value = analysis.to_json();
+        if let Some(errors) = value["errors"].as_array_mut() {
+            errors.append(&mut json_output);
+        }
+        println!("{}", value);
+    } else if json && !json_lines {
+        println!("{}", serde_json::Value::from(json_output));
+    }
+
+    // Report images as a table
+    if let Commands::Info(InfoArgs {
+        format: InfoFormat::Table,
+        sort_by,
+        ..
+    }) = &args.command
+    {
+        match sort_by {
+            None => (),
+            Some(SortBy::Name) => info_rows.sort_by(|a, b| a.name.cmp(&b.name)),
+            // Images without value last
+            Some(SortBy::Date) => info_rows.sort_by_key(|row| (row.date.is_none(), row.date)),
+            Some(SortBy::Iso) => info_rows.sort_by_key(|row| (row.iso.is_none(), row.iso)),
+        }
+        let mut table = Grid::new(&["File", "Date", "Dimensions", "Camera", "ISO", "Description"]);
+        for row in info_rows {
+            table.row(row.cells);
+        }
+        table.print();
+    }
+
+    // Report statistics
+    if summary_only {
+        let mut table = Table::default();
+        table.row("Files:", summary.files);
+        table.row("Errors:", summary.errors);
+        if args.skip_no_exif {
+            table.row("Skipped (no EXIF):", summary.skipped_no_exif);
+        }
+        table.row("With date:", summary.with_date);
+        table.row("Without date:", summary.without_date());
+        table.row("Described:", summary.with_description);
+        if let (Some(earliest), Some(latest)) = (summary.earliest, summary.latest) {
+            table.row("Date range:", format!("{} - {}", earliest, latest));
+        }
+        for (camera, count) in summary.cameras.iter() {
+            table.row("Camera:", format!("{} ({} files)", camera, count));
+        }
+        table.print();
+    }
+
+    // Report files to fix
+    if analyze && !json {
+        let report = |label: &str, files: &[std::path::PathBuf]| {
+            println!("{}: {}", label, files.len());
+            for file in files {
+                println!("{0:<2}{1:}", "", file.display());
+            }
+        };
+        report("Files needing dimension fix", &analysis.dimensions);
+        report("Files needing rename", &analysis.names);
+        report("Files with abnormal orientation", &analysis.orientations);
+        for (file, error) in analysis.errors.iter() {
+            print_table!("File:", file.display());
+            print_table!("Error!".red(), error);
+        }
+    }
+
+    // Report metadata drift
+    if reference.is_some() {
+        let mut table = Table::default();
+        table.row("Compared:", drift.compared);
+        table.row("Differ:", drift.differ);
+        table.row("No reference:", drift.no_reference);
+        for (name, count) in drift.tags.iter() {
+            table.row(format!("{}:", name), format!("{} files", count));
+        }
+        table.print();
+    }
+
+    // Report duplicates
+    if let Commands::Dedup(ref args) = args.command {
+        let groups = dedup::duplicates(signatures);
+        if groups.is_empty() {
+            println!("No duplicates found.");
+        }
+        for group in groups.iter() {
+            print_table!("Keep:", group[0].display());
+            for duplicate in group.iter().skip(1) {
+                if !args.delete_extras {
+                    print_table!("Duplicate:".yellow(), duplicate.display());
+                } else {
+                    match fs::remove_file(duplicate) {
+                        Err(e) => {
+                            print_table!("Error!".red(), e);
+                            errors += 1;
+                        }
+                        Ok(_) => {
+                            print_table!("Removed:".yellow(), duplicate.display());
+                        }
+                    }
+                }
+            }
+            println!();
+        }
+    }
+
+    if interrupted.load(Ordering::SeqCst) && processed < images.len() {
+        eprintln!(
+            "{}: Interrupted after {} files.",
+            "warning".yellow(),
+            processed
+        );
+        std::process::exit(EXIT_INTERRUPTED);
+    }
+    if errors > 0 {
+        std::process::exit(EXIT_FILE_ERRORS);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_targets() {
+        assert!(validate_set_targets(false, 1).is_ok());
+        assert!(validate_set_targets(false, 2).is_err());
+        assert!(validate_set_targets(true, 2).is_ok());
+    }
 }
@@ -1,24 +1,48 @@
 use add_extention::AddExtention;
-use camera_info::CameraInfo;
-use chrono::NaiveDateTime;
+use camera_info::{CameraInfo, FocalUnit};
+use chrono::{
+    Datelike, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike,
+};
 use enumset::EnumSet;
-use image::image_dimensions;
+use filetime::FileTime;
+use filter::Filter;
+use gps_info::GpsInfo;
+use image::{ImageDecoder, ImageFormat, ImageReader};
+use iptc::Iptc;
 use little_exif::rational::iR64;
 use little_exif::{
-    exif_tag::ExifTag, metadata::Metadata as LittleMetadata, rational::uR64,
-    u8conversion::U8conversion,
+    endian::Endian, exif_tag::ExifTag, filetype::FileExtension,
+    metadata::Metadata as LittleMetadata, rational::uR64, u8conversion::U8conversion,
 };
-use std::ffi::OsStr;
-use std::fs::rename;
+use std::cell::OnceCell;
+use std::ffi::{OsStr, OsString};
+use std::fmt::Write as _;
+use std::fs::{self, copy, create_dir_all};
 use std::{
-    io::Error,
+    io::{Cursor, Error, ErrorKind, Write as _},
     path::{Path, PathBuf},
 };
 use tag::{DisplayWithComment, Tag};
+use tag_value::TagValue;
+use xmp::Xmp;
+
+use crate::file_list;
 
 pub mod add_extention;
 pub mod camera_info;
+pub mod diff;
+pub mod filter;
+pub mod gps_info;
+pub mod heif;
+pub mod iptc;
+pub mod json_patch;
+pub mod named_tag;
+pub mod png;
+pub mod raw;
 pub mod tag;
+pub mod tag_value;
+pub mod template;
+pub mod xmp;
 
 trait ExifConversion {
     fn to_exif_string(&self) -> String;
@@ -31,163 +55,673 @@ impl ExifConversion for NaiveDateTime {
         self.format("%Y:%m:%d %H:%M:%S").to_string()
     }
     fn from_exif_string(input: String) -> Result<Self, Error> {
-        match NaiveDateTime::parse_from_str(&input, "%Y:%m:%d %H:%M:%S") {
-            Ok(dt) => Ok(dt),
-            Err(error) => Err(Error::other(error.to_string())),
+        parse_exif_date(&input, YEAR_PIVOT)
+    }
+}
+
+/// Two-digit years (like 06:10:29 16:27:21) below this pivot are in the 2000s,
+/// other ones in the 1900s (see set_year_pivot())
+pub const YEAR_PIVOT: i32 = 70;
+
+// Parse an EXIF date, two-digit years are expanded according to year_pivot.
+// Year 0000 is rejected.
+fn parse_exif_date(input: &str, year_pivot: i32) -> Result<NaiveDateTime, Error> {
+    let input = input.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+    let expanded;
+    let input = match expand_two_digit_year(input, year_pivot) {
+        Some(date) => {
+            expanded = date;
+            expanded.as_str()
+        }
+        None => input,
+    };
+    let date = match NaiveDateTime::parse_from_str(input, "%Y:%m:%d %H:%M:%S") {
+        Ok(dt) => dt,
+        // Some cameras do not follow the EXIF format strictly
+        Err(error) => EXIF_LENIENT_FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+            .ok_or_else(|| Error::other(format!("Invalid date '{}': {}", input, error)))?,
+    };
+    if date.year() == 0 {
+        return Err(Error::other(format!("Invalid date '{}': year 0000", input)));
+    }
+    Ok(date)
+}
+
+// Expand YY:MM:DD HH:MM[:SS] to a four-digit year date
+fn expand_two_digit_year(input: &str, year_pivot: i32) -> Option<String> {
+    let bytes = input.as_bytes();
+    if bytes.len() < 14
+        || !bytes[..2].iter().all(u8::is_ascii_digit)
+        || bytes[2] != b':'
+        || bytes[8] != b' '
+    {
+        return None;
+    }
+    let year: i32 = input[..2].parse().ok()?;
+    let century = if year < year_pivot { 2000 } else { 1900 };
+    Some(format!("{}{}", century + year, &input[2..]))
+}
+
+// Malformed EXIF dates that can be recovered (missing seconds are set to 00)
+const EXIF_LENIENT_FORMATS: [&str; 6] = [
+    "%Y:%m:%d %H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%Y/%m/%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+];
+
+/// Software tag written by stamp_software() by default
+pub const SOFTWARE_STAMP: &str = concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+
+// Error of files without EXIF data (see is_no_exif_error())
+const NO_EXIF: &str = "No EXIF info in this file.";
+
+// ExifOffset, GPSInfo and InteropOffset: pointers to sub-IFDs, never stripped
+const IFD_POINTER_IDS: [u16; 3] = [0x8769, 0x8825, 0xa005];
+
+// Date formats accepted by set_date_flexible (EXIF format is tried last)
+const DATE_TIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y/%m/%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+];
+const DATE_FORMATS: [&str; 3] = ["%Y-%m-%d", "%Y/%m/%d", "%Y:%m:%d"];
+
+/// Parse a date from several common formats, or now and today (at 00:00:00) in local time.
+/// Dates without time are set at 00:00:00.
+pub fn parse_date_flexible(input: &str) -> Result<NaiveDateTime, Error> {
+    let input = input.trim();
+    if input.eq_ignore_ascii_case("now") {
+        let now = Local::now().naive_local();
+        return Ok(now.with_nanosecond(0).unwrap_or(now));
+    }
+    if input.eq_ignore_ascii_case("today") {
+        return Ok(Local::now().date_naive().and_time(NaiveTime::MIN));
+    }
+    for format in DATE_TIME_FORMATS {
+        if let Ok(date) = NaiveDateTime::parse_from_str(input, format) {
+            return Ok(date);
+        }
+    }
+    for format in DATE_FORMATS {
+        if let Ok(date) = NaiveDate::parse_from_str(input, format) {
+            return Ok(date.and_time(NaiveTime::MIN));
+        }
+    }
+    NaiveDateTime::from_exif_string(input.to_string()).map_err(|_| {
+        Error::other(format!(
+            "accepted formats are {}, {}, %Y:%m:%d %H:%M:%S, now and today",
+            DATE_TIME_FORMATS.join(", "),
+            DATE_FORMATS.join(", ")
+        ))
+    })
+}
+
+/// Dates before this year are implausible by default (see date_plausible())
+pub const MIN_YEAR: i32 = 1990;
+
+// Date of normalized file names (see fix_file_name())
+const NORMALIZED_DATE_FORMAT: &str = "%Y_%m_%d-%H_%M_%S";
+
+// Date formats found in file names
+const FILE_NAME_DATE_FORMATS: [&str; 5] = [
+    // PhotosNorm
+    NORMALIZED_DATE_FORMAT,
+    // Android, Pixel, ...: IMG_20061029_162721.jpg
+    "%Y%m%d_%H%M%S",
+    "%Y%m%d-%H%M%S",
+    // Dropbox: 2006-10-29 16.27.21.jpg
+    "%Y-%m-%d %H.%M.%S",
+    "%Y-%m-%d_%H-%M-%S",
+];
+
+/// Search a date in a file name (see FILE_NAME_DATE_FORMATS)
+pub fn date_from_file_name(file_name: &str) -> Option<NaiveDateTime> {
+    file_name.char_indices().find_map(|(index, _)| {
+        FILE_NAME_DATE_FORMATS.iter().find_map(|format| {
+            NaiveDateTime::parse_and_remainder(&file_name[index..], format)
+                .ok()
+                .map(|(date, _)| date)
+        })
+    })
+}
+
+/// Return folder/prefix.extention or, if this file already exists,
+/// the first folder/prefix-N.extention that does not exist.
+pub fn unique_file_path(folder: &Path, prefix: &OsStr, extention: &OsStr) -> PathBuf {
+    let mut filename = prefix.to_os_string();
+    filename.add_ext(extention);
+    let mut file_path = folder.join(&filename);
+    let mut count = 0;
+    while file_path.exists() {
+        count += 1;
+        filename = prefix.to_os_string();
+        filename.push(format!("-{}", count));
+        filename.add_ext(extention);
+        file_path = folder.join(&filename);
+    }
+    file_path
+}
+
+/// Describe the transformation that fixes an EXIF orientation
+pub fn orientation_to_string(orientation: u16) -> String {
+    match orientation {
+        1 => "None",
+        2 => "Horizontal flip",
+        3 => "180°",
+        4 => "Vertical flip",
+        5 => "Transpose",
+        6 => "90° CW",
+        7 => "Transverse",
+        8 => "90° CCW",
+        _ => "Unknown orientation",
+    }
+    .to_string()
+}
+
+/// Check if an EXIF orientation looks right for the stored image dimensions.
+/// An image stored in landscape with a 90° orientation (5 to 8) is suspicious:
+/// it may have been rotated already without resetting the tag.
+pub fn orientation_consistent_with_dims(orientation: u16, width: u32, height: u32) -> bool {
+    !(5..=8).contains(&orientation) || width <= height
+}
+
+// Lowercase extension, with a single form for JPEG files
+fn normalized_extension(extention: &OsStr) -> OsString {
+    let extention = extention.to_string_lossy().to_lowercase();
+    match extention.as_str() {
+        "jpeg" | "jpe" => OsString::from("jpg"),
+        _ => OsString::from(extention),
+    }
+}
+
+impl ExifConversion for FixedOffset {
+    fn to_exif_string(&self) -> String {
+        self.to_string()
+    }
+    fn from_exif_string(input: String) -> Result<Self, Error> {
+        // Expected format: +HH:MM or -HH:MM
+        let input = input.trim();
+        let invalid = || Error::other(format!("Invalid time offset '{}'", input));
+        let (sign, time) = match input.split_at_checked(1) {
+            Some(("+", time)) => (1, time),
+            Some(("-", time)) => (-1, time),
+            _ => return Err(invalid()),
+        };
+        let Some((hours, minutes)) = time.split_once(':') else {
+            return Err(invalid());
+        };
+        // Digits only: parse() would accept a sign like in +05:-30
+        let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        if !digits(hours) || !digits(minutes) {
+            return Err(invalid());
+        }
+        let (Ok(hours), Ok(minutes)) = (hours.parse::<i32>(), minutes.parse::<i32>()) else {
+            return Err(invalid());
+        };
+        if minutes >= 60 {
+            return Err(invalid());
+        }
+        FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).ok_or_else(invalid)
+    }
+}
+
+/// What to do when the new name of a file is already used
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Conflict {
+    /// Number the new name (name-N.ext)
+    #[default]
+    Number,
+    /// Do not rename the file
+    Skip,
+    /// Replace the existing file (asked for unless --force, not replaced if there is no terminal to answer)
+    Overwrite,
+}
+
+/// Dimensions expected in ExifImageWidth/Height of images rotated by 90° (Orientation 5 to 8)
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Dims {
+    /// Stored pixels, as decoded (EXIF standard)
+    #[default]
+    Stored,
+    /// Displayed pixels, after rotation (as expected by some viewers)
+    Rotated,
+}
+
+/// How descriptions are written in new file names (see fix_file_name())
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileNameOptions {
+    /// Transliterate to ASCII (é -> e)
+    pub ascii_only: bool,
+    /// Replace spaces by this character
+    pub replace_spaces: Option<char>,
+    /// Truncate the description to this number of characters
+    pub max_description_length: Option<usize>,
+}
+
+/// Encoding of the UserComment tag
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum CommentEncoding {
+    /// ASCII only
+    Ascii,
+    /// UCS-2, for accented and other non-ASCII chars
+    #[default]
+    Unicode,
+}
+
+// UserComment encoding headers
+const COMMENT_ASCII: &[u8; 8] = b"ASCII\0\0\0";
+const COMMENT_UNICODE: &[u8; 8] = b"UNICODE\0";
+
+/// EXIF tag used as image date
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum DateSource {
+    /// DateTimeOriginal, then CreateDate
+    #[default]
+    Original,
+    /// CreateDate, then DateTimeOriginal
+    Create,
+    /// ModifyDate, then DateTimeOriginal and CreateDate
+    Modified,
+}
+
+/// Field the description was read from (see Metadata::description())
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DescriptionSource {
+    ImageDescription,
+    IptcCaption,
+    XpTitle,
+    Xmp,
+}
+
+impl std::fmt::Display for DescriptionSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DescriptionSource::ImageDescription => write!(f, "ImageDescription"),
+            DescriptionSource::IptcCaption => write!(f, "IPTC Caption-Abstract"),
+            DescriptionSource::XpTitle => write!(f, "XPTitle"),
+            DescriptionSource::Xmp => write!(f, "XMP sidecar"),
+        }
+    }
+}
+
+/// Format used to display dates
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum DateFormat {
+    /// %Y:%m:%d %H:%M:%S
+    #[default]
+    Exif,
+    /// %Y-%m-%dT%H:%M:%S
+    Iso,
+    /// strftime format
+    Custom(String),
+}
+
+impl DateFormat {
+    /// Parse 'exif', 'iso' or a strftime format (like %d/%m/%Y).
+    /// Malformed strftime formats are rejected.
+    pub fn parse(input: &str) -> Result<DateFormat, Error> {
+        match input {
+            "exif" => Ok(DateFormat::Exif),
+            "iso" => Ok(DateFormat::Iso),
+            _ => {
+                let mut check = String::new();
+                if !input.contains('%')
+                    || write!(check, "{}", NaiveDateTime::default().format(input)).is_err()
+                {
+                    return Err(Error::other(format!(
+                        "Invalid date format: '{}'. Expected iso, exif or a strftime format like %d/%m/%Y.",
+                        input
+                    )));
+                }
+                Ok(DateFormat::Custom(input.to_string()))
+            }
+        }
+    }
+
+    pub fn format(&self, date: NaiveDateTime) -> String {
+        match self {
+            DateFormat::Exif => date.to_exif_string(),
+            DateFormat::Iso => date.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            DateFormat::Custom(format) => date.format(format).to_string(),
         }
     }
 }
 
+// Properties read from the image data (not from EXIF data)
+#[derive(Clone, Copy)]
+struct ImageHeader {
+    dimentions: (u32, u32),
+    bit_depth: Option<u16>,
+    icc_profile: bool,
+}
+
 pub struct Metadata {
     path: PathBuf,
+    symlink: bool,
     mime: String,
+    date_source: DateSource,
     litte_metadata: LittleMetadata,
-    dimentions: (u32, u32),
+    // Set when little_exif cannot read a file the image reader can read
+    exif_error: Option<String>,
+    // Dimensions are not read (see new_exif_only())
+    exif_only: bool,
+    // Read on first use: most commands do not need it
+    header: OnceCell<ImageHeader>,
     date: Option<NaiveDateTime>,
+    original_date: Option<NaiveDateTime>,
+    create_date: Option<NaiveDateTime>,
+    // Error of an EXIF date that cannot be parsed
+    date_error: Option<String>,
+    offset: Option<FixedOffset>,
     description: Option<String>,
+    description_source: Option<DescriptionSource>,
+    user_comment: Option<String>,
+    iptc: Iptc,
+    keywords: Vec<String>,
+    rating: Option<u16>,
+    unique_id: Option<String>,
     camera_info: CameraInfo,
+    gps_info: GpsInfo,
+    maker_note: Option<Vec<u8>>,
+    maker_note_dropped: bool,
+    compression_dropped: bool,
+    in_memory: bool,
+    xmp: Option<Xmp>,
+    target_folder: Option<PathBuf>,
+    copy: bool,
+    restored_orientation: Option<u16>,
+    on_conflict: Conflict,
+    dims: Dims,
+    preserve_mtime: bool,
+    also_modify_date: bool,
+    keep_original_name: bool,
+    sequence_name: Option<String>,
+    normalize_extension: bool,
+    file_name_options: FileNameOptions,
+    min_year: i32,
+    year_pivot: i32,
+    software_stamp: Option<String>,
     modified_tags: EnumSet<Tag>,
 }
 
 impl Metadata {
     pub fn new(path: &Path) -> Result<Metadata, Error> {
+        Self::new_with_date_source(path, DateSource::default())
+    }
+
+    /// Load metadata, reading date from the given tag
+    pub fn new_with_date_source(path: &Path, date_source: DateSource) -> Result<Metadata, Error> {
+        Self::load(path, date_source, false)
+    }
+
+    /// Load EXIF data only: the file is not read again for its dimensions (see dimensions()).
+    /// Files only known from their extention are still checked by the image reader.
+    /// Uncalibrated color spaces are not described from the ICC profile.
+    pub fn new_exif_only(path: &Path, date_source: DateSource) -> Result<Metadata, Error> {
+        Self::load(path, date_source, true)
+    }
+
+    fn load(path: &Path, date_source: DateSource, exif_only: bool) -> Result<Metadata, Error> {
         // Check file type because little_exif will panic on these errors
-        let Some(kind) = infer::get_from_path(path)? else {
-            return Err(Error::other("Unknown file type."));
+        // When infer does not know the file, trust a known image extention:
+        // image reader and little_exif will do the real check.
+        // RAW files are TIFF based and are known from their extention.
+        // The file is read once: everything is then loaded from memory.
+        let data = fs::read(path)?;
+        let raw_mime = raw::mime_from_extention(path);
+        let kind = infer::get(&data);
+        let mime = match (raw_mime, kind) {
+            (Some(mime), _) => mime,
+            (None, Some(kind)) => kind.mime_type(),
+            (None, None) => match Self::mime_from_extention(path) {
+                Some(mime) => mime,
+                None => return Err(Error::other("Unknown file type.")),
+            },
         };
-        if !kind.mime_type().starts_with("image") {
+        if !mime.starts_with("image") {
             return Err(Error::other("Unsuported file type."));
         }
 
-        // Load dimention from image data (not from exif data)
-        let Ok(dimentions) = image_dimensions(path) else {
-            return Err(Error::other("Cannot read image dimentions."));
-        };
+        let header = OnceCell::new();
+        let mut exif_error = None;
+        let litte_metadata = if raw_mime.is_some() {
+            // RAW files are read-only: load dimentions and tags with our own TIFF reader
+            let (dimentions, litte_metadata) = raw::load(&data)?;
+            let _ = header.set(ImageHeader {
+                dimentions,
+                bit_depth: None,
+                icc_profile: false,
+            });
+            litte_metadata
+        } else if heif::is_heif(mime) {
+            // HEIF files are read-only: load dimentions and tags with our own reader
+            let (dimentions, litte_metadata) = heif::load(&data)?;
+            let _ = header.set(ImageHeader {
+                dimentions,
+                bit_depth: None,
+                icc_profile: false,
+            });
+            litte_metadata
+        } else {
+            // Image header is read on first use, unless the file type is only known
+            // from its extention: the image reader then checks it is really an image
+            // (even when loading EXIF only).
+            if kind.is_none() {
+                let _ = header.set(Self::read_header(&data, path)?);
+            }
 
-        // Load little_exif metadata
-        let litte_metadata = LittleMetadata::new_from_path(path)?;
-        if litte_metadata.into_iter().count() == 0 {
-            return Err(Error::other("No EXIF info in this file."));
+            // Load little_exif metadata.
+            // If little_exif fails but the image reader succeeds, the image is loaded
+            // without EXIF data (see exif_error()).
+            let result = match Self::file_extension(mime) {
+                Some(file_extension) => LittleMetadata::new_from_vec(&data, file_extension),
+                None => LittleMetadata::new_from_path(path),
+            };
+            match result {
+                Ok(litte_metadata) => litte_metadata,
+                Err(e) => {
+                    if exif_only {
+                        return Err(e);
+                    }
+                    if header.get().is_none() {
+                        match Self::read_header(&data, path) {
+                            Ok(image_header) => {
+                                let _ = header.set(image_header);
+                            }
+                            Err(_) => return Err(e),
+                        }
+                    }
+                    exif_error = Some(e.to_string());
+                    LittleMetadata::new()
+                }
+            }
+        };
+        if exif_error.is_none() && litte_metadata.into_iter().count() == 0 {
+            return Err(Error::other(NO_EXIF));
         }
 
-        // Load and parse date
-        let date =
-            Self::get_tag_string(&litte_metadata, &ExifTag::DateTimeOriginal(String::new())).or(
-                Self::get_tag_string(&litte_metadata, &ExifTag::CreateDate(String::new())),
-            );
-        let date = match date {
-            None => None,
-            Some(str_date) => NaiveDateTime::from_exif_string(str_date).ok(),
-        };
+        // Load and parse dates
+        let (date, original_date, create_date, date_error) =
+            Self::read_dates(&litte_metadata, date_source, YEAR_PIVOT);
+        let offset =
+            Self::get_tag_string(&litte_metadata, &ExifTag::OffsetTimeOriginal(String::new()))
+                .and_then(|str_offset| FixedOffset::from_exif_string(str_offset).ok());
 
         // Load description
         let description =
             Self::get_tag_string(&litte_metadata, &ExifTag::ImageDescription(String::new()));
 
-        // Load and format CameraInfo
-        let make = Self::get_tag_string(&litte_metadata, &ExifTag::Make(String::new()));
-        let model = Self::get_tag_string(&litte_metadata, &ExifTag::Model(String::new()));
-        let software = Self::get_tag_string(&litte_metadata, &ExifTag::Software(String::new()));
-        let camera = if make.is_some() && model.is_some() {
-            let mut camera = make.unwrap().clone();
-            camera.push(' ');
-            camera.push_str(&model.unwrap());
-            Some(camera)
-        } else if make.is_some() {
-            make
-        } else if model.is_some() {
-            model
-        } else {
-            None
+        // Load user comment
+        let user_comment = litte_metadata
+            .get_tag(&ExifTag::UserComment(Vec::new()))
+            .next()
+            .and_then(|tag| {
+                let endian = litte_metadata.get_endian();
+                Self::decode_user_comment(&tag.value_as_u8_vec(&endian), &endian)
+            });
+
+        // Load keywords
+        let keywords = Self::get_tag_ucs2(&litte_metadata, &ExifTag::XPKeywords(Vec::new()))
+            .map(|keywords| Self::split_keywords(&keywords, ';'))
+            .unwrap_or_default();
+
+        // Load rating
+        let rating = Self::get_tag_u16(&litte_metadata, &ExifTag::Rating(Vec::new()));
+
+        // Load image unique ID
+        let unique_id =
+            Self::get_tag_string(&litte_metadata, &ExifTag::ImageUniqueID(String::new()))
+                .filter(|id| !id.is_empty());
+
+        // Load IPTC title and caption (JPEG only). Invalid IPTC data is ignored.
+        let iptc = match mime {
+            "image/jpeg" | "image/jpg" => Iptc::from_data(&data).unwrap_or_default(),
+            _ => Iptc::default(),
         };
-        let camera = if camera.is_some() && software.is_some() {
-            let mut camera = camera.unwrap().clone();
-            camera.push_str(" (");
-            camera.push_str(&software.unwrap());
-            camera.push(')');
-            Some(camera)
-        } else {
-            camera
-        };
-
-        let exposure = Self::get_tag_ur64(&litte_metadata, &ExifTag::ExposureTime(Vec::new()))
-            .map(|v| format!("{}/{}", v.nominator, v.denominator))
-            .or(
-                Self::get_tag_ir64(&litte_metadata, &ExifTag::ShutterSpeedValue(Vec::new())).map(
-                    |rational| {
-                        let value: f64 = rational.into();
-                        // Convert APEX format to seconds
-                        let value = 2f64.powf(-value);
-                        // Convert second to rational if possible
-                        if value < 0.25001 && value > 0f64 {
-                            format!("1/{}", (0.5f64 + 1f64 / value).trunc())
-                        } else {
-                            value.to_string()
-                        }
-                    },
-                ),
-            );
 
-        let exposure_bias =
-            Self::get_tag_ir64(&litte_metadata, &ExifTag::ExposureCompensation(Vec::new())).map(
-                |v| {
-                    if v.nominator == 0 {
-                        "0".to_string()
-                    } else {
-                        format!("{}/{}", v.nominator, v.denominator)
-                    }
+        // Load and format CameraInfo.
+        // ICC profile presence is only needed to describe uncalibrated color spaces.
+        let uncalibrated =
+            Self::get_tag_u16(&litte_metadata, &ExifTag::ColorSpace(Vec::new())) == Some(0xffff);
+        let icc_profile = uncalibrated
+            && !exif_only
+            && match header.get() {
+                Some(header) => header.icc_profile,
+                None => match Self::read_header(&data, path) {
+                    Ok(read) => header.get_or_init(|| read).icc_profile,
+                    Err(_) => false,
                 },
-            );
+            };
+        let camera_info = CameraInfo::from_metadata(&litte_metadata, icc_profile);
+        let gps_info = GpsInfo::from_metadata(&litte_metadata);
 
-        let aperture = Self::get_tag_ur64(&litte_metadata, &ExifTag::FNumber(Vec::new()))
-            .map(std::convert::Into::<f64>::into)
-            .or(
-                Self::get_tag_ur64(&litte_metadata, &ExifTag::ApertureValue(Vec::new())).map(
-                    |rational| {
-                        let value: f64 = rational.into();
-                        // Convert APEX format to f-number
-                        2f64.powf(value / 2f64)
-                    },
-                ),
-            )
-            .map(|value| format!("{:.1}", value));
-
-        let iso = Self::get_tag_u16(&litte_metadata, &ExifTag::ISO(Vec::new()));
-
-        let focal = Self::get_tag_ur64(&litte_metadata, &ExifTag::FocalLength(Vec::new()))
-            .map(std::convert::Into::<f64>::into);
-
-        let flash = Self::get_tag_u16(&litte_metadata, &ExifTag::Flash(Vec::new()))
-            .map(Self::flash_code_to_string);
-
-        let camera_info = CameraInfo {
-            camera,
-            exposure,
-            exposure_bias,
-            aperture,
-            iso,
-            focal,
-            flash,
-        };
+        // Load XMP sidecar. EXIF values have priority (see prefer_xmp()).
+        let xmp = Xmp::load(path)?;
+
+        // Description is read from the first defined field
+        let xp_title = Self::get_tag_ucs2(&litte_metadata, &ExifTag::XPTitle(Vec::new()))
+            .filter(|title| !title.is_empty());
+        let (description, description_source) = Self::first_description([
+            (description, DescriptionSource::ImageDescription),
+            (iptc.caption.clone(), DescriptionSource::IptcCaption),
+            (xp_title, DescriptionSource::XpTitle),
+            (
+                xmp.as_ref().and_then(|xmp| xmp.description()),
+                DescriptionSource::Xmp,
+            ),
+        ]);
+        let date = date.or_else(|| xmp.as_ref()?.create_date());
+        let rating = rating.or_else(|| xmp.as_ref()?.rating());
+
+        let maker_note = Self::get_maker_note(&litte_metadata);
 
         Ok(Metadata {
             path: PathBuf::from(path),
-            mime: kind.mime_type().to_string(),
+            symlink: file_list::is_symlink(path),
+            date_source,
+            mime: mime.to_string(),
             litte_metadata,
-            dimentions,
+            exif_error,
+            exif_only,
+            header,
             date,
+            original_date,
+            create_date,
+            date_error,
+            offset,
             description,
+            description_source,
+            user_comment,
+            iptc,
+            keywords,
+            rating,
+            unique_id,
             camera_info,
+            gps_info,
+            maker_note,
+            maker_note_dropped: false,
+            compression_dropped: false,
+            in_memory: false,
+            xmp,
+            target_folder: None,
+            copy: false,
+            restored_orientation: None,
+            on_conflict: Conflict::default(),
+            dims: Dims::default(),
+            preserve_mtime: false,
+            also_modify_date: false,
+            keep_original_name: false,
+            sequence_name: None,
+            normalize_extension: false,
+            file_name_options: FileNameOptions::default(),
+            min_year: MIN_YEAR,
+            year_pivot: YEAR_PIVOT,
+            software_stamp: None,
             modified_tags: EnumSet::empty(),
         })
     }
 
+    /// Return true if a load error is due to a file without EXIF data
+    pub fn is_no_exif_error(error: &Error) -> bool {
+        error.kind() == ErrorKind::Other && error.to_string() == NO_EXIF
+    }
+
+    /// Read the file again (after an external modification), discarding unsaved changes.
+    /// Options (preserve_mtime(), also_modify_date(), keep_original_name(), normalize_extension(),
+    /// stamp_software(), set_on_conflict(), set_dims(), set_file_name_options(), set_min_year()
+    /// and set_year_pivot()) are kept, call prefer_xmp() again if needed.
+    pub fn reload(&mut self) -> Result<(), Error> {
+        let mut metadata = Self::load(&self.path, self.date_source, self.exif_only)?;
+        metadata.set_year_pivot(self.year_pivot);
+        *self = Metadata {
+            on_conflict: self.on_conflict,
+            dims: self.dims,
+            preserve_mtime: self.preserve_mtime,
+            also_modify_date: self.also_modify_date,
+            keep_original_name: self.keep_original_name,
+            normalize_extension: self.normalize_extension,
+            file_name_options: self.file_name_options.clone(),
+            min_year: self.min_year,
+            software_stamp: self.software_stamp.take(),
+            ..metadata
+        };
+        if let Some(software) = self.software_stamp.clone() {
+            self.hide_software(&software);
+        }
+        Ok(())
+    }
+
     // Accessors
-    pub fn width(&self) -> u32 {
-        self.dimentions.0
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// Image dimensions, read from image data on first call.
+    /// Not read when loaded with new_exif_only(), except for RAW and HEIF files
+    /// and files only known from their extention.
+    pub fn dimensions(&self) -> Result<(u32, u32), Error> {
+        Ok(self.header()?.dimentions)
     }
-    pub fn height(&self) -> u32 {
-        self.dimentions.1
+    /// Bits per channel
+    pub fn bit_depth(&self) -> Option<u16> {
+        self.header().ok()?.bit_depth
+    }
+
+    // Read image header once
+    fn header(&self) -> Result<&ImageHeader, Error> {
+        if let Some(header) = self.header.get() {
+            return Ok(header);
+        }
+        if self.exif_only {
+            return Err(Error::other("Dimensions not read (EXIF only)."));
+        }
+        let header = Self::read_header(&fs::read(&self.path)?, &self.path)?;
+        Ok(self.header.get_or_init(|| header))
     }
     pub fn date(&self) -> Option<NaiveDateTime> {
         self.date
@@ -195,510 +729,3576 @@ impl Metadata {
     pub fn exif_date(&self) -> Option<String> {
         self.date().map(|d| d.to_exif_string())
     }
+    pub fn offset(&self) -> Option<FixedOffset> {
+        self.offset
+    }
+    /// Return true if DateTimeOriginal and CreateDate are both defined and differ
+    pub fn has_inconsistent_dates(&self) -> bool {
+        matches!((self.original_date, self.create_date), (Some(a), Some(b)) if a != b)
+    }
+    /// Error of little_exif when the image was loaded without its EXIF data:
+    /// EXIF values are then None and EXIF data cannot be written.
+    pub fn exif_error(&self) -> Option<&str> {
+        self.exif_error.as_deref()
+    }
+
+    /// Return true if EXIF data can be written to this file format.
+    /// Other formats (like HEIF/HEIC and RAW files) are read-only.
+    pub fn is_writable(&self) -> bool {
+        matches!(
+            self.mime.as_str(),
+            "image/jpeg" | "image/png" | "image/tiff" | "image/webp"
+        )
+    }
+    /// RAW files are read-only (except renaming and XMP sidecar)
+    pub fn is_raw(&self) -> bool {
+        raw::mime_from_extention(&self.path).is_some()
+    }
+    pub fn date_from_filename(&self) -> Option<NaiveDateTime> {
+        date_from_file_name(&self.path.file_stem()?.to_string_lossy())
+    }
+    /// File modification time, in local time
+    pub fn date_from_mtime(&self) -> Option<NaiveDateTime> {
+        let mtime = fs::metadata(&self.path).ok()?.modified().ok()?;
+        Some(chrono::DateTime::<Local>::from(mtime).naive_local())
+    }
+    pub fn matches_filter(&self, filter: &Filter) -> bool {
+        filter.matches(self)
+    }
+    /// Return true if both file name and EXIF have a date and they differ
+    pub fn has_date_mismatch(&self) -> bool {
+        matches!((self.date_from_filename(), self.date), (Some(a), Some(b)) if a != b)
+    }
+    /// Return false if the date is before min year (see set_min_year()) or in the future,
+    /// like dates of cameras with a reset clock. Images without date are plausible.
+    pub fn date_plausible(&self) -> bool {
+        self.date.map_or(true, |date| self.is_plausible_date(date))
+    }
+    /// Error of an EXIF date tag that cannot be parsed (like year 0000): the date is then ignored
+    pub fn date_error(&self) -> Option<&str> {
+        self.date_error.as_deref()
+    }
+    /// Return true if date is not before min year nor in the future (see date_plausible())
+    pub fn is_plausible_date(&self, date: NaiveDateTime) -> bool {
+        // Up to a day ahead: the image may come from another time zone
+        date.year() >= self.min_year && date <= Local::now().naive_local() + Duration::days(1)
+    }
+    /// Description, from ImageDescription, IPTC caption, XPTitle or XMP sidecar
+    /// (the first defined one, see description_source())
     pub fn description(&self) -> Option<String> {
         self.description.clone()
     }
+    pub fn description_source(&self) -> Option<DescriptionSource> {
+        self.description_source
+    }
+    pub fn user_comment(&self) -> Option<String> {
+        self.user_comment.clone()
+    }
+    /// IPTC ObjectName
+    pub fn title(&self) -> Option<String> {
+        self.iptc.title.clone()
+    }
+    /// IPTC Caption-Abstract
+    pub fn caption(&self) -> Option<String> {
+        self.iptc.caption.clone()
+    }
+    pub fn keywords(&self) -> Vec<String> {
+        self.keywords.clone()
+    }
     pub fn camera_info(&self) -> &CameraInfo {
         &self.camera_info
     }
+    pub fn gps_info(&self) -> &GpsInfo {
+        &self.gps_info
+    }
+    pub fn is_symlink(&self) -> bool {
+        self.symlink
+    }
+    pub fn xmp(&self) -> Option<&Xmp> {
+        self.xmp.as_ref()
+    }
+    pub fn rating(&self) -> Option<u16> {
+        self.rating
+    }
+    /// ImageUniqueID tag, assigned by some cameras (32 hexadecimal digits)
+    pub fn unique_id(&self) -> Option<String> {
+        self.unique_id.clone()
+    }
+    /// Use XMP sidecar values instead of EXIF ones when both exist
+    pub fn prefer_xmp(&mut self) {
+        if let Some(xmp) = &self.xmp {
+            if let Some(description) = xmp.description() {
+                self.description = Some(description);
+                self.description_source = Some(DescriptionSource::Xmp);
+            }
+            self.date = xmp.create_date().or(self.date);
+            self.rating = xmp.rating().or(self.rating);
+        }
+    }
+    /// Unit used to display the focal length of camera_info()
+    pub fn set_focal_unit(&mut self, unit: FocalUnit) {
+        self.camera_info.focal_unit = unit;
+    }
+    /// Set the year before which dates are implausible (see date_plausible(), default: MIN_YEAR)
+    pub fn set_min_year(&mut self, min_year: i32) {
+        self.min_year = min_year;
+    }
+    /// Set the pivot of two-digit years in EXIF dates (default: YEAR_PIVOT).
+    /// Dates are parsed again unless modified.
+    pub fn set_year_pivot(&mut self, year_pivot: i32) {
+        if year_pivot == self.year_pivot {
+            return;
+        }
+        self.year_pivot = year_pivot;
+        if !self.modified_tags.contains(Tag::Date) {
+            self.reload_dates();
+        }
+    }
+    /// Restore file access and modification times after save()
+    pub fn preserve_mtime(&mut self) {
+        self.preserve_mtime = true;
+    }
+    /// Also write ModifyDate when setting date
+    pub fn also_modify_date(&mut self) {
+        self.also_modify_date = true;
+    }
+    /// Keep the original file name as suffix when renaming (see fix_file_name())
+    pub fn keep_original_name(&mut self) {
+        self.keep_original_name = true;
+    }
+    /// Rename to this name (like Wedding_001) instead of %Y_%m_%d-%H_%M_%S[ - %description]
+    /// (see fix_file_name()). Files without date are still not renamed.
+    pub fn set_sequence_name(&mut self, sequence_name: &str) {
+        self.sequence_name = Some(sequence_name.to_string());
+    }
+    /// Write the extension in lowercase and canonical form (.JPEG -> .jpg) when renaming
+    /// (see fix_file_name())
+    pub fn normalize_extension(&mut self) {
+        self.normalize_extension = true;
+    }
+    /// Write Software tag (like SOFTWARE_STAMP) when EXIF data is saved.
+    /// The stamp is not displayed as camera software (see hide_software()).
+    pub fn stamp_software(&mut self, software: &str) {
+        self.software_stamp = Some(software.to_string());
+        self.hide_software(software);
+    }
+    /// Do not display this Software tag value as camera software (like a custom stamp)
+    pub fn hide_software(&mut self, software: &str) {
+        self.camera_info.hide_software(software);
+    }
+    pub fn has_maker_note(&self) -> bool {
+        self.maker_note.is_some()
+    }
+    pub fn maker_note(&self) -> Option<Vec<u8>> {
+        self.maker_note.clone()
+    }
+    /// Return true if the last save() did not write back the maker note as is
+    /// (camera specific data is lost)
+    pub fn maker_note_dropped(&self) -> bool {
+        self.maker_note_dropped
+    }
+    /// Return true if the last save() rotated a compressed TIFF image:
+    /// it has been written back uncompressed (larger file)
+    pub fn compression_dropped(&self) -> bool {
+        self.compression_dropped
+    }
+    /// Orientation tag value (1 is displayed as is, see orientation_to_string())
+    pub fn orientation(&self) -> Option<u16> {
+        Self::get_tag_u16(&self.litte_metadata, &ExifTag::Orientation(Vec::new()))
+    }
+    /// Return true if save() will rewrite EXIF data
+    /// (renaming or moving a file does not).
+    pub fn will_write_exif(&self) -> bool {
+        self.is_writable()
+            && !self
+                .modified_tags
+                .is_subset(Tag::FileName | Tag::Folder | Tag::Mtime)
+    }
 
     /// Set description.
     /// Note: file will not be modified unless you call save().
     pub fn set_description(&mut self, description: &str) {
-        if !self.description.eq(&Some(description.to_string())) {
+        if !self.description.eq(&Some(description.to_string()))
+            || self.description_source != Some(DescriptionSource::ImageDescription)
+        {
             self.description = Some(description.to_string());
+            self.description_source = Some(DescriptionSource::ImageDescription);
             self.modified_tags.insert(Tag::Description);
             self.litte_metadata
                 .set_tag(ExifTag::ImageDescription(description.to_string()));
         }
     }
 
-    /// Set date.
+    /// Set ImageUniqueID tag
     /// Note: file will not be modified unless you call save().
-    pub fn set_date(&mut self, date: NaiveDateTime) {
-        if !self.date.eq(&Some(date)) {
-            self.date = Some(date);
-            self.modified_tags.insert(Tag::Date);
-            self.litte_metadata
-                .set_tag(ExifTag::DateTimeOriginal(date.to_exif_string()));
-            self.litte_metadata
-                .set_tag(ExifTag::CreateDate(date.to_exif_string()));
+    pub fn set_unique_id(&mut self, unique_id: &str) {
+        if self.unique_id.as_deref() != Some(unique_id) {
+            self.unique_id = Some(unique_id.to_string());
+            self.set_exif_tag(ExifTag::ImageUniqueID(unique_id.to_string()));
         }
     }
 
-    /// Set date from an exif date string.
+    /// Copy description to the other description fields: XPTitle and IPTC caption (JPEG only).
     /// Note: file will not be modified unless you call save().
-    /// Will return an error if str_date cannot be parsed
-    pub fn set_date_from_exif(&mut self, str_date: String) -> Result<(), Error> {
-        let date = NaiveDateTime::from_exif_string(str_date)?;
-        self.set_date(date);
+    pub fn mirror_description(&mut self) -> Result<(), Error> {
+        let Some(description) = self.description.clone() else {
+            return Ok(());
+        };
+        let xp_title = ExifTag::XPTitle(Vec::new());
+        if Self::get_tag_ucs2(&self.litte_metadata, &xp_title).as_ref() != Some(&description) {
+            self.modified_tags.insert(Tag::Description);
+            self.litte_metadata
+                .set_tag(ExifTag::XPTitle(Self::to_ucs2(&description)));
+        }
+        // Other files have no IPTC caption to mirror to
+        if self.check_iptc().is_ok() {
+            self.set_caption(&description)?;
+        }
         Ok(())
     }
 
-    /// Check if ExifImageWidth/Height have the good values or fix them.
+    /// Set description, limited to max_length chars.
+    /// Longer descriptions are truncated with an ellipsis if truncate is set,
+    /// otherwise an error is returned.
     /// Note: file will not be modified unless you call save().
-    /// Return true if dimensions has been fixed
-    pub fn fix_dimentions(&mut self) -> bool {
-        let exif_width =
-            Self::get_tag_u32(&self.litte_metadata, &ExifTag::ExifImageWidth(Vec::new()));
-        let exif_height =
-            Self::get_tag_u32(&self.litte_metadata, &ExifTag::ExifImageHeight(Vec::new()));
-
-        if !exif_width.eq(&Some(self.width())) || !exif_height.eq(&Some(self.height())) {
-            self.modified_tags.insert(Tag::Dimensions);
-            self.litte_metadata
-                .set_tag(ExifTag::ExifImageWidth(vec![self.width()]));
-            self.litte_metadata
-                .set_tag(ExifTag::ExifImageHeight(vec![self.height()]));
-
-            true
+    pub fn set_description_limited(
+        &mut self,
+        description: &str,
+        max_length: usize,
+        truncate: bool,
+    ) -> Result<(), Error> {
+        if Self::check_description_length(description, max_length).is_ok() {
+            self.set_description(description);
+        } else if truncate {
+            let mut truncated: String = description
+                .chars()
+                .take(max_length.saturating_sub(1))
+                .collect();
+            truncated.push('…');
+            self.set_description(&truncated);
         } else {
-            false
+            return Self::check_description_length(description, max_length);
         }
+        Ok(())
     }
 
-    /// Mark file to be renamed to %Y_%m_%d-%H_%M_%S[ - %description]
-    /// Note: file will be not modified unless you call save().
-    pub fn fix_file_name(&mut self) {
-        // The file name will be computed on save
-        // to take in account potential other set_xxx calls.
-        self.modified_tags.insert(Tag::FileName);
+    /// Return an error if description is longer than max_length chars
+    /// (see set_description_limited())
+    pub fn check_description_length(description: &str, max_length: usize) -> Result<(), Error> {
+        let length = description.chars().count();
+        if length > max_length {
+            return Err(Error::other(format!(
+                "Description is too long ({} chars, maximum is {}).",
+                length, max_length
+            )));
+        }
+        Ok(())
     }
 
-    /// Mark file to be rotated if needed
+    /// Set keywords.
     /// Note: file will not be modified unless you call save().
-    pub fn fix_orientation(&mut self) {
-        self.modified_tags.insert(Tag::Orientation);
+    pub fn set_keywords(&mut self, keywords: &[String]) {
+        if !self.keywords.eq(keywords) {
+            self.keywords = keywords.to_vec();
+            self.modified_tags.insert(Tag::Keywords);
+            self.litte_metadata
+                .set_tag(ExifTag::XPKeywords(Self::to_ucs2(&self.keywords.join(";"))));
+        }
     }
 
-    /// Save modified tags
-    /// Return the list of modified tags
-    pub fn save(&mut self) -> Result<EnumSet<Tag>, Error> {
-        if !self.modified_tags.is_empty() {
-            //
-            // Rename file
-            //
-            if self.modified_tags.contains(Tag::FileName) {
-                match self.date {
-                    None => {
-                        self.modified_tags.remove(Tag::FileName);
-                    }
-                    Some(date) => {
-                        let mut new_fileprefix = date.format("%Y_%m_%d-%H_%M_%S").to_string();
-                        if self.description.is_some() {
-                            new_fileprefix.push_str(" - ");
-                            new_fileprefix.push_str(self.description.as_ref().unwrap());
-                        }
-
-                        let extention = self.path.extension().unwrap_or(OsStr::new(""));
-
-                        // Sanitize the file name and preserve space for the extention
-                        // The ext space reservation may not works for non-utf8 encoding extenttion
-                        let mut opt = sanitise_file_name::Options::DEFAULT;
-                        opt.length_limit -= extention.len() + 1;
-                        new_fileprefix =
-                            sanitise_file_name::sanitise_with_options(&new_fileprefix, &opt);
-                        let os_new_fileprefix = OsStr::new(&new_fileprefix);
-
-                        let mut os_new_filename = os_new_fileprefix.to_os_string();
-                        os_new_filename.add_ext(extention);
-                        if Some(os_new_filename.as_os_str()) != self.path.file_name() {
-                            let mut target_file_path = self.path.with_file_name(os_new_filename);
-                            // Number filename to prevent file overwriting
-                            let mut count = 0;
-                            while target_file_path.exists() {
-                                count += 1;
-                                os_new_filename = os_new_fileprefix.into();
-                                os_new_filename.push(format!("-{}", count));
-                                os_new_filename.add_ext(extention);
-                                target_file_path = self.path.with_file_name(os_new_filename);
-                            }
-                            rename(&self.path, &target_file_path)?;
-                            self.path = target_file_path;
-                        } else {
-                            // File already have the expected name
-                            self.modified_tags.remove(Tag::FileName);
-                        }
-                    }
-                }
-            }
-
-            //
-            // Rotate image
-            //
-            if self.modified_tags.contains(Tag::Orientation) {
-                if self.mime != "image/jpeg" && self.mime != "image/jpg" {
-                    // Curently, only JPEG files are supported
-                    self.modified_tags.remove(Tag::Orientation);
-                } else {
-                    let orientation =
-                        Self::get_tag_u16(&self.litte_metadata, &ExifTag::Orientation(Vec::new()));
-                    let trasform_op = match orientation {
-                        Some(2) => turbojpeg::TransformOp::Hflip,
-                        Some(3) => turbojpeg::TransformOp::Rot180,
-                        Some(4) => turbojpeg::TransformOp::Vflip,
-                        Some(5) => turbojpeg::TransformOp::Transpose,
-                        Some(6) => turbojpeg::TransformOp::Rot90,
-                        Some(7) => turbojpeg::TransformOp::Transverse,
-                        Some(8) => turbojpeg::TransformOp::Rot270,
-                        _ => turbojpeg::TransformOp::None,
-                    };
-
-                    if trasform_op == turbojpeg::TransformOp::None {
-                        self.modified_tags.remove(Tag::Orientation);
-                    } else {
-                        let jpeg_data = std::fs::read(&self.path)?;
-                        let mut transformer = match turbojpeg::Transformer::new() {
-                            Err(e) => return Err(Error::other(e.to_string())),
-                            Ok(t) => t,
-                        };
-                        let transform = turbojpeg::Transform::op(trasform_op);
-                        let mut flipped_data = turbojpeg::OutputBuf::new_owned();
-                        if let Err(e) =
-                            transformer.transform(&transform, &jpeg_data, &mut flipped_data)
-                        {
-                            return Err(Error::other(e.to_string()));
-                        }
-                        std::fs::write(&self.path, &flipped_data)?;
-                        self.litte_metadata.set_tag(ExifTag::Orientation(vec![1]));
-                    }
-                }
-            }
+    /// Set rating (0 to 5 stars).
+    /// Both Rating and RatingPercent tags are written.
+    /// Note: file will not be modified unless you call save().
+    /// Will return an error if rating is out of range.
+    pub fn set_rating(&mut self, rating: u16) -> Result<(), Error> {
+        if rating > 5 {
+            return Err(Error::other(format!(
+                "Invalid rating {} (expected 0 to 5).",
+                rating
+            )));
+        }
+        if !self.rating.eq(&Some(rating)) {
+            self.rating = Some(rating);
+            self.modified_tags.insert(Tag::Rating);
+            self.litte_metadata.set_tag(ExifTag::Rating(vec![rating]));
+            self.litte_metadata
+                .set_tag(ExifTag::RatingPercent(vec![Self::rating_to_percent(
+                    rating,
+                )]));
+        }
+        Ok(())
+    }
 
-            //
-            // Save tags
-            //
-            self.litte_metadata.write_to_file(&self.path)?;
-            let modified_tags = self.modified_tags;
-            self.modified_tags = EnumSet::empty();
-            Ok(modified_tags)
-        } else {
-            Ok(EnumSet::empty())
+    // Rating percent as written by common viewers
+    fn rating_to_percent(rating: u16) -> u16 {
+        match rating {
+            0 => 0,
+            1 => 1,
+            5 => 99,
+            _ => (rating - 1) * 25,
         }
     }
 
-    // Read a string tag
-    fn get_tag_string(litte_metadata: &LittleMetadata, tag: &ExifTag) -> Option<String> {
-        let tag = litte_metadata.get_tag(tag).next()?;
-        let endian = litte_metadata.get_endian();
-        Some(String::from_u8_vec(&tag.value_as_u8_vec(&endian), &endian))
+    /// Split a keyword list like "beach, 2006, family"
+    pub fn split_keywords(keywords: &str, separator: char) -> Vec<String> {
+        keywords
+            .split(separator)
+            .map(|k| k.trim().to_string())
+            .filter(|k| !k.is_empty())
+            .collect()
     }
 
-    // Read an u16 tag
-    fn get_tag_u16(litte_metadata: &LittleMetadata, tag: &ExifTag) -> Option<u16> {
-        let tag = litte_metadata.get_tag(tag).next()?;
-        let endian = litte_metadata.get_endian();
-        Some(u16::from_u8_vec(&tag.value_as_u8_vec(&endian), &endian))
+    /// True if a description looks like a keyword list (like "beach, 2006, family").
+    /// Conservative: at least 3 comma separated keywords of at most 3 words,
+    /// and no sentence punctuation.
+    pub fn looks_like_keywords(description: &str) -> bool {
+        let description = description.trim();
+        if description.contains(['.', '!', '?', ';', ':', '\n']) {
+            return false;
+        }
+        let keywords: Vec<&str> = description.split(',').map(str::trim).collect();
+        keywords.len() >= 3
+            && keywords.iter().all(|keyword| {
+                !keyword.is_empty()
+                    && keyword.chars().count() <= 30
+                    && keyword.split_whitespace().count() <= 3
+            })
     }
 
-    // Read an u32 tag
-    fn get_tag_u32(litte_metadata: &LittleMetadata, tag: &ExifTag) -> Option<u32> {
-        let tag = litte_metadata.get_tag(tag).next()?;
-        let endian = litte_metadata.get_endian();
-        Some(u32::from_u8_vec(&tag.value_as_u8_vec(&endian), &endian))
+    /// Set date.
+    /// Note: file will not be modified unless you call save().
+    pub fn set_date(&mut self, date: NaiveDateTime) {
+        if !self.date.eq(&Some(date)) {
+            self.write_date(date);
+        }
     }
 
-    // Read an uR64 tag
-    fn get_tag_ur64(litte_metadata: &LittleMetadata, tag: &ExifTag) -> Option<uR64> {
-        let endian = litte_metadata.get_endian();
-        litte_metadata
-            .get_tag(tag)
+    /// Set both DateTimeOriginal and CreateDate to the image date.
+    /// Note: file will not be modified unless you call save().
+    /// Return false if there is no date.
+    pub fn sync_dates(&mut self) -> bool {
+        let Some(date) = self.date else {
+            return false;
+        };
+        if self.original_date != Some(date) || self.create_date != Some(date) {
+            self.write_date(date);
+        }
+        true
+    }
+
+    /// Remove trailing NUL bytes and spaces of string tags
+    /// (ImageDescription, Make, Model, Software, Artist and Copyright).
+    /// Note: file will not be modified unless you call save().
+    /// Return false if there is nothing to trim.
+    pub fn trim_strings(&mut self) -> bool {
+        let mut trimmed = false;
+        for (tag, exif_tag) in [
+            (
+                ExifTag::ImageDescription(String::new()),
+                ExifTag::ImageDescription as fn(String) -> ExifTag,
+            ),
+            (ExifTag::Make(String::new()), ExifTag::Make),
+            (ExifTag::Model(String::new()), ExifTag::Model),
+            (ExifTag::Software(String::new()), ExifTag::Software),
+            (ExifTag::Artist(String::new()), ExifTag::Artist),
+            (ExifTag::Copyright(String::new()), ExifTag::Copyright),
+        ] {
+            let Some(value) = Self::get_tag_raw_string(&self.litte_metadata, &tag) else {
+                continue;
+            };
+            // A single NUL byte is the string terminator
+            let value_trimmed = Self::trim_string(&value).to_string();
+            if value != value_trimmed && value != format!("{}\0", value_trimmed) {
+                self.litte_metadata.set_tag(exif_tag(value_trimmed));
+                self.modified_tags.insert(Tag::Strings);
+                trimmed = true;
+            }
+        }
+        trimmed
+    }
+
+    /// Set file modification time to the image date
+    /// (local time unless OffsetTimeOriginal is defined).
+    /// Note: file will not be modified unless you call save().
+    /// Return false if there is no date.
+    pub fn set_mtime_from_date(&mut self) -> bool {
+        if self.date.is_none() {
+            return false;
+        }
+        self.modified_tags.insert(Tag::Mtime);
+        true
+    }
+
+    // Image date as an Unix timestamp
+    fn date_timestamp(&self) -> Option<i64> {
+        let date = self.date?;
+        match self.offset {
+            Some(offset) => Some(offset.from_local_datetime(&date).single()?.timestamp()),
+            None => Some(Local.from_local_datetime(&date).earliest()?.timestamp()),
+        }
+    }
+
+    // Write date to DateTimeOriginal and CreateDate tags (and ModifyDate if requested)
+    fn write_date(&mut self, date: NaiveDateTime) {
+        self.date = Some(date);
+        self.original_date = Some(date);
+        self.create_date = Some(date);
+        self.modified_tags.insert(Tag::Date);
+        self.litte_metadata
+            .set_tag(ExifTag::DateTimeOriginal(date.to_exif_string()));
+        self.litte_metadata
+            .set_tag(ExifTag::CreateDate(date.to_exif_string()));
+        if self.also_modify_date {
+            self.litte_metadata
+                .set_tag(ExifTag::ModifyDate(date.to_exif_string()));
+        }
+    }
+
+    /// Set date from an exif date string.
+    /// Note: file will not be modified unless you call save().
+    /// Will return an error if str_date cannot be parsed
+    pub fn set_date_from_exif(&mut self, str_date: String) -> Result<(), Error> {
+        let date = NaiveDateTime::from_exif_string(str_date)?;
+        self.set_date(date);
+        Ok(())
+    }
+
+    /// Remove description from all the fields it is read from:
+    /// ImageDescription, IPTC caption, XPTitle and XMP sidecar.
+    /// Note: file will not be modified unless you call save().
+    pub fn clear_description(&mut self) {
+        if self.description.is_some() {
+            self.description = None;
+            self.description_source = None;
+            self.modified_tags.insert(Tag::Description);
+            self.litte_metadata
+                .remove_tag(ExifTag::ImageDescription(String::new()));
+            self.litte_metadata.remove_tag(ExifTag::XPTitle(Vec::new()));
+            self.clear_caption();
+        }
+    }
+
+    /// Set UserComment tag, with its encoding header.
+    /// Note: file will not be modified unless you call save().
+    /// Will return an error if comment is not ASCII with the ASCII encoding.
+    pub fn set_user_comment(
+        &mut self,
+        comment: &str,
+        encoding: CommentEncoding,
+    ) -> Result<(), Error> {
+        Self::check_user_comment(comment, encoding)?;
+        let endian = self.litte_metadata.get_endian();
+        let header = match encoding {
+            CommentEncoding::Ascii => COMMENT_ASCII,
+            CommentEncoding::Unicode => COMMENT_UNICODE,
+        };
+        // Same comment with the same encoding: nothing to write
+        let stored = self
+            .litte_metadata
+            .get_tag(&ExifTag::UserComment(Vec::new()))
             .next()
-            .map(|tag| uR64::from_u8_vec(&tag.value_as_u8_vec(&endian), &endian))
+            .map(|tag| tag.value_as_u8_vec(&endian));
+        if self.user_comment.as_deref() == Some(comment)
+            && stored.is_some_and(|stored| stored.starts_with(header))
+        {
+            return Ok(());
+        }
+        let mut data = header.to_vec();
+        match encoding {
+            CommentEncoding::Ascii => {
+                data.extend_from_slice(comment.as_bytes());
+            }
+            CommentEncoding::Unicode => {
+                // UCS-2 with EXIF byte order
+                for c in comment.encode_utf16() {
+                    match endian {
+                        Endian::Big => data.extend_from_slice(&c.to_be_bytes()),
+                        Endian::Little => data.extend_from_slice(&c.to_le_bytes()),
+                    }
+                }
+            }
+        }
+        self.user_comment = Some(comment.to_string());
+        self.modified_tags.insert(Tag::Comment);
+        self.litte_metadata.set_tag(ExifTag::UserComment(data));
+        Ok(())
     }
 
-    //  Read an iR64 tag
-    fn get_tag_ir64(litte_metadata: &LittleMetadata, tag: &ExifTag) -> Option<iR64> {
-        let endian = litte_metadata.get_endian();
-        litte_metadata
-            .get_tag(tag)
+    /// Return an error if comment cannot be written with this encoding (see set_user_comment())
+    pub fn check_user_comment(comment: &str, encoding: CommentEncoding) -> Result<(), Error> {
+        if encoding == CommentEncoding::Ascii && !comment.is_ascii() {
+            return Err(Error::other(
+                "Comment is not ASCII, use the unicode encoding.",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Remove UserComment tag.
+    /// Note: file will not be modified unless you call save().
+    pub fn clear_user_comment(&mut self) {
+        if self.user_comment.is_some() {
+            self.user_comment = None;
+            self.modified_tags.insert(Tag::Comment);
+            self.litte_metadata
+                .remove_tag(ExifTag::UserComment(Vec::new()));
+        }
+    }
+
+    /// Set IPTC title (ObjectName).
+    /// Note: file will not be modified unless you call save().
+    /// Will return an Unsupported error for non-JPEG files.
+    pub fn set_title(&mut self, title: &str) -> Result<(), Error> {
+        self.check_iptc()?;
+        if !self.iptc.title.eq(&Some(title.to_string())) {
+            self.iptc.title = Some(title.to_string());
+            self.modified_tags.insert(Tag::Title);
+        }
+        Ok(())
+    }
+
+    /// Remove IPTC title.
+    /// Note: file will not be modified unless you call save().
+    pub fn clear_title(&mut self) {
+        if self.iptc.title.is_some() {
+            self.iptc.title = None;
+            self.modified_tags.insert(Tag::Title);
+        }
+    }
+
+    /// Set IPTC caption (Caption-Abstract).
+    /// Note: file will not be modified unless you call save().
+    /// Will return an Unsupported error for non-JPEG files.
+    pub fn set_caption(&mut self, caption: &str) -> Result<(), Error> {
+        self.check_iptc()?;
+        if !self.iptc.caption.eq(&Some(caption.to_string())) {
+            self.iptc.caption = Some(caption.to_string());
+            self.modified_tags.insert(Tag::Caption);
+        }
+        Ok(())
+    }
+
+    /// Remove IPTC caption.
+    /// Note: file will not be modified unless you call save().
+    pub fn clear_caption(&mut self) {
+        if self.iptc.caption.is_some() {
+            self.iptc.caption = None;
+            self.modified_tags.insert(Tag::Caption);
+        }
+    }
+
+    // IPTC is only written to JPEG files
+    fn check_iptc(&self) -> Result<(), Error> {
+        if self.mime != "image/jpeg" && self.mime != "image/jpg" {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "IPTC is only supported for JPEG files.",
+            ));
+        }
+        Ok(())
+    }
+
+    // Decode UserComment according to its encoding header.
+    // Unknown encodings (like JIS) are read as UTF-8.
+    fn decode_user_comment(data: &[u8], endian: &Endian) -> Option<String> {
+        let (header, text) = (data.get(..8)?, &data[8..]);
+        let comment = if header == COMMENT_UNICODE {
+            let text: Vec<u16> = text
+                .chunks_exact(2)
+                .map(|c| match endian {
+                    Endian::Big => u16::from_be_bytes([c[0], c[1]]),
+                    Endian::Little => u16::from_le_bytes([c[0], c[1]]),
+                })
+                .collect();
+            String::from_utf16_lossy(&text)
+        } else {
+            String::from_utf8_lossy(text).to_string()
+        };
+        let comment = comment.trim_end_matches(['\0', ' ']);
+        (!comment.is_empty()).then(|| comment.to_string())
+    }
+
+    /// Remove keywords.
+    /// Note: file will not be modified unless you call save().
+    pub fn clear_keywords(&mut self) {
+        if !self.keywords.is_empty() {
+            self.keywords.clear();
+            self.modified_tags.insert(Tag::Keywords);
+            self.litte_metadata
+                .remove_tag(ExifTag::XPKeywords(Vec::new()));
+        }
+    }
+
+    /// True if the description is read from ImageDescription and looks like a keyword list
+    /// (see migrate_description_to_keywords())
+    pub fn description_looks_like_keywords(&self) -> bool {
+        match (&self.description, self.description_source) {
+            (Some(description), Some(DescriptionSource::ImageDescription)) => {
+                Self::looks_like_keywords(description)
+            }
+            _ => false,
+        }
+    }
+
+    // First defined description field, in priority order
+    fn first_description(
+        fields: impl IntoIterator<Item = (Option<String>, DescriptionSource)>,
+    ) -> (Option<String>, Option<DescriptionSource>) {
+        fields
+            .into_iter()
+            .find_map(|(description, source)| Some((description?, source)))
+            .unzip()
+    }
+
+    /// Move an ImageDescription that looks like a keyword list (see looks_like_keywords())
+    /// into XPKeywords, after the existing keywords, and remove ImageDescription.
+    /// The other description fields are kept: the description is read again from them.
+    /// Return false if the description does not look like keywords.
+    /// Note: file will not be modified unless you call save().
+    pub fn migrate_description_to_keywords(&mut self) -> bool {
+        if !self.description_looks_like_keywords() {
+            return false;
+        }
+        let description = self.description.clone().unwrap_or_default();
+        let mut keywords = self.keywords.clone();
+        for keyword in Self::split_keywords(&description, ',') {
+            if !keywords.contains(&keyword) {
+                keywords.push(keyword);
+            }
+        }
+        self.set_keywords(&keywords);
+        self.modified_tags.insert(Tag::Description);
+        self.litte_metadata
+            .remove_tag(ExifTag::ImageDescription(String::new()));
+        let xp_title = Self::get_tag_ucs2(&self.litte_metadata, &ExifTag::XPTitle(Vec::new()))
+            .filter(|title| !title.is_empty());
+        (self.description, self.description_source) = Self::first_description([
+            (self.iptc.caption.clone(), DescriptionSource::IptcCaption),
+            (xp_title, DescriptionSource::XpTitle),
+            (
+                self.xmp.as_ref().and_then(|xmp| xmp.description()),
+                DescriptionSource::Xmp,
+            ),
+        ]);
+        true
+    }
+
+    /// Move XPKeywords into ImageDescription (comma separated list) and remove the keywords.
+    /// An existing description is only replaced with overwrite.
+    /// Return false if there is nothing to move or the description was kept.
+    /// Note: file will not be modified unless you call save().
+    pub fn migrate_keywords_to_description(&mut self, overwrite: bool) -> bool {
+        if self.keywords.is_empty() || (self.description.is_some() && !overwrite) {
+            return false;
+        }
+        self.set_description(&self.keywords.join(", "));
+        self.clear_keywords();
+        true
+    }
+
+    /// Remove rating.
+    /// Note: file will not be modified unless you call save().
+    pub fn clear_rating(&mut self) {
+        if self.rating.is_some() {
+            self.rating = None;
+            self.modified_tags.insert(Tag::Rating);
+            self.litte_metadata.remove_tag(ExifTag::Rating(Vec::new()));
+            self.litte_metadata
+                .remove_tag(ExifTag::RatingPercent(Vec::new()));
+        }
+    }
+
+    /// Remove date.
+    /// Note: file will not be modified unless you call save().
+    pub fn clear_date(&mut self) {
+        if self.date.is_some() {
+            self.date = None;
+            self.original_date = None;
+            self.create_date = None;
+            self.modified_tags.insert(Tag::Date);
+            self.litte_metadata
+                .remove_tag(ExifTag::DateTimeOriginal(String::new()));
+            self.litte_metadata
+                .remove_tag(ExifTag::CreateDate(String::new()));
+        }
+    }
+
+    /// Remove GPS location.
+    /// Note: file will not be modified unless you call save().
+    pub fn clear_gps(&mut self) {
+        for tag in Self::gps_tags() {
+            if self.litte_metadata.get_tag(&tag).next().is_some() {
+                self.modified_tags.insert(Tag::Gps);
+                self.litte_metadata.remove_tag(tag);
+            }
+        }
+        self.gps_info = GpsInfo::from_metadata(&self.litte_metadata);
+    }
+
+    /// Set GPS location (decimal degrees, see gps_info::parse_gps).
+    /// Altitude and direction are kept as is.
+    /// Note: file will not be modified unless you call save().
+    pub fn set_gps(&mut self, latitude: f64, longitude: f64) {
+        if self
+            .litte_metadata
+            .get_tag(&ExifTag::GPSVersionID(Vec::new()))
             .next()
-            .map(|tag| iR64::from_u8_vec(&tag.value_as_u8_vec(&endian), &endian))
+            .is_none()
+        {
+            self.litte_metadata
+                .set_tag(ExifTag::GPSVersionID(vec![2, 3, 0, 0]));
+        }
+        let latitude_ref = if latitude < 0f64 { "S" } else { "N" };
+        let longitude_ref = if longitude < 0f64 { "W" } else { "E" };
+        self.litte_metadata
+            .set_tag(ExifTag::GPSLatitudeRef(latitude_ref.to_string()));
+        self.litte_metadata
+            .set_tag(ExifTag::GPSLatitude(GpsInfo::f64_to_dms(latitude)));
+        self.litte_metadata
+            .set_tag(ExifTag::GPSLongitudeRef(longitude_ref.to_string()));
+        self.litte_metadata
+            .set_tag(ExifTag::GPSLongitude(GpsInfo::f64_to_dms(longitude)));
+        self.gps_info = GpsInfo::from_metadata(&self.litte_metadata);
+        self.modified_tags.insert(Tag::Gps);
     }
 
-    pub fn tags_to_string(&self, tags: &EnumSet<Tag>) -> String {
-        if tags.is_empty() {
-            "None".to_string()
+    /// Set GPSDateStamp and GPSTimeStamp from the image date,
+    /// converted to UTC if its time offset is known (see offset()).
+    /// Return false if the image has no date.
+    /// Note: file will not be modified unless you call save().
+    pub fn set_gps_time(&mut self) -> bool {
+        let Some(date) = self.date else {
+            return false;
+        };
+        let timestamp = match self.offset {
+            Some(offset) => date - offset,
+            None => date,
+        };
+        self.litte_metadata
+            .set_tag(ExifTag::GPSDateStamp(GpsInfo::date_stamp(timestamp)));
+        self.litte_metadata
+            .set_tag(ExifTag::GPSTimeStamp(GpsInfo::time_stamp(timestamp)));
+        self.gps_info = GpsInfo::from_metadata(&self.litte_metadata);
+        self.modified_tags.insert(Tag::Gps);
+        true
+    }
+
+    // All GPS tags
+    fn gps_tags() -> Vec<ExifTag> {
+        vec![
+            ExifTag::GPSVersionID(Vec::new()),
+            ExifTag::GPSLatitudeRef(String::new()),
+            ExifTag::GPSLatitude(Vec::new()),
+            ExifTag::GPSLongitudeRef(String::new()),
+            ExifTag::GPSLongitude(Vec::new()),
+            ExifTag::GPSAltitudeRef(Vec::new()),
+            ExifTag::GPSAltitude(Vec::new()),
+            ExifTag::GPSTimeStamp(Vec::new()),
+            ExifTag::GPSDateStamp(String::new()),
+            ExifTag::GPSImgDirectionRef(String::new()),
+            ExifTag::GPSImgDirection(Vec::new()),
+        ]
+    }
+
+    /// Remove all EXIF tags but the ones of the given categories.
+    /// Tags not handled by this tool are kept with Tag::Others.
+    /// IPTC and XMP sidecar are not modified.
+    /// Note: file will not be modified unless you call save().
+    pub fn strip(&mut self, keep: &EnumSet<Tag>) {
+        let keep_ids: Vec<u16> = self
+            .litte_metadata
+            .into_iter()
+            .filter(|tag| keep.contains(Self::tag_category(tag)))
+            .map(|tag| tag.as_u16())
+            .collect();
+        let removed: EnumSet<Tag> = self
+            .remove_tags_except(&keep_ids)
+            .iter()
+            .map(Self::tag_category)
+            .collect();
+        if removed.is_empty() {
+            return;
+        }
+        self.modified_tags |= removed;
+
+        // Update loaded values
+        if removed.contains(Tag::Description) {
+            self.description = None;
+            self.description_source = None;
+        }
+        if removed.contains(Tag::Comment) {
+            self.user_comment = None;
+        }
+        if removed.contains(Tag::Keywords) {
+            self.keywords.clear();
+        }
+        if removed.contains(Tag::Rating) {
+            self.rating = None;
+        }
+        if removed.contains(Tag::Date) {
+            self.date = None;
+            self.original_date = None;
+            self.create_date = None;
+            self.offset = None;
+        }
+        if removed.contains(Tag::MakerNote) {
+            self.maker_note = None;
+        }
+        self.gps_info = GpsInfo::from_metadata(&self.litte_metadata);
+    }
+
+    // Remove all raw EXIF tags except the ones with these IDs (and the IFD pointers).
+    // Return the removed tags.
+    fn remove_tags_except(&mut self, keep_ids: &[u16]) -> Vec<ExifTag> {
+        let removed: Vec<ExifTag> = self
+            .litte_metadata
+            .into_iter()
+            .filter(|tag| {
+                !keep_ids.contains(&tag.as_u16()) && !IFD_POINTER_IDS.contains(&tag.as_u16())
+            })
+            .cloned()
+            .collect();
+        for tag in removed.iter() {
+            self.litte_metadata.remove_tag(tag.clone());
+        }
+        removed
+    }
+
+    /// Category of an EXIF tag: all the EXIF tags written for a Tag.
+    /// Like Date: DateTimeOriginal, CreateDate, ModifyDate and time offsets.
+    pub fn tag_category(tag: &ExifTag) -> Tag {
+        let id = tag.as_u16();
+        let is = |tags: &[ExifTag]| tags.iter().any(|t| t.as_u16() == id);
+        if is(&[
+            ExifTag::ImageDescription(String::new()),
+            ExifTag::XPTitle(Vec::new()),
+        ]) {
+            Tag::Description
+        } else if is(&[ExifTag::UserComment(Vec::new())]) {
+            Tag::Comment
+        } else if is(&[ExifTag::XPKeywords(Vec::new())]) {
+            Tag::Keywords
+        } else if is(&[
+            ExifTag::Rating(Vec::new()),
+            ExifTag::RatingPercent(Vec::new()),
+        ]) {
+            Tag::Rating
+        } else if is(&[
+            ExifTag::DateTimeOriginal(String::new()),
+            ExifTag::CreateDate(String::new()),
+            ExifTag::ModifyDate(String::new()),
+            ExifTag::OffsetTime(String::new()),
+            ExifTag::OffsetTimeOriginal(String::new()),
+            ExifTag::OffsetTimeDigitized(String::new()),
+        ]) {
+            Tag::Date
+        } else if is(&Self::gps_tags()) {
+            Tag::Gps
+        } else if is(&[ExifTag::Orientation(Vec::new())]) {
+            Tag::Orientation
+        } else if is(&[
+            ExifTag::ExifImageWidth(Vec::new()),
+            ExifTag::ExifImageHeight(Vec::new()),
+        ]) {
+            Tag::Dimensions
+        } else if is(&[ExifTag::MakerNote(Vec::new())]) {
+            Tag::MakerNote
         } else {
-            tags.iter()
-                .map(|t| t.to_string_comment(self))
-                .collect::<Vec<_>>()
-                .join(", ")
+            Tag::Others
         }
     }
 
-    fn flash_code_to_string(flash_code: u16) -> String {
-        match flash_code {
-            0x00 => "No Flash",
-            0x01 => "Fired",
-            0x05 => "Fired, Return not detected",
-            0x07 => "Fired, Return detected",
-            0x08 => "On, Did not fire",
-            0x09 => "On, Fired",
-            0x0d => "On, Return not detected",
-            0x0f => "On, Return detected",
-            0x10 => "Off, Did not fire",
-            0x14 => "Off, Did not fire, Return not detected",
-            0x18 => "Auto, Did not fire",
-            0x19 => "Auto, Fired",
-            0x1d => "Auto, Fired, Return not detected",
-            0x1f => "Auto, Fired, Return detected",
-            0x20 => "No flash function",
-            0x30 => "Off, No flash function",
-            0x41 => "Fired, Red-eye reduction",
-            0x45 => "Fired, Red-eye reduction, Return not detected",
-            0x47 => "Fired, Red-eye reduction, Return detected",
-            0x49 => "On, Red-eye reduction",
-            0x4d => "On, Red-eye reduction, Return not detected",
-            0x4f => "On, Red-eye reduction, Return detected",
-            0x50 => "Off, Red-eye reduction",
-            0x58 => "Auto, Did not fire, Red-eye reduction",
-            0x59 => "Auto, Fired, Red-eye reduction",
-            0x5d => "Auto, Fired, Red-eye reduction, Return not detected",
-            0x5f => "Auto, Fired, Red-eye reduction, Return detected",
-            _ => "Unknown flash code",
-        }
-        .to_string()
+    /// Set date from a string in one of the common formats
+    /// (see parse_date_flexible).
+    /// Note: file will not be modified unless you call save().
+    /// Will return an error if str_date cannot be parsed
+    pub fn set_date_flexible(&mut self, str_date: &str) -> Result<(), Error> {
+        let date = parse_date_flexible(str_date)?;
+        self.set_date(date);
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::fs::{self, OpenOptions};
+    /// Shift date by delta.
+    /// Note: file will not be modified unless you call save().
+    /// Return false if there is no date to shift.
+    pub fn shift_date(&mut self, delta: Duration) -> bool {
+        match self.date {
+            None => false,
+            Some(date) => {
+                self.set_date(date + delta);
+                true
+            }
+        }
+    }
 
-    use chrono::NaiveDate;
-    use enumset::enum_set;
+    /// Compute the delta between the date of this image and the given (true) date.
+    /// Will return an error if this image has no date or if str_date cannot be parsed.
+    pub fn date_offset_to(&self, str_date: &str) -> Result<Duration, Error> {
+        let Some(date) = self.date else {
+            return Err(Error::other(format!(
+                "{} has no EXIF date.",
+                self.path.display()
+            )));
+        };
+        Ok(parse_date_flexible(str_date)? - date)
+    }
 
-    use super::*;
+    /// Convert date to UTC according to OffsetTimeOriginal tag.
+    /// Offset tags are set to +00:00 (the original offset is lost).
+    /// Note: file will not be modified unless you call save().
+    /// Return false if there is no date or no offset to convert from.
+    pub fn normalize_to_utc(&mut self) -> bool {
+        let (Some(date), Some(offset)) = (self.date, self.offset) else {
+            return false;
+        };
+        let utc = FixedOffset::east_opt(0).unwrap();
+        if offset != utc {
+            self.set_date(date - offset);
+            self.offset = Some(utc);
+            self.litte_metadata
+                .set_tag(ExifTag::OffsetTimeOriginal(utc.to_exif_string()));
+            self.litte_metadata
+                .set_tag(ExifTag::OffsetTimeDigitized(utc.to_exif_string()));
+        }
+        true
+    }
 
-    #[test]
-    fn invalid_files() {
-        let result = Metadata::new(Path::new("/do/not/exists"));
-        assert!(result.is_err());
-        let result = Metadata::new(Path::new("tests/empty"));
-        assert!(result.is_err());
-        let result = Metadata::new(Path::new("tests/archive.tar.gz"));
-        assert!(result.is_err());
-        let result = Metadata::new(Path::new("tests/no_exif.png"));
-        assert!(result.is_err());
+    /// Return true if ExifImageWidth/Height do not match the real image dimensions
+    /// (swapped for images rotated by 90° with set_dims(Dims::Rotated)).
+    /// Return false if the real dimensions cannot be read (see dimensions()).
+    pub fn needs_dimensions_fix(&self) -> bool {
+        self.expected_exif_dimensions()
+            .is_some_and(|expected| self.exif_dimensions() != Some(expected))
+    }
+
+    /// Dimensions stored in ExifImageWidth/Height tags (may differ from dimensions())
+    pub fn exif_dimensions(&self) -> Option<(u32, u32)> {
+        Some((
+            Self::get_tag_u32(&self.litte_metadata, &ExifTag::ExifImageWidth(Vec::new()))?,
+            Self::get_tag_u32(&self.litte_metadata, &ExifTag::ExifImageHeight(Vec::new()))?,
+        ))
+    }
+
+    /// Return true if the file name is not %Y_%m_%d-%H_%M_%S[ - %description]
+    /// (files without date and symbolic links are never renamed)
+    pub fn needs_rename(&self) -> bool {
+        self.date.is_some() && !self.symlink && self.new_file_name(Tag::FileName.into()).is_some()
+    }
+
+    /// Return true if the Orientation tag matches the stored image dimensions (or is not set).
+    /// Return false if the orientation tag seems wrong (see orientation_consistent_with_dims())
+    pub fn orientation_consistent(&self) -> bool {
+        match Self::get_tag_u16(&self.litte_metadata, &ExifTag::Orientation(Vec::new())) {
+            // Unknown dimensions: nothing to compare to
+            Some(orientation) => match self.dimensions() {
+                Ok((width, height)) => orientation_consistent_with_dims(orientation, width, height),
+                Err(_) => true,
+            },
+            None => true,
+        }
+    }
+
+    /// Return true if the image is not displayed as is (Orientation tag)
+    pub fn needs_orientation_fix(&self) -> bool {
+        !matches!(
+            Self::get_tag_u16(&self.litte_metadata, &ExifTag::Orientation(Vec::new())),
+            None | Some(1)
+        )
+    }
+
+    /// Check if ExifImageWidth/Height have the good values or fix them.
+    /// Note: file will not be modified unless you call save().
+    /// Return true if dimensions has been fixed (false if they cannot be read)
+    pub fn fix_dimentions(&mut self) -> bool {
+        if self.needs_dimensions_fix() {
+            let Some((width, height)) = self.expected_exif_dimensions() else {
+                return false;
+            };
+            self.modified_tags.insert(Tag::Dimensions);
+            self.litte_metadata
+                .set_tag(ExifTag::ExifImageWidth(vec![width]));
+            self.litte_metadata
+                .set_tag(ExifTag::ExifImageHeight(vec![height]));
+
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Set which dimensions ExifImageWidth/Height of images rotated by 90° should hold
+    /// (see fix_dimentions(), default: Dims::Stored)
+    pub fn set_dims(&mut self, dims: Dims) {
+        self.dims = dims;
+    }
+
+    // ExifImageWidth/Height values according to dims option (None if dimensions cannot be read)
+    fn expected_exif_dimensions(&self) -> Option<(u32, u32)> {
+        let (width, height) = self.dimensions().ok()?;
+        let orientation =
+            Self::get_tag_u16(&self.litte_metadata, &ExifTag::Orientation(Vec::new()));
+        Some(match orientation {
+            Some(5..=8) if self.dims == Dims::Rotated => (height, width),
+            _ => (width, height),
+        })
+    }
+
+    /// Mark file to be renamed to %Y_%m_%d-%H_%M_%S[ - %description]
+    /// Note: file will be not modified unless you call save().
+    /// Symbolic links are never renamed.
+    pub fn fix_file_name(&mut self) {
+        // The file name will be computed on save
+        // to take in account potential other set_xxx calls.
+        self.modified_tags.insert(Tag::FileName);
+    }
+
+    /// Set how descriptions are written in new file names (see FileNameOptions)
+    pub fn set_file_name_options(&mut self, file_name_options: FileNameOptions) {
+        self.file_name_options = file_name_options;
+    }
+
+    /// Set what to do when the new file name is already used (see Conflict)
+    pub fn set_on_conflict(&mut self, on_conflict: Conflict) {
+        self.on_conflict = on_conflict;
+    }
+
+    /// Return the existing file that has the new name of this file, if any
+    pub fn rename_conflict(&self) -> Option<PathBuf> {
+        let (folder, mut filename, extention) = self.new_file_name(self.pending_tags())?;
+        filename.add_ext(&extention);
+        let file_path = folder.join(filename);
+        file_path.exists().then_some(file_path)
+    }
+
+    /// Mark file to be moved into folder (created if needed).
+    /// If copy is set, the file is copied and only the copy is modified.
+    /// File names may be numbered to prevent erasing file with same name.
+    /// Note: file will not be modified unless you call save().
+    pub fn move_to_folder(&mut self, folder: &Path, copy: bool) {
+        self.target_folder = Some(folder.to_path_buf());
+        self.copy = copy;
+        self.modified_tags.insert(Tag::Folder);
+    }
+
+    /// Mark file to be rotated if needed
+    /// Note: file will not be modified unless you call save().
+    /// The image is rotated in memory by save() and written into a temporary file
+    /// renamed over the original (see fix_orientation_in_memory() to avoid it).
+    /// Return the orientation that will be corrected (None if already normal)
+    /// or an Unsupported error for non-JPEG files.
+    pub fn fix_orientation(&mut self) -> Result<Option<u16>, Error> {
+        let orientation =
+            Self::get_tag_u16(&self.litte_metadata, &ExifTag::Orientation(Vec::new()));
+        match orientation {
+            None | Some(1) => Ok(None),
+            Some(_)
+                if !matches!(
+                    self.mime.as_str(),
+                    "image/jpeg" | "image/jpg" | "image/tiff"
+                ) =>
+            {
+                Err(Error::new(
+                    ErrorKind::Unsupported,
+                    "Orientation fix is only supported for JPEG and TIFF files.",
+                ))
+            }
+            Some(_) => {
+                self.modified_tags.insert(Tag::Orientation);
+                Ok(orientation)
+            }
+        }
+    }
+
+    /// Like fix_orientation(), but save() writes the rotated image directly to its final
+    /// path, without temporary file: one file creation and rename less per image,
+    /// but the original file is damaged if writing fails or is interrupted.
+    /// Note: file will not be modified unless you call save().
+    pub fn fix_orientation_in_memory(&mut self) -> Result<Option<u16>, Error> {
+        let orientation = self.fix_orientation()?;
+        self.in_memory = orientation.is_some();
+        Ok(orientation)
+    }
+
+    /// Rotate the image back to how it was stored before fix_orientation()
+    /// and restore its Orientation tag (to undo an orientation fix).
+    /// Note: file will not be modified unless you call save().
+    pub fn restore_orientation(&mut self, orientation: u16) -> Result<(), Error> {
+        // The transformation of the inverse orientation rotates the image back
+        let inverse = match orientation {
+            6 => 8,
+            8 => 6,
+            _ => orientation,
+        };
+        self.litte_metadata
+            .set_tag(ExifTag::Orientation(vec![inverse]));
+        if self.fix_orientation()?.is_some() {
+            self.restored_orientation = Some(orientation);
+        }
+        Ok(())
+    }
+
+    /// Save modified tags
+    /// Return the list of modified tags
+    pub fn save(&mut self) -> Result<EnumSet<Tag>, Error> {
+        if !self.modified_tags.is_empty() {
+            // Keep file times to restore them once done
+            let file_times = if self.preserve_mtime {
+                let file_metadata = fs::metadata(&self.path)?;
+                Some((
+                    FileTime::from_last_access_time(&file_metadata),
+                    FileTime::from_last_modification_time(&file_metadata),
+                ))
+            } else {
+                None
+            };
+
+            // Renaming or moving files does not require to write EXIF data
+            // Description, date and rating can be written to the XMP sidecar only
+            let mut file_tags = Tag::FileName | Tag::Folder | Tag::Mtime;
+            if self.xmp.is_some() {
+                file_tags |= Tag::Description | Tag::Date | Tag::Rating;
+            }
+            if self.is_raw() && !self.modified_tags.is_subset(file_tags) {
+                return Err(Error::other("RAW files are read-only."));
+            }
+            if !self.is_writable() && !self.modified_tags.is_subset(file_tags) {
+                return Err(Error::other(format!(
+                    "Cannot write EXIF data to {} files.",
+                    self.mime
+                )));
+            }
+            if let Some(exif_error) = &self.exif_error {
+                if !self.modified_tags.is_subset(file_tags) {
+                    return Err(Error::other(format!(
+                        "Cannot write EXIF data: {}",
+                        exif_error
+                    )));
+                }
+            }
+
+            //
+            // New file name and/or location
+            //
+            self.modified_tags = self.pending_tags();
+            let target_file_path = self.target_path(self.modified_tags);
+            match &target_file_path {
+                None => {
+                    // File already have the expected name and location
+                    self.modified_tags.remove(Tag::FileName);
+                    self.modified_tags.remove(Tag::Folder);
+                }
+                Some(target_file_path) => {
+                    if target_file_path.file_name() == self.path.file_name() {
+                        self.modified_tags.remove(Tag::FileName);
+                    }
+                    if target_file_path.parent() == self.path.parent() {
+                        self.modified_tags.remove(Tag::Folder);
+                    }
+                    if self.modified_tags.contains(Tag::Folder) {
+                        create_dir_all(target_file_path.parent().unwrap_or(Path::new("")))?;
+                    }
+                }
+            }
+            let copy_file = self.copy && self.modified_tags.contains(Tag::Folder);
+
+            //
+            // Rotate image and save tags into a temporary file, then move it to its final
+            // (maybe renamed) path: on error, the original file is left untouched
+            // (unless fix_orientation_in_memory() was used).
+            //
+            if self.will_write_exif() || self.modified_tags.contains(Tag::Orientation) {
+                let data = self.updated_data()?;
+                let final_path = match &target_file_path {
+                    Some(target_file_path) => target_file_path.clone(),
+                    // Write through symbolic links
+                    None => fs::canonicalize(&self.path)?,
+                };
+                if self.in_memory && Self::file_extension(&self.mime).is_some() {
+                    // Single write, see fix_orientation_in_memory()
+                    self.in_memory = false;
+                    fs::write(&final_path, &data)?;
+                    if target_file_path.is_some() {
+                        fs::set_permissions(&final_path, fs::metadata(&self.path)?.permissions())?;
+                    }
+                } else {
+                    let folder = final_path.parent().unwrap_or(Path::new(""));
+                    let mut temp_file = tempfile::NamedTempFile::new_in(folder)?;
+                    temp_file.write_all(&data)?;
+                    if Self::file_extension(&self.mime).is_none() {
+                        self.litte_metadata.write_to_file(temp_file.path())?;
+                    }
+                    fs::set_permissions(temp_file.path(), fs::metadata(&self.path)?.permissions())?;
+                    if let Err(e) = temp_file.persist(&final_path) {
+                        return Err(e.error);
+                    }
+                }
+                if target_file_path.is_some() && !copy_file {
+                    if let Err(e) = fs::remove_file(&self.path) {
+                        // Do not keep two copies
+                        let _ = fs::remove_file(&final_path);
+                        return Err(e);
+                    }
+                }
+            } else if let Some(target_file_path) = &target_file_path {
+                if copy_file {
+                    copy(&self.path, target_file_path)?;
+                } else {
+                    file_list::move_file(&self.path, target_file_path)?;
+                }
+            }
+            if let Some(target_file_path) = target_file_path {
+                if let Some(xmp) = &mut self.xmp {
+                    xmp.follow(&target_file_path, copy_file)?;
+                }
+                if copy_file {
+                    self.symlink = false;
+                }
+                self.path = target_file_path;
+            }
+
+            //
+            // Save XMP sidecar
+            //
+            if let Some(xmp) = &mut self.xmp {
+                if self.modified_tags.contains(Tag::Description) {
+                    xmp.set_description(self.description.as_deref())?;
+                }
+                if self.modified_tags.contains(Tag::Date) {
+                    xmp.set_create_date(self.date)?;
+                }
+                if self.modified_tags.contains(Tag::Rating) {
+                    xmp.set_rating(self.rating)?;
+                }
+                if !self
+                    .modified_tags
+                    .is_disjoint(Tag::Description | Tag::Date | Tag::Rating)
+                {
+                    xmp.save()?;
+                }
+            }
+            if let Some((atime, mtime)) = file_times {
+                // self.path is the renamed/moved/copied file
+                filetime::set_file_times(&self.path, atime, mtime)?;
+            }
+            if self.modified_tags.contains(Tag::Mtime) {
+                match self.date_timestamp() {
+                    Some(timestamp) => filetime::set_file_mtime(
+                        &self.path,
+                        FileTime::from_unix_time(timestamp, 0),
+                    )?,
+                    None => {
+                        self.modified_tags.remove(Tag::Mtime);
+                    }
+                }
+            }
+            let modified_tags = self.modified_tags;
+            self.modified_tags = EnumSet::empty();
+            Ok(modified_tags)
+        } else {
+            Ok(EnumSet::empty())
+        }
+    }
+
+    // File content with rotated image and modified tags
+    fn updated_data(&mut self) -> Result<Vec<u8>, Error> {
+        let mut data = fs::read(&self.path)?;
+        self.compression_dropped = false;
+
+        // Rotate image
+        if self.modified_tags.contains(Tag::Orientation) {
+            let trasform_op = self.orientation_transform();
+            if trasform_op == turbojpeg::TransformOp::None {
+                self.modified_tags.remove(Tag::Orientation);
+            } else if self.mime == "image/tiff" {
+                self.compression_dropped = raw::compression(&data).is_some_and(|c| c != 1);
+                data = Self::rotate_tiff(&data, trasform_op)?;
+                self.litte_metadata.set_tag(ExifTag::Orientation(vec![self
+                    .restored_orientation
+                    .unwrap_or(1)]));
+                self.rotate_thumbnail(trasform_op)?;
+            } else if self.mime != "image/jpeg" && self.mime != "image/jpg" {
+                // Curently, only JPEG and TIFF files are supported
+                self.modified_tags.remove(Tag::Orientation);
+            } else {
+                let mut transformer = match turbojpeg::Transformer::new() {
+                    Err(e) => return Err(Error::other(e.to_string())),
+                    Ok(t) => t,
+                };
+                let transform = turbojpeg::Transform::op(trasform_op);
+                let mut flipped_data = turbojpeg::OutputBuf::new_owned();
+                if let Err(e) = transformer.transform(&transform, &data, &mut flipped_data) {
+                    return Err(Error::other(e.to_string()));
+                }
+                data = flipped_data.to_vec();
+                self.litte_metadata.set_tag(ExifTag::Orientation(vec![self
+                    .restored_orientation
+                    .unwrap_or(1)]));
+                self.rotate_thumbnail(trasform_op)?;
+            }
+        }
+
+        // Save tags
+        if self.is_writable() {
+            if let Some(software) = &self.software_stamp {
+                if self.will_write_exif() {
+                    self.litte_metadata
+                        .set_tag(ExifTag::Software(software.clone()));
+                }
+            }
+            // Without file extension, tags are written to the file (see save())
+            if let Some(file_extension) = Self::file_extension(&self.mime) {
+                // PNG EXIF may be stored in several chunks: keep only the written one
+                if self.mime == "image/png" {
+                    data = png::remove_exif(&data)?;
+                }
+                self.litte_metadata
+                    .write_to_vec(&mut data, file_extension)?;
+                if !self.modified_tags.is_disjoint(Tag::Title | Tag::Caption) {
+                    data = self.iptc.update(&data)?;
+                }
+                // Check the maker note has been written back byte-for-byte
+                self.maker_note_dropped = self.maker_note.is_some()
+                    && Self::file_extension(&self.mime)
+                        .and_then(|file_extension| {
+                            LittleMetadata::new_from_vec(&data, file_extension).ok()
+                        })
+                        .and_then(|written| Self::get_maker_note(&written))
+                        != self.maker_note;
+            }
+        }
+        Ok(data)
+    }
+
+    /// Tags that save() will write and the new file path if the file will be renamed or moved
+    pub fn planned_changes(&self) -> (EnumSet<Tag>, Option<PathBuf>) {
+        let mut tags = self.pending_tags();
+        let target = self.target_path(tags);
+        if target.is_none() {
+            tags.remove(Tag::FileName);
+            tags.remove(Tag::Folder);
+        }
+        (tags, target)
+    }
+
+    // Modified tags, without renaming or moving that cannot be done
+    fn pending_tags(&self) -> EnumSet<Tag> {
+        let mut tags = self.modified_tags;
+        if self.symlink {
+            // Do not rename or move through a symbolic link
+            tags.remove(Tag::FileName);
+            if !self.copy {
+                tags.remove(Tag::Folder);
+            }
+        }
+        if self.date.is_none() {
+            tags.remove(Tag::FileName);
+            tags.remove(Tag::Mtime);
+        }
+        tags
+    }
+
+    // New path of the file if it has to be renamed and/or moved.
+    // If this path is already used, the file is numbered, not renamed
+    // or overwrites the existing one according to on_conflict.
+    fn target_path(&self, tags: EnumSet<Tag>) -> Option<PathBuf> {
+        let (folder, prefix, extention) = self.new_file_name(tags)?;
+        let mut filename = prefix.clone();
+        filename.add_ext(&extention);
+        let file_path = folder.join(filename);
+        if !file_path.exists() {
+            return Some(file_path);
+        }
+        match self.on_conflict {
+            Conflict::Number => Some(unique_file_path(&folder, &prefix, &extention)),
+            Conflict::Skip => None,
+            Conflict::Overwrite => Some(file_path),
+        }
+    }
+
+    // New folder, file prefix and extention of the file, whether this name is already used or not
+    // (None if the file already has the expected name and location)
+    fn new_file_name(&self, tags: EnumSet<Tag>) -> Option<(PathBuf, OsString, OsString)> {
+        if !tags.contains(Tag::FileName) && !tags.contains(Tag::Folder) {
+            return None;
+        }
+        let mut extention = self.path.extension().unwrap_or(OsStr::new("")).to_owned();
+        if self.normalize_extension && tags.contains(Tag::FileName) {
+            extention = normalized_extension(&extention);
+        }
+        let os_new_fileprefix = match self.date {
+            Some(date) if tags.contains(Tag::FileName) => {
+                self.normalized_file_prefix(date, &extention)
+            }
+            _ => self.path.file_stem().unwrap_or(OsStr::new("")).to_owned(),
+        };
+        let folder = match &self.target_folder {
+            Some(folder) if tags.contains(Tag::Folder) => folder.clone(),
+            _ => self.path.parent().unwrap_or(Path::new("")).to_path_buf(),
+        };
+
+        let mut os_new_filename = os_new_fileprefix.clone();
+        os_new_filename.add_ext(&extention);
+        if folder.join(&os_new_filename) == self.path {
+            return None;
+        }
+        Some((folder, os_new_fileprefix, extention))
+    }
+
+    // Transformation that displays the image as expected, according to Orientation tag
+    fn orientation_transform(&self) -> turbojpeg::TransformOp {
+        let orientation =
+            Self::get_tag_u16(&self.litte_metadata, &ExifTag::Orientation(Vec::new()));
+        match orientation {
+            Some(2) => turbojpeg::TransformOp::Hflip,
+            Some(3) => turbojpeg::TransformOp::Rot180,
+            Some(4) => turbojpeg::TransformOp::Vflip,
+            Some(5) => turbojpeg::TransformOp::Transpose,
+            Some(6) => turbojpeg::TransformOp::Rot90,
+            Some(7) => turbojpeg::TransformOp::Transverse,
+            Some(8) => turbojpeg::TransformOp::Rot270,
+            _ => turbojpeg::TransformOp::None,
+        }
+    }
+
+    /// Write the EXIF thumbnail into folder (created if needed) as <name>_thumb.jpg.
+    /// If rotate is set, the thumbnail is rotated according to the Orientation tag.
+    /// The thumbnail file may be numbered to prevent erasing a file with same name.
+    /// Return the path of the thumbnail file or None if there is no thumbnail.
+    pub fn extract_thumbnail(&self, folder: &Path, rotate: bool) -> Result<Option<PathBuf>, Error> {
+        let Some(mut thumbnail) = self.thumbnail() else {
+            return Ok(None);
+        };
+        if rotate {
+            thumbnail = Self::transform_thumbnail(&thumbnail, self.orientation_transform())?;
+        }
+        create_dir_all(folder)?;
+        let mut prefix = self.path.file_stem().unwrap_or_default().to_os_string();
+        prefix.push("_thumb");
+        let path = unique_file_path(folder, &prefix, OsStr::new("jpg"));
+        fs::write(&path, thumbnail)?;
+        Ok(Some(path))
+    }
+
+    /// Read the EXIF thumbnail (JPEG data)
+    pub fn thumbnail(&self) -> Option<Vec<u8>> {
+        match self
+            .litte_metadata
+            .get_tag(&ExifTag::ThumbnailOffset(Vec::new(), Vec::new()))
+            .next()?
+        {
+            ExifTag::ThumbnailOffset(_, data) if !data.is_empty() => Some(data.clone()),
+            _ => None,
+        }
+    }
+
+    // Apply to the EXIF thumbnail (if any) the transformation applied to the image
+    fn rotate_thumbnail(&mut self, transform_op: turbojpeg::TransformOp) -> Result<(), Error> {
+        let Some(thumbnail) = self.thumbnail() else {
+            return Ok(());
+        };
+        let data = Self::transform_thumbnail(&thumbnail, transform_op)?;
+        self.litte_metadata
+            .set_tag(ExifTag::ThumbnailLength(vec![data.len() as u32]));
+        self.litte_metadata
+            .set_tag(ExifTag::ThumbnailOffset(vec![0], data));
+        Ok(())
+    }
+
+    // Rotate TIFF pixels. TIFF files are not compressed like JPEG ones (no block
+    // transform needed): the image is decoded, rotated then encoded again.
+    // Note: the whole image is decoded in memory, this may be costly for large scans.
+    // The image is encoded uncompressed, whatever its original compression.
+    fn rotate_tiff(data: &[u8], transform_op: turbojpeg::TransformOp) -> Result<Vec<u8>, Error> {
+        let tiff = match image::load_from_memory_with_format(data, ImageFormat::Tiff) {
+            Err(e) => return Err(Error::other(e.to_string())),
+            Ok(t) => t,
+        };
+        let tiff = Self::transform_image(tiff, transform_op);
+        let mut data: Vec<u8> = Vec::new();
+        if let Err(e) = tiff.write_to(&mut Cursor::new(&mut data), ImageFormat::Tiff) {
+            return Err(Error::other(e.to_string()));
+        }
+        // EXIF tags are written back when saving tags
+        Ok(data)
+    }
+
+    // Apply a transformation to a decoded image
+    fn transform_image(
+        image: image::DynamicImage,
+        transform_op: turbojpeg::TransformOp,
+    ) -> image::DynamicImage {
+        match transform_op {
+            turbojpeg::TransformOp::Hflip => image.fliph(),
+            turbojpeg::TransformOp::Rot180 => image.rotate180(),
+            turbojpeg::TransformOp::Vflip => image.flipv(),
+            turbojpeg::TransformOp::Transpose => image.rotate90().fliph(),
+            turbojpeg::TransformOp::Rot90 => image.rotate90(),
+            turbojpeg::TransformOp::Transverse => image.rotate90().flipv(),
+            turbojpeg::TransformOp::Rot270 => image.rotate270(),
+            _ => image,
+        }
+    }
+
+    // Apply a transformation to a JPEG thumbnail
+    fn transform_thumbnail(
+        thumbnail: &[u8],
+        transform_op: turbojpeg::TransformOp,
+    ) -> Result<Vec<u8>, Error> {
+        if transform_op == turbojpeg::TransformOp::None {
+            return Ok(thumbnail.to_vec());
+        }
+        let thumbnail = match image::load_from_memory(thumbnail) {
+            Err(e) => return Err(Error::other(e.to_string())),
+            Ok(t) => t,
+        };
+        let thumbnail = Self::transform_image(thumbnail, transform_op);
+        let mut data: Vec<u8> = Vec::new();
+        if let Err(e) = thumbnail.write_to(&mut Cursor::new(&mut data), ImageFormat::Jpeg) {
+            return Err(Error::other(e.to_string()));
+        }
+        Ok(data)
+    }
+
+    // Compute file name %Y_%m_%d-%H_%M_%S[ - %description][ - original name] (without extention)
+    fn normalized_file_prefix(&self, date: NaiveDateTime, extention: &OsStr) -> OsString {
+        let new_fileprefix = match &self.sequence_name {
+            Some(sequence_name) => sequence_name.clone(),
+            None => {
+                let separator = self.file_name_part(" - ");
+                let mut new_fileprefix = date.format(NORMALIZED_DATE_FORMAT).to_string();
+                if let Some(description) = self.file_name_description() {
+                    new_fileprefix.push_str(&separator);
+                    new_fileprefix.push_str(&description);
+                }
+                if self.keep_original_name {
+                    if let Some(original_name) = self.original_name() {
+                        new_fileprefix.push_str(&separator);
+                        new_fileprefix.push_str(&original_name);
+                    }
+                }
+                new_fileprefix
+            }
+        };
+
+        // Sanitize the file name and preserve space for the extention
+        // The ext space reservation may not works for non-utf8 encoding extenttion
+        let mut opt = sanitise_file_name::Options::DEFAULT;
+        opt.length_limit -= extention.len() + 1;
+        OsString::from(sanitise_file_name::sanitise_with_options(
+            &new_fileprefix,
+            &opt,
+        ))
+    }
+
+    // Original file name (without extention). For already normalized names,
+    // this is what follows the date and description (None if nothing follows).
+    fn original_name(&self) -> Option<String> {
+        let stem = self.path.file_stem()?.to_string_lossy().to_string();
+        let Ok((_, remainder)) = NaiveDateTime::parse_and_remainder(&stem, NORMALIZED_DATE_FORMAT)
+        else {
+            return Some(stem);
+        };
+        let separator = self.file_name_part(" - ");
+        let remainder = match self.file_name_description() {
+            Some(description) => remainder
+                .strip_prefix(&format!("{}{}", separator, description))
+                .unwrap_or(remainder),
+            None => remainder,
+        };
+        remainder
+            .strip_prefix(&separator)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+    }
+
+    // Description as written in file names (see FileNameOptions)
+    fn file_name_description(&self) -> Option<String> {
+        let description = self.description.as_ref()?;
+        let description = match self.file_name_options.max_description_length {
+            Some(length) => description.chars().take(length).collect::<String>(),
+            None => description.clone(),
+        };
+        Some(self.file_name_part(description.trim_end()))
+    }
+
+    // Text transliterated to ASCII and with spaces replaced, according to FileNameOptions
+    fn file_name_part(&self, text: &str) -> String {
+        let text = match self.file_name_options.ascii_only {
+            true => deunicode::deunicode(text),
+            false => text.to_string(),
+        };
+        match self.file_name_options.replace_spaces {
+            Some(replacement) => text.replace(' ', &replacement.to_string()),
+            None => text,
+        }
+    }
+
+    // Read dimensions, bit depth (per channel) and ICC profile presence from image header
+    fn read_header(data: &[u8], path: &Path) -> Result<ImageHeader, Error> {
+        let decoder = Self::image_reader(data, path).and_then(|reader| reader.into_decoder().ok());
+        let Some(mut decoder) = decoder else {
+            return Err(Error::other("Cannot read image dimentions."));
+        };
+        let color_type = decoder.color_type();
+        Ok(ImageHeader {
+            dimentions: decoder.dimensions(),
+            bit_depth: Some(color_type.bits_per_pixel() / color_type.channel_count() as u16),
+            icc_profile: matches!(decoder.icc_profile(), Ok(Some(_))),
+        })
+    }
+
+    // Image reader on file data. Format is guessed from data, then from file extention.
+    fn image_reader<'a>(data: &'a [u8], path: &Path) -> Option<ImageReader<Cursor<&'a [u8]>>> {
+        let mut reader = ImageReader::new(Cursor::new(data))
+            .with_guessed_format()
+            .ok()?;
+        if reader.format().is_none() {
+            reader.set_format(ImageFormat::from_path(path).ok()?);
+        }
+        Some(reader)
+    }
+
+    // little_exif file type of writable files
+    fn file_extension(mime: &str) -> Option<FileExtension> {
+        match mime {
+            "image/jpeg" | "image/jpg" => Some(FileExtension::JPEG),
+            "image/png" => Some(FileExtension::PNG {
+                as_zTXt_chunk: false,
+            }),
+            "image/tiff" => Some(FileExtension::TIFF),
+            "image/webp" => Some(FileExtension::WEBP),
+            _ => None,
+        }
+    }
+
+    // Guess image mime type from file extention
+    fn mime_from_extention(path: &Path) -> Option<&'static str> {
+        let extention = path.extension()?.to_str()?.to_lowercase();
+        match extention.as_str() {
+            "jpg" | "jpeg" => Some("image/jpeg"),
+            "png" => Some("image/png"),
+            "tif" | "tiff" => Some("image/tiff"),
+            "heic" => Some("image/heic"),
+            "webp" => Some("image/webp"),
+            _ => None,
+        }
+    }
+
+    // Parse dates and time offset again, after a change of date tags or year pivot
+    fn reload_dates(&mut self) {
+        let date;
+        (date, self.original_date, self.create_date, self.date_error) =
+            Self::read_dates(&self.litte_metadata, self.date_source, self.year_pivot);
+        self.date = date.or_else(|| self.xmp.as_ref()?.create_date());
+        self.offset = Self::get_tag_string(
+            &self.litte_metadata,
+            &ExifTag::OffsetTimeOriginal(String::new()),
+        )
+        .and_then(|str_offset| FixedOffset::from_exif_string(str_offset).ok());
+    }
+
+    // Image date (according to date_source), DateTimeOriginal, CreateDate
+    // and the error of the first date that cannot be parsed.
+    // Unset dates (like 0000:00:00 00:00:00) are not errors.
+    fn read_dates(
+        litte_metadata: &LittleMetadata,
+        date_source: DateSource,
+        year_pivot: i32,
+    ) -> (
+        Option<NaiveDateTime>,
+        Option<NaiveDateTime>,
+        Option<NaiveDateTime>,
+        Option<String>,
+    ) {
+        let mut error = None;
+        let mut get_date = |tag| {
+            let str_date = Self::get_tag_string(litte_metadata, &tag)?;
+            if str_date
+                .chars()
+                .all(|c| matches!(c, '0' | ' ' | ':' | '\0'))
+            {
+                return None;
+            }
+            match parse_exif_date(&str_date, year_pivot) {
+                Ok(date) => Some(date),
+                Err(e) => {
+                    error.get_or_insert(e.to_string());
+                    None
+                }
+            }
+        };
+        let original_date = get_date(ExifTag::DateTimeOriginal(String::new()));
+        let create_date = get_date(ExifTag::CreateDate(String::new()));
+        let date = match date_source {
+            DateSource::Original => original_date.or(create_date),
+            DateSource::Create => create_date.or(original_date),
+            DateSource::Modified => get_date(ExifTag::ModifyDate(String::new()))
+                .or(original_date)
+                .or(create_date),
+        };
+        (date, original_date, create_date, error)
+    }
+
+    // Read a string tag, without trailing NUL bytes and spaces
+    fn get_tag_string(litte_metadata: &LittleMetadata, tag: &ExifTag) -> Option<String> {
+        let value = Self::get_tag_raw_string(litte_metadata, tag)?;
+        Some(Self::trim_string(&value).to_string())
+    }
+
+    // Read a string tag as stored
+    fn get_tag_raw_string(litte_metadata: &LittleMetadata, tag: &ExifTag) -> Option<String> {
+        let tag = litte_metadata.get_tag(tag).next()?;
+        let endian = litte_metadata.get_endian();
+        Some(String::from_u8_vec(&tag.value_as_u8_vec(&endian), &endian))
+    }
+
+    fn trim_string(value: &str) -> &str {
+        value.trim_end_matches(|c: char| c == '\0' || c.is_whitespace())
+    }
+
+    // Read a Windows XP tag (UCS-2 little endian, null terminated)
+    fn get_tag_ucs2(litte_metadata: &LittleMetadata, tag: &ExifTag) -> Option<String> {
+        let tag = litte_metadata.get_tag(tag).next()?;
+        let endian = litte_metadata.get_endian();
+        Some(Self::from_ucs2(&tag.value_as_u8_vec(&endian)))
+    }
+
+    // Decode a Windows XP tag value.
+    // XP tags are always little endian, whatever the EXIF endianness.
+    fn from_ucs2(data: &[u8]) -> String {
+        let data: Vec<u16> = data
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .take_while(|c| *c != 0)
+            .collect();
+        String::from_utf16_lossy(&data)
+    }
+
+    // Encode a Windows XP tag value
+    fn to_ucs2(value: &str) -> Vec<u8> {
+        value
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    }
+
+    // Read an u16 tag
+    fn get_tag_u16(litte_metadata: &LittleMetadata, tag: &ExifTag) -> Option<u16> {
+        let tag = litte_metadata.get_tag(tag).next()?;
+        let endian = litte_metadata.get_endian();
+        Some(u16::from_u8_vec(&tag.value_as_u8_vec(&endian), &endian))
+    }
+
+    // Read the MakerNote tag, kept as is
+    fn get_maker_note(litte_metadata: &LittleMetadata) -> Option<Vec<u8>> {
+        match litte_metadata
+            .get_tag(&ExifTag::MakerNote(Vec::new()))
+            .next()
+        {
+            Some(ExifTag::MakerNote(data)) => Some(data.clone()),
+            _ => None,
+        }
+    }
+
+    // Read an u8 tag
+    fn get_tag_u8(litte_metadata: &LittleMetadata, tag: &ExifTag) -> Option<u8> {
+        let tag = litte_metadata.get_tag(tag).next()?;
+        let endian = litte_metadata.get_endian();
+        tag.value_as_u8_vec(&endian).first().copied()
+    }
+
+    // Read an u32 tag
+    fn get_tag_u32(litte_metadata: &LittleMetadata, tag: &ExifTag) -> Option<u32> {
+        let tag = litte_metadata.get_tag(tag).next()?;
+        let endian = litte_metadata.get_endian();
+        Some(u32::from_u8_vec(&tag.value_as_u8_vec(&endian), &endian))
+    }
+
+    // Read an uR64 tag
+    fn get_tag_ur64(litte_metadata: &LittleMetadata, tag: &ExifTag) -> Option<uR64> {
+        let endian = litte_metadata.get_endian();
+        litte_metadata
+            .get_tag(tag)
+            .next()
+            .map(|tag| uR64::from_u8_vec(&tag.value_as_u8_vec(&endian), &endian))
+    }
+
+    // Read all values of an uR64 tag
+    fn get_tag_ur64_vec(litte_metadata: &LittleMetadata, tag: &ExifTag) -> Option<Vec<uR64>> {
+        let tag = litte_metadata.get_tag(tag).next()?;
+        let endian = litte_metadata.get_endian();
+        Some(
+            tag.value_as_u8_vec(&endian)
+                .chunks_exact(8)
+                .map(|data| uR64::from_u8_vec(&data.to_vec(), &endian))
+                .collect(),
+        )
+    }
+
+    //  Read an iR64 tag
+    fn get_tag_ir64(litte_metadata: &LittleMetadata, tag: &ExifTag) -> Option<iR64> {
+        let endian = litte_metadata.get_endian();
+        litte_metadata
+            .get_tag(tag)
+            .next()
+            .map(|tag| iR64::from_u8_vec(&tag.value_as_u8_vec(&endian), &endian))
+    }
+
+    pub fn tags_to_string(&self, tags: &EnumSet<Tag>) -> String {
+        if tags.is_empty() {
+            "None".to_string()
+        } else {
+            tags.iter()
+                .map(|t| t.to_string_comment(self))
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    }
+
+    /// Value of any EXIF tag (see named_tag::lookup())
+    pub fn tag_value(&self, tag: &ExifTag) -> Option<TagValue> {
+        let endian = self.litte_metadata.get_endian();
+        let tag = self.litte_metadata.get_tag(tag).next()?;
+        Some(TagValue::from_exif_tag(tag, &endian))
+    }
+
+    /// Set any EXIF tag (see named_tag::parse()).
+    /// Values loaded from this tag (like description()) are not updated.
+    /// The Orientation tag is written as is: the image is not rotated.
+    /// Note: file will not be modified unless you call save().
+    pub fn set_exif_tag(&mut self, tag: ExifTag) {
+        let category = match Self::tag_category(&tag) {
+            Tag::Orientation => Tag::Others,
+            category => category,
+        };
+        self.modified_tags.insert(category);
+        self.litte_metadata.set_tag(tag);
+    }
+
+    /// Set a tag by name (see named_tag::parse()), checking its value.
+    /// Tags loaded by this tool (description, rating, dates...) are set through their
+    /// setter or reloaded, other tags are written as is (see set_exif_tag()).
+    /// Note: file will not be modified unless you call save().
+    /// Will return an error if the value is invalid.
+    pub fn set_named_tag(&mut self, tag: ExifTag) -> Result<(), Error> {
+        match &tag {
+            ExifTag::ImageDescription(description) => self.set_description(description),
+            ExifTag::ImageUniqueID(unique_id) => self.set_unique_id(unique_id),
+            ExifTag::Rating(rating) => match rating[..] {
+                [rating] => self.set_rating(rating)?,
+                _ => return Err(Error::other("Rating expects a single value.")),
+            },
+            ExifTag::RatingPercent(_) => {
+                return Err(Error::other(
+                    "Use Rating to set the rating (RatingPercent is written with it).",
+                ))
+            }
+            ExifTag::Orientation(orientation) => match orientation[..] {
+                [1..=8] => self.set_exif_tag(tag),
+                _ => {
+                    return Err(Error::other(
+                        "Invalid orientation (expected a single value, 1 to 8).",
+                    ))
+                }
+            },
+            ExifTag::DateTimeOriginal(date)
+            | ExifTag::CreateDate(date)
+            | ExifTag::ModifyDate(date) => {
+                parse_exif_date(date, self.year_pivot)?;
+                self.set_exif_tag(tag);
+                self.reload_dates();
+            }
+            ExifTag::OffsetTime(offset)
+            | ExifTag::OffsetTimeOriginal(offset)
+            | ExifTag::OffsetTimeDigitized(offset) => {
+                FixedOffset::from_exif_string(offset.clone())?;
+                self.set_exif_tag(tag);
+                self.reload_dates();
+            }
+            _ => {
+                let category = Self::tag_category(&tag);
+                self.set_exif_tag(tag);
+                if category == Tag::Gps {
+                    self.gps_info = GpsInfo::from_metadata(&self.litte_metadata);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove any EXIF tag.
+    /// Values loaded from this tag (like description()) are not updated.
+    /// Note: file will not be modified unless you call save().
+    pub fn remove_exif_tag(&mut self, tag: ExifTag) {
+        if self.litte_metadata.get_tag(&tag).next().is_some() {
+            self.modified_tags.insert(Self::tag_category(&tag));
+            self.litte_metadata.remove_tag(tag);
+        }
+    }
+
+    /// Iterate over all EXIF tags (name, typed value), including the ones not handled by this tool
+    pub fn tags(&self) -> impl Iterator<Item = (String, TagValue)> + '_ {
+        let endian = self.litte_metadata.get_endian();
+        self.litte_metadata
+            .into_iter()
+            .map(move |tag| (diff::tag_name(tag), TagValue::from_exif_tag(tag, &endian)))
+    }
+
+    /// List all EXIF tags (name, value), including the ones not handled by this tool.
+    /// Binary values are summarized (length and first bytes).
+    pub fn all_tags(&self) -> Vec<(String, String)> {
+        let endian = self.litte_metadata.get_endian();
+        self.litte_metadata
+            .into_iter()
+            .map(|tag| {
+                let value = diff::tag_value(tag);
+                let value = if Self::is_printable(&value) {
+                    value
+                } else {
+                    Self::bytes_summary(&tag.value_as_u8_vec(&endian))
+                };
+                (diff::tag_name(tag), value)
+            })
+            .collect()
+    }
+
+    // Check if a tag value (as displayed by little_exif) is human readable:
+    // no escaped control chars and no long list of bytes
+    fn is_printable(value: &str) -> bool {
+        let escaped = ["\\0", "\\u{", "\\x"]
+            .iter()
+            .any(|escape| value.contains(escape));
+        let long_list = value.starts_with('[') && value.matches(',').count() >= 16;
+        !escaped && !long_list
+    }
+
+    // Display length and first bytes of a binary value
+    fn bytes_summary(data: &[u8]) -> String {
+        let hex = data
+            .iter()
+            .take(16)
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let more = if data.len() > 16 { " ..." } else { "" };
+        format!("<{} bytes> {}{}", data.len(), hex, more)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, OpenOptions};
+
+    use enumset::enum_set;
+
+    use super::*;
+
+    #[test]
+    fn invalid_files() {
+        let result = Metadata::new(Path::new("/do/not/exists"));
+        assert!(result.is_err());
+        let result = Metadata::new(Path::new("tests/empty"));
+        assert!(result.is_err());
+        let result = Metadata::new(Path::new("tests/archive.tar.gz"));
+        assert!(result.is_err());
+        let result = Metadata::new(Path::new("tests/no_exif.png"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn unknown_file_type_with_image_extention() {
+        assert_eq!(
+            Metadata::mime_from_extention(Path::new("a/photo.JPG")),
+            Some("image/jpeg")
+        );
+        assert_eq!(
+            Metadata::mime_from_extention(Path::new("photo.tiff")),
+            Some("image/tiff")
+        );
+        assert_eq!(Metadata::mime_from_extention(Path::new("photo.txt")), None);
+        assert_eq!(Metadata::mime_from_extention(Path::new("photo")), None);
+
+        // infer does not know this file: the extention let the image reader do the check
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/empty"), &tmp_file_path,).is_ok());
+        let result = Metadata::new(&tmp_file_path);
+        assert_eq!(
+            result.err().map(|e| e.to_string()),
+            Some("Cannot read image dimentions.".to_string())
+        );
+
+        // HEIC with 'heix' brand (10 bits images) is not known by infer
+        let mut data = fs::read("tests/heic_with_exif.heic").unwrap();
+        data[8..12].copy_from_slice(b"heix");
+        data[16..24].copy_from_slice(b"mif1heix");
+        assert!(infer::get(&data).is_none());
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.heic");
+        fs::write(&tmp_file_path, &data).unwrap();
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.dimensions().ok(), Some((2048, 1536)));
+        metadata.fix_file_name();
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::FileName)));
+
+        let renamed = tmpdir
+            .path()
+            .join("2006_10_29-16_27_21 - A fun picture!.heic");
+        assert_eq!(metadata.path(), renamed);
+        let metadata = Metadata::new(&renamed).unwrap();
+        assert_eq!(metadata.description(), Some("A fun picture!".to_string()));
+        assert_eq!(
+            metadata.exif_date(),
+            Some("2006:10:29 16:27:21".to_string())
+        );
+    }
+
+    #[test]
+    fn raw_file() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.nef");
+        assert!(fs::copy(Path::new("tests/raw.nef"), &tmp_file_path,).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(metadata.is_raw());
+        assert!(!metadata.is_writable());
+        assert_eq!(metadata.dimensions().ok(), Some((4256, 2832)));
+        assert_eq!(
+            metadata.exif_date(),
+            Some("2006:10:29 16:27:21".to_string())
+        );
+        assert_eq!(metadata.camera_info().iso, Some(800));
+        assert!(metadata
+            .camera_info()
+            .camera
+            .as_ref()
+            .is_some_and(|camera| camera.contains("NIKON D700")));
+
+        // Read-only
+        metadata.set_description("Description 1");
+        assert_eq!(
+            metadata.save().err().map(|e| e.to_string()),
+            Some("RAW files are read-only.".to_string())
+        );
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.description(), None);
+    }
+
+    #[test]
+    fn heic_file() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.heic");
+        assert!(fs::copy(Path::new("tests/heic_with_exif.heic"), &tmp_file_path,).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(!metadata.is_writable());
+        assert_eq!(metadata.dimensions().ok(), Some((2048, 1536)));
+        assert_eq!(metadata.description(), Some("A fun picture!".to_string()));
+        assert_eq!(
+            metadata.exif_date(),
+            Some("2006:10:29 16:27:21".to_string())
+        );
+
+        // Read-only, but can be renamed
+        metadata.set_description("Description 1");
+        assert_eq!(
+            metadata.save().err().map(|e| e.to_string()),
+            Some("Cannot write EXIF data to image/heif files.".to_string())
+        );
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.fix_file_name();
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::FileName)));
+    }
+
+    #[test]
+    fn file_all_tags() {
+        let result = Metadata::new(Path::new("tests/all_tags.jpg"));
+        assert!(result.is_ok());
+        let metadata = result.unwrap();
+        assert_eq!(metadata.path, Path::new("tests/all_tags.jpg"));
+        assert_eq!(metadata.dimensions().ok(), Some((2048, 1536)));
+        assert_eq!(
+            metadata.date(),
+            NaiveDate::from_ymd_opt(2006, 10, 29)
+                .unwrap()
+                .and_hms_opt(16, 27, 21)
+        );
+        assert_eq!(
+            metadata.exif_date(),
+            Some("2006:10:29 16:27:21".to_string())
+        );
+        assert_eq!(metadata.description(), Some("A fun picture!".to_string()));
+        assert_eq!(
+            metadata.camera_info().camera,
+            Some("Pablo Picasso (1.4)".to_string())
+        );
+        assert_eq!(metadata.camera_info().exposure, Some("1/32".to_string()));
+        assert_eq!(metadata.camera_info().exposure_bias, Some("0".to_string()));
+        assert_eq!(metadata.camera_info().aperture, Some("5.6".to_string()));
+        assert_eq!(metadata.camera_info().iso, Some(100));
+        assert_eq!(metadata.camera_info().focal, Some(7.9));
+        assert_eq!(
+            metadata.camera_info().flash,
+            Some(CameraInfo::flash_code_to_string(0x18))
+        );
+    }
+
+    #[test]
+    fn file_webp() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.webp");
+        assert!(fs::copy(Path::new("tests/webp_with_exif.webp"), &tmp_file_path,).is_ok());
+
+        let result = Metadata::new(&tmp_file_path);
+        assert!(result.is_ok());
+        let mut metadata = result.unwrap();
+        assert!(metadata.is_writable());
+        assert_eq!(metadata.dimensions().ok(), Some((1, 1)));
+        assert_eq!(metadata.description(), Some("A fun picture!".to_string()));
+        assert_eq!(
+            metadata.exif_date(),
+            Some("2006:10:29 16:27:21".to_string())
+        );
+
+        metadata.set_description("Description 1");
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Description)));
+
+        let result = Metadata::new(&tmp_file_path);
+        assert!(result.is_ok());
+        let metadata = result.unwrap();
+        assert_eq!(metadata.description(), Some("Description 1".to_string()));
+        assert_eq!(
+            metadata.exif_date(),
+            Some("2006:10:29 16:27:21".to_string())
+        );
+    }
+
+    #[test]
+    fn file_missing_tags() {
+        let result = Metadata::new(Path::new("tests/no_date.jpg"));
+        assert!(result.is_ok());
+        let metadata = result.unwrap();
+        assert_eq!(metadata.date(), None);
+
+        let result = Metadata::new(Path::new("tests/no_description.jpg"));
+        assert!(result.is_ok());
+        let metadata = result.unwrap();
+        assert_eq!(metadata.description(), None);
+
+        let result = Metadata::new(Path::new("tests/no_camera.jpg"));
+        assert!(result.is_ok());
+        let metadata = result.unwrap();
+        assert_eq!(metadata.camera_info().camera, None);
+
+        let result = Metadata::new(Path::new("tests/no_exposure.jpg"));
+        assert!(result.is_ok());
+        let metadata = result.unwrap();
+        assert_eq!(metadata.camera_info().exposure, None);
+
+        let result = Metadata::new(Path::new("tests/no_bias.jpg"));
+        assert!(result.is_ok());
+        let metadata = result.unwrap();
+        assert_eq!(metadata.camera_info().exposure_bias, None);
+
+        let result = Metadata::new(Path::new("tests/no_iso.jpg"));
+        assert!(result.is_ok());
+        let metadata = result.unwrap();
+        assert_eq!(metadata.camera_info().iso, None);
+
+        let result = Metadata::new(Path::new("tests/no_focal.jpg"));
+        assert!(result.is_ok());
+        let metadata = result.unwrap();
+        assert_eq!(metadata.camera_info().focal, None);
+
+        let result = Metadata::new(Path::new("tests/no_flash.jpg"));
+        assert!(result.is_ok());
+        let metadata = result.unwrap();
+        assert_eq!(metadata.camera_info().flash, None);
+    }
+
+    #[test]
+    fn bit_depth() {
+        let metadata = Metadata::new(Path::new("tests/all_tags.jpg")).unwrap();
+        assert_eq!(metadata.bit_depth(), Some(8));
+    }
+
+    #[test]
+    fn description_sources() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/no_description.jpg"), &tmp_file_path,).is_ok());
+
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.description_source(), None);
+
+        // Description only in XPTitle
+        let mut litte_metadata = LittleMetadata::new_from_path(&tmp_file_path).unwrap();
+        litte_metadata.set_tag(ExifTag::XPTitle(Metadata::to_ucs2("Capitole")));
+        assert!(litte_metadata.write_to_file(&tmp_file_path).is_ok());
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.description(), Some("Capitole".to_string()));
+        assert_eq!(
+            metadata.description_source(),
+            Some(DescriptionSource::XpTitle)
+        );
+
+        // IPTC caption has priority over XPTitle
+        assert!(metadata.set_caption("Toulouse").is_ok());
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Caption)));
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.description(), Some("Toulouse".to_string()));
+        assert_eq!(
+            metadata.description_source(),
+            Some(DescriptionSource::IptcCaption)
+        );
+
+        // Same value, written to ImageDescription, then mirrored
+        metadata.set_description("Toulouse");
+        assert!(metadata.mirror_description().is_ok());
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Description)));
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(
+            metadata.description_source(),
+            Some(DescriptionSource::ImageDescription)
+        );
+        assert_eq!(metadata.caption(), Some("Toulouse".to_string()));
+        assert_eq!(
+            Metadata::get_tag_ucs2(&metadata.litte_metadata, &ExifTag::XPTitle(Vec::new())),
+            Some("Toulouse".to_string())
+        );
+
+        // All fields are cleared: no other field becomes the description
+        metadata.clear_description();
+        assert_eq!(metadata.save().ok(), Some(Tag::Description | Tag::Caption));
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.description(), None);
+        assert_eq!(metadata.caption(), None);
+    }
+
+    #[test]
+    fn lazy_dimensions() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        // Image header is not read until needed
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(metadata.header.get().is_none());
+        metadata.set_description("Description 1");
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Description)));
+        assert!(metadata.header.get().is_none());
+        assert_eq!(metadata.dimensions().ok(), Some((2048, 1536)));
+        assert!(metadata.header.get().is_some());
+
+        // Never read when loading EXIF only
+        let metadata = Metadata::new_exif_only(&tmp_file_path, DateSource::default()).unwrap();
+        assert_eq!(metadata.description(), Some("Description 1".to_string()));
+        assert!(metadata.dimensions().is_err());
+        assert!(metadata.header.get().is_none());
+        // Files only known from their extention are still checked
+        let unknown_path = tmpdir.path().join("unknown.jpg");
+        assert!(fs::write(&unknown_path, b"Not an image").is_ok());
+        assert!(Metadata::new_exif_only(&unknown_path, DateSource::default()).is_err());
+
+        // Read errors are reported on use
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(fs::write(&tmp_file_path, b"").is_ok());
+        assert!(metadata.dimensions().is_err());
+        assert_eq!(metadata.bit_depth(), None);
+    }
+
+    #[test]
+    fn update_rating() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.rating(), None);
+        assert!(metadata.set_rating(6).is_err());
+        assert!(metadata.set_rating(4).is_ok());
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Rating)));
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.rating(), Some(4));
+        assert_eq!(
+            Metadata::get_tag_u16(
+                &metadata.litte_metadata,
+                &ExifTag::RatingPercent(Vec::new())
+            ),
+            Some(75)
+        );
+        assert!(metadata.set_rating(4).is_ok());
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+        metadata.clear_rating();
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Rating)));
+
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.rating(), None);
+
+        let percents: Vec<u16> = (0..=5).map(Metadata::rating_to_percent).collect();
+        assert_eq!(percents, vec![0, 1, 25, 50, 75, 99]);
+    }
+
+    #[test]
+    fn xmp_sidecar() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+        assert!(fs::copy(
+            "tests/sidecar.xmp",
+            tmpdir.path().join("photo_norm_test.xmp")
+        )
+        .is_ok());
+
+        // EXIF wins unless prefer_xmp()
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.description(), Some("A fun picture!".to_string()));
+        assert_eq!(metadata.rating(), Some(4));
+        metadata.prefer_xmp();
+        assert_eq!(
+            metadata.description(),
+            Some("A fun picture & a sidecar".to_string())
+        );
+
+        // Both EXIF and sidecar are updated, sidecar follows the renamed image
+        metadata.set_description("Description 1");
+        metadata.fix_file_name();
+        assert_eq!(metadata.save().ok(), Some(Tag::Description | Tag::FileName));
+        assert!(!tmpdir.path().join("photo_norm_test.xmp").exists());
+        let metadata = Metadata::new(metadata.path()).unwrap();
+        assert_eq!(metadata.description(), Some("Description 1".to_string()));
+        let xmp = metadata.xmp().unwrap();
+        assert_eq!(xmp.path(), Xmp::sidecar_path(metadata.path()));
+        assert_eq!(xmp.description(), Some("Description 1".to_string()));
+        assert_eq!(xmp.rating(), Some(4));
+    }
+
+    #[test]
+    fn description_max_length() {
+        let mut metadata = Metadata::new(Path::new("tests/all_tags.jpg")).unwrap();
+        assert!(metadata.set_description_limited("été", 3, false).is_ok());
+        assert_eq!(metadata.description(), Some("été".to_string()));
+
+        assert!(metadata.set_description_limited("étés", 3, false).is_err());
+        assert_eq!(metadata.description(), Some("été".to_string()));
+
+        assert!(metadata.set_description_limited("éééé", 3, true).is_ok());
+        assert_eq!(metadata.description(), Some("éé…".to_string()));
+    }
+
+    #[test]
+    fn set_gps() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(metadata.gps_info().is_empty());
+        metadata.set_gps(43.604, -1.444);
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Gps)));
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        let gps_info = metadata.gps_info();
+        assert!((gps_info.latitude.unwrap() - 43.604).abs() < 1e-6);
+        assert!((gps_info.longitude.unwrap() + 1.444).abs() < 1e-6);
+        assert_eq!(gps_info.altitude, None);
+
+        // GPS time from the image date (no time offset)
+        assert!(metadata.set_gps_time());
+        assert!(metadata.save().is_ok());
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.gps_info().timestamp, metadata.date());
+
+        metadata.clear_gps();
+        assert!(metadata.gps_info().is_empty());
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Gps)));
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(metadata.gps_info().is_empty());
+    }
+
+    #[test]
+    fn preserve_mtime() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+        let mtime = FileTime::from_unix_time(1162132041, 0);
+        assert!(filetime::set_file_mtime(&tmp_file_path, mtime).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.preserve_mtime();
+        metadata.set_description("Description 1");
+        metadata.fix_file_name();
+        assert!(metadata.save().is_ok());
+        assert_ne!(metadata.path(), tmp_file_path);
+
+        let file_metadata = fs::metadata(metadata.path()).unwrap();
+        let new_mtime = FileTime::from_last_modification_time(&file_metadata);
+        assert!((new_mtime.unix_seconds() - mtime.unix_seconds()).abs() <= 1);
+    }
+
+    #[test]
+    fn no_exif_error() {
+        let e = Metadata::new(Path::new("tests/no_exif.png")).err().unwrap();
+        assert!(Metadata::is_no_exif_error(&e));
+        let e = Metadata::new(Path::new("tests/empty")).err().unwrap();
+        assert!(!Metadata::is_no_exif_error(&e));
+    }
+
+    #[test]
+    fn unreadable_exif() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+
+        // Invalid TIFF header in the EXIF segment: only the image reader succeeds
+        let mut data = fs::read("tests/all_tags.jpg").unwrap();
+        let exif = data.windows(6).position(|w| w == b"Exif\0\0").unwrap();
+        data[exif + 6..exif + 8].copy_from_slice(b"XX");
+        assert!(fs::write(&tmp_file_path, &data).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(metadata.exif_error().is_some());
+        assert_eq!(metadata.dimensions().ok(), Some((2048, 1536)));
+        assert_eq!(metadata.date(), None);
+        assert_eq!(metadata.description(), None);
+        assert!(metadata.needs_dimensions_fix());
+
+        // EXIF data cannot be written
+        metadata.set_description("Capitole");
+        assert!(metadata.save().is_err());
+        assert_eq!(fs::read(&tmp_file_path).unwrap(), data);
+    }
+
+    #[test]
+    fn unreadable_dimensions() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+
+        // Truncated after the EXIF segment: only little_exif succeeds
+        let data = fs::read("tests/all_tags.jpg").unwrap();
+        let exif = data.windows(6).position(|w| w == b"Exif\0\0").unwrap();
+        let length = u16::from_be_bytes([data[exif - 2], data[exif - 1]]) as usize;
+        assert!(fs::write(&tmp_file_path, &data[..exif - 2 + length]).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.exif_error(), None);
+        assert_eq!(metadata.description(), Some("A fun picture!".to_string()));
+        assert!(metadata.date().is_some());
+        assert!(metadata.dimensions().is_err());
+        // Unknown dimensions are never written
+        assert!(!metadata.needs_dimensions_fix());
+        assert!(!metadata.fix_dimentions());
+        assert!(metadata.orientation_consistent());
+    }
+
+    #[test]
+    fn unique_id() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.unique_id(), None);
+        metadata.set_unique_id("0123456789abcdef0123456789abcdef");
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Others)));
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(
+            metadata.unique_id(),
+            Some("0123456789abcdef0123456789abcdef".to_string())
+        );
+        metadata.set_unique_id("0123456789abcdef0123456789abcdef");
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+    }
+
+    #[test]
+    fn date_plausible() {
+        let mut metadata = Metadata::new(Path::new("tests/all_tags.jpg")).unwrap();
+        assert!(metadata.date_plausible());
+        metadata.set_min_year(2010);
+        assert!(!metadata.date_plausible());
+
+        metadata.set_min_year(MIN_YEAR);
+        metadata.set_date(
+            NaiveDate::from_ymd_opt(1970, 1, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+        );
+        assert!(!metadata.date_plausible());
+        metadata.set_date(Local::now().naive_local() + Duration::days(30));
+        assert!(!metadata.date_plausible());
+        metadata.set_date(Local::now().naive_local());
+        assert!(metadata.date_plausible());
+        metadata.clear_date();
+        assert!(metadata.date_plausible());
+    }
+
+    #[test]
+    fn reload() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        let mut metadata =
+            Metadata::new_with_date_source(&tmp_file_path, DateSource::Create).unwrap();
+        let description = metadata.description();
+        metadata.set_on_conflict(Conflict::Skip);
+        metadata.set_description("Unsaved");
+        assert!(metadata.reload().is_ok());
+        assert_eq!(metadata.description(), description);
+        assert_eq!(metadata.on_conflict, Conflict::Skip);
+        assert_eq!(metadata.date_source, DateSource::Create);
+        assert_eq!(metadata.save().ok(), Some(EnumSet::empty()));
+
+        // External modification
+        assert!(fs::copy(Path::new("tests/no_description.jpg"), &tmp_file_path,).is_ok());
+        assert!(metadata.reload().is_ok());
+        assert_eq!(metadata.description(), None);
+
+        assert!(fs::remove_file(&tmp_file_path).is_ok());
+        assert!(metadata.reload().is_err());
+    }
+
+    #[test]
+    fn mtime_from_date() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(metadata.set_mtime_from_date());
+        metadata.fix_file_name();
+        assert!(metadata.save().unwrap().contains(Tag::Mtime));
+
+        let file_metadata = fs::metadata(metadata.path()).unwrap();
+        let mtime = FileTime::from_last_modification_time(&file_metadata);
+        assert_eq!(Some(mtime.unix_seconds()), metadata.date_timestamp());
+
+        metadata.clear_date();
+        assert!(!metadata.set_mtime_from_date());
+    }
+
+    #[test]
+    fn date_from_mtime() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/no_date.jpg"), &tmp_file_path,).is_ok());
+        let date = parse_date_flexible("2006-10-29 16:27:21").unwrap();
+        let timestamp = Local
+            .from_local_datetime(&date)
+            .earliest()
+            .unwrap()
+            .timestamp();
+        assert!(
+            filetime::set_file_mtime(&tmp_file_path, FileTime::from_unix_time(timestamp, 0))
+                .is_ok()
+        );
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.date(), None);
+        assert_eq!(metadata.date_from_mtime(), Some(date));
+        metadata.set_date(metadata.date_from_mtime().unwrap());
+        assert!(metadata.save().unwrap().contains(Tag::Date));
+
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.original_date, Some(date));
+        assert_eq!(metadata.create_date, Some(date));
+    }
+
+    #[test]
+    fn named_tags() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.set_exif_tag(named_tag::parse("Artist", "John Doe").unwrap());
+        metadata.set_exif_tag(named_tag::parse("ExposureTime", "1/250").unwrap());
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Others)));
+
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        let value = |name| metadata.tag_value(&named_tag::lookup(name).unwrap());
+        assert_eq!(
+            value("artist").map(|value| Metadata::trim_string(&value.to_string()).to_string()),
+            Some("John Doe".to_string())
+        );
+        assert_eq!(
+            value("ExposureTime").map(|value| value.to_string()),
+            Some("1/250".to_string())
+        );
+        assert_eq!(value("LensSerialNumber"), None);
+    }
+
+    #[test]
+    fn set_named_tags() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        let mut set = |name, value| metadata.set_named_tag(named_tag::parse(name, value).unwrap());
+        assert!(set("Rating", "7").is_err());
+        assert!(set("Orientation", "9").is_err());
+        assert!(set("DateTimeOriginal", "2006:13:29 16:27:21").is_err());
+        assert!(set("OffsetTimeOriginal", "+05:60").is_err());
+        assert!(set("Rating", "3").is_ok());
+        assert!(set("DateTimeOriginal", "2007:01:02 03:04:05").is_ok());
+        assert!(set("OffsetTimeOriginal", "+02:00").is_ok());
+        assert!(set("ImageDescription", "Capitole").is_ok());
+        // The tag is updated, the image is not rotated
+        assert!(set("Orientation", "1").is_ok());
+
+        // Loaded values are updated
+        assert_eq!(metadata.rating(), Some(3));
+        assert_eq!(
+            metadata.exif_date(),
+            Some("2007:01:02 03:04:05".to_string())
+        );
+        assert_eq!(metadata.offset(), FixedOffset::east_opt(2 * 3600));
+        assert_eq!(metadata.description(), Some("Capitole".to_string()));
+        assert_eq!(
+            metadata.save().ok(),
+            Some(Tag::Rating | Tag::Date | Tag::Description | Tag::Others)
+        );
+
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.orientation(), Some(1));
+        assert_eq!(metadata.dimensions().ok(), Some((2048, 1536)));
+        assert_eq!(metadata.rating(), Some(3));
+        assert_eq!(
+            metadata.exif_date(),
+            Some("2007:01:02 03:04:05".to_string())
+        );
+    }
+
+    #[test]
+    fn software_stamp() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+        let camera = Metadata::new(&tmp_file_path)
+            .unwrap()
+            .camera_info()
+            .camera
+            .clone();
+        let software = ExifTag::Software(String::new());
+
+        // Not written without modification
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.stamp_software(SOFTWARE_STAMP);
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_ne!(
+            Metadata::get_tag_string(&metadata.litte_metadata, &software).as_deref(),
+            Some(SOFTWARE_STAMP)
+        );
+
+        metadata.stamp_software(SOFTWARE_STAMP);
+        metadata.set_description("Stamped");
+        assert!(metadata.save().is_ok());
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(
+            Metadata::get_tag_string(&metadata.litte_metadata, &software).as_deref(),
+            Some(SOFTWARE_STAMP)
+        );
+        // The stamp is not displayed as camera software
+        let expected = camera.map(|camera| match camera.split_once(" (") {
+            Some((camera, _)) => camera.to_string(),
+            None => camera,
+        });
+        assert_eq!(metadata.camera_info().camera, expected);
+
+        // Neither is a custom stamp, once configured
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.stamp_software("Darkroom 2");
+        metadata.set_description("Stamped again");
+        assert!(metadata.save().is_ok());
+        assert!(metadata.reload().is_ok());
+        assert_eq!(metadata.camera_info().camera, expected);
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_ne!(metadata.camera_info().camera, expected);
+        metadata.hide_software("Darkroom 2");
+        assert_eq!(metadata.camera_info().camera, expected);
+    }
+
+    #[test]
+    fn also_modify_date() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+        let date_tags = [
+            ExifTag::DateTimeOriginal(String::new()),
+            ExifTag::CreateDate(String::new()),
+            ExifTag::ModifyDate(String::new()),
+        ];
+        let read_dates = |path: &Path| {
+            let metadata = Metadata::new(path).unwrap();
+            date_tags
+                .iter()
+                .map(|tag| {
+                    Metadata::get_tag_string(&metadata.litte_metadata, tag)
+                        .map(|date| date.trim_end_matches('\0').to_string())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Default: ModifyDate is left untouched
+        let modify_date = read_dates(&tmp_file_path)[2].clone();
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(metadata.set_date_flexible("2001-01-01 01:01:01").is_ok());
+        assert!(metadata.save().is_ok());
+        let dates = read_dates(&tmp_file_path);
+        assert_eq!(dates[0], Some("2001:01:01 01:01:01".to_string()));
+        assert_eq!(dates[1], dates[0]);
+        assert_eq!(dates[2], modify_date);
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.also_modify_date();
+        assert!(metadata.set_date_flexible("2002-02-02 02:02:02").is_ok());
+        assert!(metadata.save().is_ok());
+        let dates = read_dates(&tmp_file_path);
+        assert_eq!(dates[0], Some("2002:02:02 02:02:02".to_string()));
+        assert_eq!(dates[1], dates[0]);
+        assert_eq!(dates[2], dates[0]);
+    }
+
+    #[test]
+    fn trim_strings() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/padded_strings.jpg"), &tmp_file_path,).is_ok());
+
+        // Values are trimmed when read
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.description(), Some("A fun pic".to_string()));
+        assert!(metadata
+            .camera_info()
+            .camera
+            .as_ref()
+            .is_some_and(|camera| camera.contains("Picas") && !camera.contains('\0')));
+        metadata.set_description("A fun pic");
+        assert_eq!(metadata.save().ok(), Some(EnumSet::empty()));
+
+        assert!(metadata.trim_strings());
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Strings)));
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.description(), Some("A fun pic".to_string()));
+        assert!(!metadata.trim_strings());
+
+        let mut metadata = Metadata::new(Path::new("tests/all_tags.jpg")).unwrap();
+        assert!(!metadata.trim_strings());
+    }
+
+    #[test]
+    fn update_tags() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        // Description tag check
+        let result = Metadata::new(&tmp_file_path);
+        assert!(result.is_ok());
+        let mut metadata = result.unwrap();
+
+        metadata.set_description("Description 1");
+        assert_eq!(metadata.description(), Some("Description 1".to_string()));
+
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Description)));
+
+        let result = Metadata::new(&tmp_file_path);
+        assert!(result.is_ok());
+        let mut metadata = result.unwrap();
+        assert_eq!(metadata.description(), Some("Description 1".to_string()));
+        assert_eq!(
+            metadata.date(),
+            NaiveDate::from_ymd_opt(2006, 10, 29)
+                .unwrap()
+                .and_hms_opt(16, 27, 21)
+        );
+
+        // invalid Date tag check
+        assert!(metadata
+            .set_date_from_exif("2001:01:01".to_string())
+            .is_err());
+        assert_eq!(
+            metadata.date(),
+            NaiveDate::from_ymd_opt(2006, 10, 29)
+                .unwrap()
+                .and_hms_opt(16, 27, 21)
+        );
+
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+
+        let result = Metadata::new(&tmp_file_path);
+        assert!(result.is_ok());
+        let mut metadata = result.unwrap();
+        assert_eq!(metadata.description(), Some("Description 1".to_string()));
+        assert_eq!(
+            metadata.date(),
+            NaiveDate::from_ymd_opt(2006, 10, 29)
+                .unwrap()
+                .and_hms_opt(16, 27, 21)
+        );
+
+        // Date tag check
+        assert!(metadata
+            .set_date_from_exif("2001:01:01 01:01:01".to_string())
+            .is_ok());
+        assert_eq!(
+            metadata.date(),
+            NaiveDate::from_ymd_opt(2001, 1, 1)
+                .unwrap()
+                .and_hms_opt(1, 1, 1)
+        );
+
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Date)));
+
+        let result = Metadata::new(&tmp_file_path);
+        assert!(result.is_ok());
+        let mut metadata = result.unwrap();
+        assert_eq!(metadata.description(), Some("Description 1".to_string()));
+        assert_eq!(
+            metadata.date(),
+            NaiveDate::from_ymd_opt(2001, 1, 1)
+                .unwrap()
+                .and_hms_opt(1, 1, 1)
+        );
+
+        // All tags check
+        assert!(metadata
+            .set_date_from_exif("2002:02:02 02:02:02".to_string())
+            .is_ok());
+        metadata.set_description("Description 2");
+        assert_eq!(metadata.description(), Some("Description 2".to_string()));
+        assert_eq!(
+            metadata.date(),
+            NaiveDate::from_ymd_opt(2002, 2, 2)
+                .unwrap()
+                .and_hms_opt(2, 2, 2)
+        );
+
+        assert_eq!(
+            metadata.save().ok(),
+            Some(enum_set!(Tag::Date | Tag::Description))
+        );
+
+        let result = Metadata::new(&tmp_file_path);
+        assert!(result.is_ok());
+        let metadata = result.unwrap();
+        assert_eq!(metadata.description(), Some("Description 2".to_string()));
+        assert_eq!(
+            metadata.date(),
+            NaiveDate::from_ymd_opt(2002, 2, 2)
+                .unwrap()
+                .and_hms_opt(2, 2, 2)
+        );
+    }
+
+    #[test]
+    fn update_tags_png() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.png");
+        assert!(fs::copy(Path::new("tests/png_with_exif.png"), &tmp_file_path,).is_ok());
+
+        // EXIF is read from the eXIf chunk
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.description(), Some("PNG picture".to_string()));
+        assert_eq!(
+            metadata.date(),
+            NaiveDate::from_ymd_opt(2021, 7, 14)
+                .unwrap()
+                .and_hms_opt(10, 30, 0)
+        );
+        assert_eq!(metadata.dimensions().ok(), Some((8, 4)));
+
+        metadata.set_description("Description 1");
+        assert!(metadata.fix_dimentions());
+        assert_eq!(
+            metadata.save().ok(),
+            Some(Tag::Description | Tag::Dimensions)
+        );
+
+        // Written back as a single eXIf chunk
+        let data = fs::read(&tmp_file_path).unwrap();
+        assert_eq!(
+            data.windows(4).filter(|window| *window == b"eXIf").count(),
+            1
+        );
+        assert!(image::load_from_memory(&data).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.description(), Some("Description 1".to_string()));
+        assert_eq!(
+            metadata.date(),
+            NaiveDate::from_ymd_opt(2021, 7, 14)
+                .unwrap()
+                .and_hms_opt(10, 30, 0)
+        );
+        assert!(!metadata.fix_dimentions());
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+    }
+
+    #[test]
+    fn ucs2() {
+        let encoded = Metadata::to_ucs2("été;2006");
+        assert_eq!(encoded.len(), 8 * 2 + 2);
+        assert_eq!(&encoded[0..2], &[0xe9, 0x00]);
+        assert_eq!(&encoded[encoded.len() - 2..], &[0x00, 0x00]);
+        assert_eq!(Metadata::from_ucs2(&encoded), "été;2006");
+        // Unterminated, odd length
+        assert_eq!(Metadata::from_ucs2(&[b'a', 0, b'b', 0, b'c']), "ab");
+    }
+
+    #[test]
+    fn update_keywords() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        let keywords = Metadata::split_keywords("plage, 2006,,famille ", ',');
+        assert_eq!(keywords, vec!["plage", "2006", "famille"]);
+
+        let result = Metadata::new(&tmp_file_path);
+        assert!(result.is_ok());
+        let mut metadata = result.unwrap();
+        metadata.set_keywords(&keywords);
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Keywords)));
+
+        let result = Metadata::new(&tmp_file_path);
+        assert!(result.is_ok());
+        let mut metadata = result.unwrap();
+        assert_eq!(metadata.keywords(), keywords);
+
+        // No change on same keywords
+        metadata.set_keywords(&keywords);
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+    }
+
+    #[test]
+    fn looks_like_keywords() {
+        assert!(Metadata::looks_like_keywords("beach, 2006, family"));
+        assert!(Metadata::looks_like_keywords(
+            "plage,Toulouse,Saint Sernin,été "
+        ));
+        assert!(!Metadata::looks_like_keywords("A fun picture!"));
+        assert!(!Metadata::looks_like_keywords("Paris, France"));
+        assert!(!Metadata::looks_like_keywords("beach, 2006,, family"));
+        assert!(!Metadata::looks_like_keywords(
+            "Me, my wife and our dog, at the beach"
+        ));
+        assert!(!Metadata::looks_like_keywords("Sun, sea, sand. What else?"));
+        assert!(!Metadata::looks_like_keywords(""));
+    }
+
+    #[test]
+    fn migrate_keywords() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        // Not a keyword list
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(!metadata.migrate_description_to_keywords());
+
+        metadata.set_keywords(&["plage".to_string()]);
+        metadata.set_description("plage, 2006, famille");
+        assert!(metadata.migrate_description_to_keywords());
+        assert_eq!(metadata.description(), None);
+        assert_eq!(metadata.keywords(), vec!["plage", "2006", "famille"]);
+        assert!(metadata.save().is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.description(), None);
+        assert_eq!(metadata.keywords(), vec!["plage", "2006", "famille"]);
+
+        // Back to description, existing description kept unless overwrite
+        metadata.set_description("A fun picture!");
+        assert!(!metadata.migrate_keywords_to_description(false));
+        assert!(metadata.migrate_keywords_to_description(true));
+        assert!(metadata.save().is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(
+            metadata.description(),
+            Some("plage, 2006, famille".to_string())
+        );
+        assert!(metadata.keywords().is_empty());
+
+        // Only ImageDescription is moved: the IPTC caption becomes the description
+        assert!(metadata.set_caption("Toulouse").is_ok());
+        assert!(metadata.migrate_description_to_keywords());
+        assert_eq!(metadata.description(), Some("Toulouse".to_string()));
+        assert_eq!(
+            metadata.description_source(),
+            Some(DescriptionSource::IptcCaption)
+        );
+        assert!(metadata.save().is_ok());
+
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.caption(), Some("Toulouse".to_string()));
+        assert_eq!(metadata.description(), Some("Toulouse".to_string()));
+        assert_eq!(metadata.keywords(), vec!["plage", "2006", "famille"]);
+    }
+
+    #[test]
+    fn clear_tags() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.clear_description();
+        assert_eq!(metadata.description(), None);
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Description)));
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.description(), None);
+        assert!(metadata.date().is_some());
+        metadata.clear_description();
+        metadata.clear_date();
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Date)));
+
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.date(), None);
+        assert_eq!(metadata.description(), None);
+    }
+
+    #[test]
+    fn strip() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.set_gps(43.604, 1.444);
+        // Set both DateTimeOriginal and CreateDate
+        metadata.set_date(parse_date_flexible("2007-01-02").unwrap());
+        assert!(metadata.save().is_ok());
+        let make = ExifTag::Make(String::new());
+
+        // Remove only some tags
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(Metadata::get_tag_string(&metadata.litte_metadata, &make).is_some());
+        metadata.strip(&(EnumSet::all() - Tag::Gps - Tag::MakerNote));
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Gps)));
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(metadata.gps_info().is_empty());
+        assert!(metadata.description().is_some());
+        assert!(Metadata::get_tag_string(&metadata.litte_metadata, &make).is_some());
+
+        // Keep only some tags: both dates are kept
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.strip(&(Tag::Date | Tag::Description | Tag::Orientation));
+        assert!(metadata.save().is_ok_and(|tags| tags.contains(Tag::Others)));
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.description(), Some("A fun picture!".to_string()));
+        assert!(metadata.original_date.is_some());
+        assert!(metadata.create_date.is_some());
+        assert!(metadata.needs_orientation_fix());
+        assert_eq!(
+            Metadata::get_tag_string(&metadata.litte_metadata, &make),
+            None
+        );
+        assert!(metadata.keywords().is_empty());
+    }
+
+    #[test]
+    fn lenient_exif_date() {
+        let expected = NaiveDate::from_ymd_opt(2006, 10, 29)
+            .unwrap()
+            .and_hms_opt(16, 27, 0)
+            .unwrap();
+        let parse = |input: &str| NaiveDateTime::from_exif_string(input.to_string()).ok();
+        assert_eq!(parse("2006:10:29 16:27:00"), Some(expected));
+        // Missing seconds
+        assert_eq!(parse("2006:10:29 16:27"), Some(expected));
+        // Trailing space or NUL
+        assert_eq!(parse("2006:10:29 16:27:00 "), Some(expected));
+        assert_eq!(parse("2006:10:29 16:27:00\0"), Some(expected));
+        // Other separators
+        assert_eq!(parse("2006-10-29 16:27:00"), Some(expected));
+        assert_eq!(parse("2006/10/29 16:27:00"), Some(expected));
+        assert_eq!(parse("2006/10/29 16:27"), Some(expected));
+        assert_eq!(parse("2006-10-29T16:27:00"), Some(expected));
+        // Still canonical
+        assert_eq!(expected.to_exif_string(), "2006:10:29 16:27:00");
+
+        assert_eq!(parse("29/10/2006 16:27"), None);
+        assert_eq!(parse("    :  :     :  :  "), None);
     }
 
     #[test]
-    fn file_all_tags() {
-        let result = Metadata::new(Path::new("tests/all_tags.jpg"));
-        assert!(result.is_ok());
-        let metadata = result.unwrap();
-        assert_eq!(metadata.path, Path::new("tests/all_tags.jpg"));
-        assert_eq!(metadata.width(), 2048);
-        assert_eq!(metadata.height(), 1536);
-        assert_eq!(
-            metadata.date(),
-            NaiveDate::from_ymd_opt(2006, 10, 29)
+    fn two_digit_year() {
+        let date = |year| {
+            NaiveDate::from_ymd_opt(year, 10, 29)
                 .unwrap()
                 .and_hms_opt(16, 27, 21)
+                .unwrap()
+        };
+        assert_eq!(
+            parse_exif_date("06:10:29 16:27:21", YEAR_PIVOT).ok(),
+            Some(date(2006))
         );
+        // Pivot boundary
         assert_eq!(
-            metadata.exif_date(),
-            Some("2006:10:29 16:27:21".to_string())
+            parse_exif_date("69:10:29 16:27:21", YEAR_PIVOT).ok(),
+            Some(date(2069))
         );
-        assert_eq!(metadata.description(), Some("A fun picture!".to_string()));
         assert_eq!(
-            metadata.camera_info().camera,
-            Some("Pablo Picasso (1.4)".to_string())
+            parse_exif_date("70:10:29 16:27:21", YEAR_PIVOT).ok(),
+            Some(date(1970))
         );
-        assert_eq!(metadata.camera_info().exposure, Some("1/32".to_string()));
-        assert_eq!(metadata.camera_info().exposure_bias, Some("0".to_string()));
-        assert_eq!(metadata.camera_info().aperture, Some("5.6".to_string()));
-        assert_eq!(metadata.camera_info().iso, Some(100));
-        assert_eq!(metadata.camera_info().focal, Some(7.9));
         assert_eq!(
-            metadata.camera_info().flash,
-            Some(Metadata::flash_code_to_string(0x18))
+            parse_exif_date("06:10:29 16:27:21", 5).ok(),
+            Some(date(1906))
+        );
+        assert_eq!(
+            parse_exif_date("06:10:29 16:27:21", 100).ok(),
+            Some(date(2006))
+        );
+        // Four-digit years are not changed
+        assert_eq!(
+            parse_exif_date("1969:10:29 16:27:21", YEAR_PIVOT).ok(),
+            Some(date(1969))
         );
+
+        // Year 0000 is rejected
+        let e = parse_exif_date("0000:10:29 16:27:21", YEAR_PIVOT)
+            .err()
+            .unwrap();
+        assert!(e.to_string().contains("year 0000"));
     }
 
     #[test]
-    fn file_missing_tags() {
-        let result = Metadata::new(Path::new("tests/no_date.jpg"));
-        assert!(result.is_ok());
-        let metadata = result.unwrap();
+    fn date_error() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        let mut litte_metadata = LittleMetadata::new_from_path(&tmp_file_path).unwrap();
+        litte_metadata.set_tag(ExifTag::DateTimeOriginal("0000:10:29 16:27:21".to_string()));
+        litte_metadata.set_tag(ExifTag::CreateDate("0000:00:00 00:00:00".to_string()));
+        assert!(litte_metadata.write_to_file(&tmp_file_path).is_ok());
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
         assert_eq!(metadata.date(), None);
+        assert!(metadata.date_error().unwrap().contains("year 0000"));
 
-        let result = Metadata::new(Path::new("tests/no_description.jpg"));
-        assert!(result.is_ok());
-        let metadata = result.unwrap();
-        assert_eq!(metadata.description(), None);
+        // Unset dates (CreateDate) are not errors, two-digit years are expanded
+        let mut litte_metadata = LittleMetadata::new_from_path(&tmp_file_path).unwrap();
+        litte_metadata.set_tag(ExifTag::DateTimeOriginal("06:10:29 16:27:21".to_string()));
+        assert!(litte_metadata.write_to_file(&tmp_file_path).is_ok());
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.date_error(), None);
+        assert_eq!(metadata.date().map(|date| date.year()), Some(2006));
+        metadata.set_year_pivot(5);
+        assert_eq!(metadata.date().map(|date| date.year()), Some(1906));
+    }
 
-        let result = Metadata::new(Path::new("tests/no_camera.jpg"));
-        assert!(result.is_ok());
-        let metadata = result.unwrap();
-        assert_eq!(metadata.camera_info().camera, None);
+    #[test]
+    fn flexible_date() {
+        let expected = NaiveDate::from_ymd_opt(2006, 10, 29)
+            .unwrap()
+            .and_hms_opt(16, 27, 21)
+            .unwrap();
+        assert_eq!(
+            parse_date_flexible("2006-10-29 16:27:21").ok(),
+            Some(expected)
+        );
+        assert_eq!(
+            parse_date_flexible("2006-10-29T16:27:21").ok(),
+            Some(expected)
+        );
+        assert_eq!(
+            parse_date_flexible("2006/10/29 16:27:21").ok(),
+            Some(expected)
+        );
+        assert_eq!(
+            parse_date_flexible(" 2006:10:29 16:27:21 ").ok(),
+            Some(expected)
+        );
 
-        let result = Metadata::new(Path::new("tests/no_exposure.jpg"));
-        assert!(result.is_ok());
-        let metadata = result.unwrap();
-        assert_eq!(metadata.camera_info().exposure, None);
+        let midnight = NaiveDate::from_ymd_opt(2006, 10, 29)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        assert_eq!(parse_date_flexible("2006-10-29").ok(), Some(midnight));
+        assert_eq!(parse_date_flexible("2006/10/29").ok(), Some(midnight));
+        assert_eq!(parse_date_flexible("2006:10:29").ok(), Some(midnight));
 
-        let result = Metadata::new(Path::new("tests/no_bias.jpg"));
-        assert!(result.is_ok());
-        let metadata = result.unwrap();
-        assert_eq!(metadata.camera_info().exposure_bias, None);
+        let result = parse_date_flexible("29/10/2006");
+        assert!(result.is_err());
+        assert!(result.err().unwrap().to_string().contains("%Y-%m-%d"));
+    }
 
-        let result = Metadata::new(Path::new("tests/no_iso.jpg"));
-        assert!(result.is_ok());
-        let metadata = result.unwrap();
-        assert_eq!(metadata.camera_info().iso, None);
+    #[test]
+    fn relative_date() {
+        let before = Local::now().naive_local().with_nanosecond(0).unwrap();
+        let now = parse_date_flexible("now").unwrap();
+        let after = Local::now().naive_local();
+        assert!(before <= now && now <= after);
+        assert_eq!(now.nanosecond(), 0);
+        assert!(parse_date_flexible(" NOW ").is_ok());
 
-        let result = Metadata::new(Path::new("tests/no_focal.jpg"));
-        assert!(result.is_ok());
-        let metadata = result.unwrap();
-        assert_eq!(metadata.camera_info().focal, None);
+        let today = parse_date_flexible("today").unwrap();
+        assert_eq!(today.time(), NaiveTime::MIN);
+        assert!(today.date() == before.date() || today.date() == after.date());
+        assert!(parse_date_flexible("tomorrow").is_err());
+    }
 
-        let result = Metadata::new(Path::new("tests/no_flash.jpg"));
-        assert!(result.is_ok());
-        let metadata = result.unwrap();
-        assert_eq!(metadata.camera_info().flash, None);
+    #[test]
+    fn date_format() {
+        let date = NaiveDate::from_ymd_opt(2006, 10, 29)
+            .unwrap()
+            .and_hms_opt(16, 27, 21)
+            .unwrap();
+        assert_eq!(DateFormat::default().format(date), "2006:10:29 16:27:21");
+        let format = DateFormat::parse("iso").unwrap();
+        assert_eq!(format.format(date), "2006-10-29T16:27:21");
+        let format = DateFormat::parse("%d/%m/%Y %H:%M").unwrap();
+        assert_eq!(format.format(date), "29/10/2006 16:27");
+
+        // Malformed strftime formats
+        let result = DateFormat::parse("%d/%m/%Q");
+        assert!(result.is_err());
+        assert!(result.err().unwrap().to_string().contains("%Q"));
+        assert!(DateFormat::parse("year").is_err());
     }
 
     #[test]
-    fn update_tags() {
+    fn date_source() {
         let tmpdir = tempfile::tempdir().unwrap();
         let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
         assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
 
-        // Description tag check
-        let result = Metadata::new(&tmp_file_path);
-        assert!(result.is_ok());
-        let mut metadata = result.unwrap();
+        let mut litte_metadata = LittleMetadata::new_from_path(&tmp_file_path).unwrap();
+        litte_metadata.set_tag(ExifTag::CreateDate("2007:01:02 03:04:05".to_string()));
+        litte_metadata.set_tag(ExifTag::ModifyDate("2008:01:02 03:04:05".to_string()));
+        assert!(litte_metadata.write_to_file(&tmp_file_path).is_ok());
 
-        metadata.set_description("Description 1");
-        assert_eq!(metadata.description(), Some("Description 1".to_string()));
+        let date = |source| {
+            Metadata::new_with_date_source(&tmp_file_path, source)
+                .unwrap()
+                .date()
+                .map(|date| date.to_exif_string())
+        };
+        assert_eq!(
+            date(DateSource::Original),
+            Some("2006:10:29 16:27:21".to_string())
+        );
+        assert_eq!(
+            date(DateSource::Create),
+            Some("2007:01:02 03:04:05".to_string())
+        );
+        assert_eq!(
+            date(DateSource::Modified),
+            Some("2008:01:02 03:04:05".to_string())
+        );
 
-        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Description)));
+        // Synchronize dates according to date source
+        let mut metadata =
+            Metadata::new_with_date_source(&tmp_file_path, DateSource::Create).unwrap();
+        assert!(metadata.has_inconsistent_dates());
+        assert!(metadata.sync_dates());
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Date)));
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(!metadata.has_inconsistent_dates());
+        assert_eq!(
+            metadata.exif_date(),
+            Some("2007:01:02 03:04:05".to_string())
+        );
+        assert!(metadata.sync_dates());
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
 
-        let result = Metadata::new(&tmp_file_path);
-        assert!(result.is_ok());
-        let mut metadata = result.unwrap();
-        assert_eq!(metadata.description(), Some("Description 1".to_string()));
+        // Fallback to other tags
+        let mut litte_metadata = LittleMetadata::new_from_path(&tmp_file_path).unwrap();
+        litte_metadata.remove_tag(ExifTag::ModifyDate(String::new()));
+        assert!(litte_metadata.write_to_file(&tmp_file_path).is_ok());
         assert_eq!(
-            metadata.date(),
-            NaiveDate::from_ymd_opt(2006, 10, 29)
-                .unwrap()
-                .and_hms_opt(16, 27, 21)
+            date(DateSource::Modified),
+            Some("2006:10:29 16:27:21".to_string())
         );
+    }
 
-        // invalid Date tag check
-        assert!(metadata
-            .set_date_from_exif("2001:01:01".to_string())
-            .is_err());
+    #[test]
+    fn file_name_date() {
+        let expected = NaiveDate::from_ymd_opt(2006, 10, 29)
+            .unwrap()
+            .and_hms_opt(16, 27, 21);
+        assert_eq!(date_from_file_name("2006_10_29-16_27_21"), expected);
         assert_eq!(
-            metadata.date(),
-            NaiveDate::from_ymd_opt(2006, 10, 29)
-                .unwrap()
-                .and_hms_opt(16, 27, 21)
+            date_from_file_name("2006_10_29-16_27_21 - A fun picture!-1"),
+            expected
+        );
+        assert_eq!(date_from_file_name("IMG_20061029_162721"), expected);
+        assert_eq!(date_from_file_name("PXL_20061029_162721123"), expected);
+        assert_eq!(date_from_file_name("VID-20061029-162721"), expected);
+        assert_eq!(date_from_file_name("2006-10-29 16.27.21"), expected);
+        assert_eq!(date_from_file_name("Photo 2006-10-29_16-27-21"), expected);
+        assert_eq!(date_from_file_name("DSC_0123"), None);
+        assert_eq!(date_from_file_name("IMG_20061329_162721"), None);
+
+        let metadata = Metadata::new(Path::new("tests/all_tags.jpg")).unwrap();
+        assert_eq!(metadata.date_from_filename(), None);
+        assert!(!metadata.has_date_mismatch());
+
+        let tmpdir = tempfile::tempdir().unwrap();
+        let same = tmpdir.path().join("IMG_20061029_162721.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &same).is_ok());
+        assert!(!Metadata::new(&same).unwrap().has_date_mismatch());
+        let other = tmpdir.path().join("IMG_20061029_162722.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &other).is_ok());
+        assert!(Metadata::new(&other).unwrap().has_date_mismatch());
+    }
+
+    #[test]
+    fn normalize_to_utc() {
+        assert_eq!(
+            FixedOffset::from_exif_string("-05:30".to_string()).ok(),
+            FixedOffset::west_opt(5 * 3600 + 30 * 60)
         );
+        assert!(FixedOffset::from_exif_string("05:30".to_string()).is_err());
+        assert!(FixedOffset::from_exif_string("+0530".to_string()).is_err());
+        assert!(FixedOffset::from_exif_string("+05:-30".to_string()).is_err());
+        assert!(FixedOffset::from_exif_string("-+5:30".to_string()).is_err());
+        assert!(FixedOffset::from_exif_string("+05:60".to_string()).is_err());
+
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
 
+        // No offset
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.offset(), None);
+        assert!(!metadata.normalize_to_utc());
         assert_eq!(metadata.save().ok(), Some(enum_set!()));
 
-        let result = Metadata::new(&tmp_file_path);
-        assert!(result.is_ok());
-        let mut metadata = result.unwrap();
-        assert_eq!(metadata.description(), Some("Description 1".to_string()));
+        // Add an offset
+        let mut litte_metadata = LittleMetadata::new_from_path(&tmp_file_path).unwrap();
+        litte_metadata.set_tag(ExifTag::OffsetTimeOriginal("+02:00".to_string()));
+        assert!(litte_metadata.write_to_file(&tmp_file_path).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.offset(), FixedOffset::east_opt(2 * 3600));
+        assert!(metadata.normalize_to_utc());
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Date)));
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.offset(), FixedOffset::east_opt(0));
         assert_eq!(
             metadata.date(),
             NaiveDate::from_ymd_opt(2006, 10, 29)
                 .unwrap()
-                .and_hms_opt(16, 27, 21)
+                .and_hms_opt(14, 27, 21)
         );
+        assert!(metadata.normalize_to_utc());
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+    }
 
-        // Date tag check
-        assert!(metadata
-            .set_date_from_exif("2001:01:01 01:01:01".to_string())
-            .is_ok());
+    #[test]
+    fn shift_date() {
+        let reference = Metadata::new(Path::new("tests/all_tags.jpg")).unwrap();
+        let delta = reference.date_offset_to("2006-10-29 17:30:00");
         assert_eq!(
-            metadata.date(),
-            NaiveDate::from_ymd_opt(2001, 1, 1)
-                .unwrap()
-                .and_hms_opt(1, 1, 1)
+            delta.ok(),
+            Some(Duration::minutes(62) + Duration::seconds(39))
         );
+        assert!(reference.date_offset_to("not a date").is_err());
+        let reference = Metadata::new(Path::new("tests/no_date.jpg")).unwrap();
+        assert!(reference.date_offset_to("2006-10-29 17:30:00").is_err());
+
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
 
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(metadata.shift_date(Duration::hours(-2)));
         assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Date)));
 
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(
+            metadata.exif_date(),
+            Some("2006:10:29 14:27:21".to_string())
+        );
+
+        let mut metadata = Metadata::new(Path::new("tests/no_date.jpg")).unwrap();
+        assert!(!metadata.shift_date(Duration::hours(1)));
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+    }
+
+    #[test]
+    fn fix_dimensions() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+
+        // Check a valid file
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
         let result = Metadata::new(&tmp_file_path);
         assert!(result.is_ok());
         let mut metadata = result.unwrap();
-        assert_eq!(metadata.description(), Some("Description 1".to_string()));
+        assert!(!metadata.fix_dimentions());
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+
+        // Check an invalid file
+        assert!(fs::copy(Path::new("tests/invalid_dim.jpg"), &tmp_file_path).is_ok());
+        let result = Metadata::new(&tmp_file_path);
+        assert!(result.is_ok());
+        let mut metadata = result.unwrap();
+        assert_ne!(metadata.exif_dimensions(), Some((2048, 1536)));
+        assert!(metadata.fix_dimentions());
+        assert_eq!(metadata.exif_dimensions(), Some((2048, 1536)));
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Dimensions)));
+
+        // Reload file and check dimensions
+        let litte_metadata = LittleMetadata::new_from_path(&tmp_file_path);
+        assert!(litte_metadata.is_ok());
+        let width = Metadata::get_tag_u32(
+            litte_metadata.as_ref().unwrap(),
+            &ExifTag::ExifImageWidth(Vec::new()),
+        );
+        assert_eq!(width, Some(2048));
+        let height = Metadata::get_tag_u32(
+            litte_metadata.as_ref().unwrap(),
+            &ExifTag::ExifImageHeight(Vec::new()),
+        );
+        assert_eq!(height, Some(1536));
+    }
+
+    #[test]
+    fn sequence_name() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("DSC_0123.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.set_sequence_name("Wedding_001");
+        metadata.fix_file_name();
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::FileName)));
+        assert_eq!(metadata.path(), tmpdir.path().join("Wedding_001.jpg"));
+        assert!(!tmp_file_path.exists());
+
+        // Already named
+        let mut metadata = Metadata::new(metadata.path()).unwrap();
+        metadata.set_sequence_name("Wedding_001");
+        metadata.fix_file_name();
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+    }
+
+    #[test]
+    fn keep_original_name() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("DSC_0123.jpg");
+        let target_file_path = tmpdir
+            .path()
+            .join("2006_10_29-16_27_21 - A fun picture! - DSC_0123.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.keep_original_name();
+        metadata.fix_file_name();
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::FileName)));
+        assert!(target_file_path.exists());
+
+        // Already normalized: the name is kept as is
+        let mut metadata = Metadata::new(&target_file_path).unwrap();
+        metadata.keep_original_name();
+        metadata.fix_file_name();
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+        assert!(target_file_path.exists());
+
+        // Normalized name without description
+        let tmp_file_path = tmpdir.path().join("2006_10_29-16_27_21 - DSC_0123.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.original_name(), Some("DSC_0123".to_string()));
+
+        // Nothing to keep
+        let tmp_file_path = tmpdir.path().join("2006_10_29-16_27_21.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.original_name(), None);
+    }
+
+    #[test]
+    fn file_name_options() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.set_description("Fête à Saint-Cyprien");
+        let date = metadata.date().unwrap();
+        let prefix = |metadata: &Metadata| {
+            metadata
+                .normalized_file_prefix(date, OsStr::new("jpg"))
+                .to_string_lossy()
+                .to_string()
+        };
         assert_eq!(
-            metadata.date(),
-            NaiveDate::from_ymd_opt(2001, 1, 1)
-                .unwrap()
-                .and_hms_opt(1, 1, 1)
+            prefix(&metadata),
+            "2006_10_29-16_27_21 - Fête à Saint-Cyprien"
         );
 
-        // All tags check
-        assert!(metadata
-            .set_date_from_exif("2002:02:02 02:02:02".to_string())
-            .is_ok());
-        metadata.set_description("Description 2");
-        assert_eq!(metadata.description(), Some("Description 2".to_string()));
+        let mut options = FileNameOptions {
+            ascii_only: true,
+            ..Default::default()
+        };
+        metadata.set_file_name_options(options.clone());
         assert_eq!(
-            metadata.date(),
-            NaiveDate::from_ymd_opt(2002, 2, 2)
-                .unwrap()
-                .and_hms_opt(2, 2, 2)
+            prefix(&metadata),
+            "2006_10_29-16_27_21 - Fete a Saint-Cyprien"
         );
 
+        options.replace_spaces = Some('_');
+        metadata.set_file_name_options(options.clone());
         assert_eq!(
-            metadata.save().ok(),
-            Some(enum_set!(Tag::Date | Tag::Description))
+            prefix(&metadata),
+            "2006_10_29-16_27_21_-_Fete_a_Saint-Cyprien"
         );
 
-        let result = Metadata::new(&tmp_file_path);
-        assert!(result.is_ok());
-        let metadata = result.unwrap();
-        assert_eq!(metadata.description(), Some("Description 2".to_string()));
+        // Only the description is truncated
+        options.max_description_length = Some(7);
+        metadata.set_file_name_options(options.clone());
+        assert_eq!(prefix(&metadata), "2006_10_29-16_27_21_-_Fete_a");
+
+        // Original name is kept once
+        metadata.keep_original_name();
+        metadata.fix_file_name();
+        assert!(metadata.save().is_ok());
+        let tmp_file_path = tmpdir
+            .path()
+            .join("2006_10_29-16_27_21_-_Fete_a_-_photo_norm_test.jpg");
+        assert!(tmp_file_path.exists());
+        assert_eq!(metadata.path(), tmp_file_path);
         assert_eq!(
-            metadata.date(),
-            NaiveDate::from_ymd_opt(2002, 2, 2)
-                .unwrap()
-                .and_hms_opt(2, 2, 2)
+            metadata.original_name(),
+            Some("photo_norm_test".to_string())
         );
     }
 
     #[test]
-    fn fix_dimensions() {
+    fn normalize_extension() {
         let tmpdir = tempfile::tempdir().unwrap();
-        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
-
-        // Check a valid file
+        let tmp_file_path = tmpdir.path().join("PHOTO.JPEG");
+        let target_file_path = tmpdir
+            .path()
+            .join("2006_10_29-16_27_21 - A fun picture!.jpg");
         assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
-        let result = Metadata::new(&tmp_file_path);
-        assert!(result.is_ok());
-        let mut metadata = result.unwrap();
-        assert!(!metadata.fix_dimentions());
-        assert_eq!(metadata.save().ok(), Some(enum_set!()));
 
-        // Check an invalid file
-        assert!(fs::copy(Path::new("tests/invalid_dim.jpg"), &tmp_file_path).is_ok());
-        let result = Metadata::new(&tmp_file_path);
-        assert!(result.is_ok());
-        let mut metadata = result.unwrap();
-        assert!(metadata.fix_dimentions());
-        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Dimensions)));
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.normalize_extension();
+        metadata.fix_file_name();
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::FileName)));
+        assert!(target_file_path.exists());
+        assert!(!tmp_file_path.exists());
 
-        // Reload file and check dimensions
-        let litte_metadata = LittleMetadata::new_from_path(&tmp_file_path);
-        assert!(litte_metadata.is_ok());
-        let width = Metadata::get_tag_u32(
-            litte_metadata.as_ref().unwrap(),
-            &ExifTag::ExifImageWidth(Vec::new()),
+        assert_eq!(
+            normalized_extension(OsStr::new("TIF")),
+            OsString::from("tif")
         );
-        assert_eq!(width, Some(2048));
-        let height = Metadata::get_tag_u32(
-            litte_metadata.as_ref().unwrap(),
-            &ExifTag::ExifImageHeight(Vec::new()),
+        assert_eq!(
+            normalized_extension(OsStr::new("jpg")),
+            OsString::from("jpg")
         );
-        assert_eq!(height, Some(1536));
     }
 
     #[test]
@@ -717,6 +4317,11 @@ mod tests {
         assert!(result.is_ok());
         let mut metadata = result.unwrap();
         metadata.fix_file_name();
+        assert_eq!(
+            metadata.planned_changes(),
+            (enum_set!(Tag::FileName), Some(target_file_path.clone()))
+        );
+        assert!(tmp_file_path.exists());
         assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::FileName)));
         assert!(!tmp_file_path.exists());
         assert!(target_file_path.exists());
@@ -726,11 +4331,56 @@ mod tests {
         assert!(result.is_ok());
         let mut metadata = result.unwrap();
         metadata.fix_file_name();
+        assert_eq!(metadata.planned_changes(), (enum_set!(), None));
         assert_eq!(metadata.save().ok(), Some(enum_set!()));
         assert!(!tmp_file_path.exists());
         assert!(target_file_path.exists());
     }
 
+    #[test]
+    fn fix_file_name_conflict() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        let target_file_path = tmpdir
+            .path()
+            .join("2006_10_29-16_27_21 - A fun picture!.jpg");
+        let numbered_file_path = tmpdir
+            .path()
+            .join("2006_10_29-16_27_21 - A fun picture!-1.jpg");
+        assert!(fs::write(&target_file_path, "existing").is_ok());
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        // Skip
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.set_on_conflict(Conflict::Skip);
+        metadata.fix_file_name();
+        assert_eq!(metadata.rename_conflict(), Some(target_file_path.clone()));
+        assert_eq!(metadata.planned_changes(), (enum_set!(), None));
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+        assert!(tmp_file_path.exists());
+
+        // Number
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.fix_file_name();
+        assert_eq!(
+            metadata.planned_changes(),
+            (enum_set!(Tag::FileName), Some(numbered_file_path.clone()))
+        );
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::FileName)));
+        assert!(numbered_file_path.exists());
+        assert!(fs::rename(&numbered_file_path, &tmp_file_path).is_ok());
+
+        // Overwrite
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.set_on_conflict(Conflict::Overwrite);
+        metadata.fix_file_name();
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::FileName)));
+        assert!(!tmp_file_path.exists());
+        assert!(!numbered_file_path.exists());
+        assert!(Metadata::new(&target_file_path).is_ok());
+        assert_eq!(metadata.rename_conflict(), None);
+    }
+
     #[test]
     fn fix_file_name_numbered() {
         let tmpdir = tempfile::tempdir().unwrap();
@@ -771,6 +4421,65 @@ mod tests {
         assert!(target_file_path.exists());
     }
 
+    #[test]
+    fn move_to_folder() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        let folder = tmpdir.path().join("sorted/2006/10");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        // Copy and rename
+        let result = Metadata::new(&tmp_file_path);
+        assert!(result.is_ok());
+        let mut metadata = result.unwrap();
+        metadata.fix_file_name();
+        metadata.move_to_folder(&folder, true);
+        assert_eq!(
+            metadata.save().ok(),
+            Some(enum_set!(Tag::FileName | Tag::Folder))
+        );
+        assert!(tmp_file_path.exists());
+        assert!(folder
+            .join("2006_10_29-16_27_21 - A fun picture!.jpg")
+            .exists());
+
+        // Move, keeping name, numbered on collision
+        assert!(fs::copy(&tmp_file_path, folder.join("photo_norm_test.jpg")).is_ok());
+        let result = Metadata::new(&tmp_file_path);
+        assert!(result.is_ok());
+        let mut metadata = result.unwrap();
+        metadata.move_to_folder(&folder, false);
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Folder)));
+        assert!(!tmp_file_path.exists());
+        assert_eq!(metadata.path(), folder.join("photo_norm_test-1.jpg"));
+        assert!(metadata.path().exists());
+
+        // Already in folder
+        metadata.move_to_folder(&folder, false);
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fix_file_name_symlink() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        let link_path = tmpdir.path().join("photo_norm_link.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+        assert!(std::os::unix::fs::symlink(&tmp_file_path, &link_path).is_ok());
+
+        // Tags are read through the link but the link is not renamed
+        let result = Metadata::new(&link_path);
+        assert!(result.is_ok());
+        let mut metadata = result.unwrap();
+        assert!(metadata.is_symlink());
+        assert_eq!(metadata.description(), Some("A fun picture!".to_string()));
+        metadata.fix_file_name();
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+        assert!(file_list::is_symlink(&link_path));
+        assert!(tmp_file_path.exists());
+    }
+
     #[test]
     fn fix_orientation() {
         let tmpdir = tempfile::tempdir().unwrap();
@@ -785,9 +4494,13 @@ mod tests {
                 .unwrap();
         assert_eq!(orientation, 8);
 
-        metadata.fix_orientation();
+        assert_eq!(metadata.fix_orientation().ok(), Some(Some(8)));
+        assert_eq!(orientation_to_string(8), "90° CCW");
         assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Orientation)));
 
+        // No intermediate file is left
+        assert_eq!(fs::read_dir(tmpdir.path()).unwrap().count(), 1);
+
         let result = Metadata::new(&tmp_file_path);
         assert!(result.is_ok());
         let mut metadata = result.unwrap();
@@ -796,8 +4509,382 @@ mod tests {
                 .unwrap();
         assert_eq!(orientation, 1);
 
-        metadata.fix_orientation();
+        assert_eq!(metadata.fix_orientation().ok(), Some(None));
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+    }
+
+    #[test]
+    fn fix_orientation_in_memory() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let default_path = tmpdir.path().join("default.jpg");
+        let in_memory_path = tmpdir.path().join("in_memory.jpg");
+        fs::copy(Path::new("tests/all_tags.jpg"), &default_path).unwrap();
+        fs::copy(Path::new("tests/all_tags.jpg"), &in_memory_path).unwrap();
+
+        let mut metadata = Metadata::new(&default_path).unwrap();
+        assert_eq!(metadata.fix_orientation().ok(), Some(Some(8)));
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Orientation)));
+
+        let mut metadata = Metadata::new(&in_memory_path).unwrap();
+        assert_eq!(metadata.fix_orientation_in_memory().ok(), Some(Some(8)));
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Orientation)));
+
+        // Same result, no intermediate file is left
+        assert_eq!(
+            fs::read(&default_path).unwrap(),
+            fs::read(&in_memory_path).unwrap()
+        );
+        assert_eq!(fs::read_dir(tmpdir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn orientation_dims() {
+        assert!(orientation_consistent_with_dims(1, 2048, 1536));
+        assert!(orientation_consistent_with_dims(3, 2048, 1536));
+        assert!(orientation_consistent_with_dims(6, 1536, 2048));
+        assert!(orientation_consistent_with_dims(8, 100, 100));
+        assert!(!orientation_consistent_with_dims(6, 2048, 1536));
+        assert!(!orientation_consistent_with_dims(5, 2, 1));
+
+        // 2048x1536 with a 90° CCW orientation
+        let metadata = Metadata::new(Path::new("tests/all_tags.jpg")).unwrap();
+        assert!(!metadata.orientation_consistent());
+        // No orientation
+        let metadata = Metadata::new(Path::new("tests/webp_with_exif.webp")).unwrap();
+        assert!(metadata.orientation_consistent());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn save_failure_keeps_original() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        // A read-only file can be renamed and updated
+        let mut permissions = fs::metadata(&tmp_file_path).unwrap().permissions();
+        permissions.set_readonly(true);
+        assert!(fs::set_permissions(&tmp_file_path, permissions).is_ok());
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.set_description("Read-only");
+        metadata.fix_file_name();
+        assert!(metadata.save().is_ok());
+        assert!(!tmp_file_path.exists());
+        assert!(fs::metadata(metadata.path())
+            .unwrap()
+            .permissions()
+            .readonly());
+        assert_eq!(
+            Metadata::new(metadata.path()).unwrap().description(),
+            Some("Read-only".to_string())
+        );
+
+        // Cannot write into target folder
+        let tmp_file_path = metadata.path().to_path_buf();
+        let folder = tmpdir.path().join("readonly");
+        assert!(fs::create_dir(&folder).is_ok());
+        assert!(fs::set_permissions(&folder, fs::Permissions::from_mode(0o555)).is_ok());
+        if fs::File::create(folder.join("probe")).is_ok() {
+            // Permissions are not enforced (like for root)
+            return;
+        }
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.set_description("Moved");
+        metadata.move_to_folder(&folder, false);
+        assert!(metadata.save().is_err());
+        assert_eq!(fs::read_dir(&folder).unwrap().count(), 0);
+        assert_eq!(
+            Metadata::new(&tmp_file_path).unwrap().description(),
+            Some("Read-only".to_string())
+        );
+        assert!(fs::set_permissions(&folder, fs::Permissions::from_mode(0o755)).is_ok());
+    }
+
+    #[test]
+    fn fix_orientation_tiff() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.tiff");
+        assert!(fs::copy(Path::new("tests/rotated.tiff"), &tmp_file_path,).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.dimensions().ok(), Some((8, 4)));
+        assert_eq!(metadata.fix_orientation().ok(), Some(Some(6)));
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Orientation)));
+        // Already uncompressed
+        assert!(!metadata.compression_dropped());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.dimensions().ok(), Some((4, 8)));
+        let orientation =
+            Metadata::get_tag_u16(&metadata.litte_metadata, &ExifTag::Orientation(Vec::new()));
+        assert_eq!(orientation, Some(1));
+        assert_eq!(metadata.fix_orientation().ok(), Some(None));
+    }
+
+    #[test]
+    fn fix_dimensions_rotated() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        let mut litte_metadata = LittleMetadata::new_from_path(&tmp_file_path).unwrap();
+        litte_metadata.set_tag(ExifTag::Orientation(vec![6]));
+        assert!(litte_metadata.write_to_file(&tmp_file_path).is_ok());
+
+        // Stored dimensions by default
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        let (width, height) = metadata.dimensions().unwrap();
+        assert_ne!(width, height);
+        assert!(!metadata.fix_dimentions());
+
+        // Rotated dimensions
+        metadata.set_dims(Dims::Rotated);
+        assert!(metadata.needs_dimensions_fix());
+        assert!(metadata.fix_dimentions());
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Dimensions)));
+
+        let litte_metadata = LittleMetadata::new_from_path(&tmp_file_path).unwrap();
+        assert_eq!(
+            Metadata::get_tag_u32(&litte_metadata, &ExifTag::ExifImageWidth(Vec::new())),
+            Some(height)
+        );
+        assert_eq!(
+            Metadata::get_tag_u32(&litte_metadata, &ExifTag::ExifImageHeight(Vec::new())),
+            Some(width)
+        );
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.set_dims(Dims::Rotated);
+        assert!(!metadata.fix_dimentions());
+
+        // Back to stored dimensions
+        metadata.set_dims(Dims::Stored);
+        assert!(metadata.fix_dimentions());
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Dimensions)));
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(!metadata.needs_dimensions_fix());
+    }
+
+    #[test]
+    fn fix_orientation_unsupported() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.webp");
+        assert!(fs::copy(Path::new("tests/webp_with_exif.webp"), &tmp_file_path,).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.fix_orientation().ok(), Some(None));
+
+        let mut litte_metadata = LittleMetadata::new_from_path(&tmp_file_path).unwrap();
+        litte_metadata.set_tag(ExifTag::Orientation(vec![6]));
+        assert!(litte_metadata.write_to_file(&tmp_file_path).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(
+            metadata.fix_orientation().err().map(|e| e.kind()),
+            Some(ErrorKind::Unsupported)
+        );
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+    }
+
+    #[test]
+    fn all_tags() {
+        let metadata = Metadata::new(Path::new("tests/all_tags.jpg")).unwrap();
+        let tags = metadata.all_tags();
+        assert!(tags.contains(&(
+            "ImageDescription".to_string(),
+            "\"A fun picture!\"".to_string()
+        )));
+
+        assert!(Metadata::is_printable("\"Canon\""));
+        assert!(Metadata::is_printable("[1, 2, 3]"));
+        assert!(!Metadata::is_printable("\"Nikon\\0\\u{2}\""));
+        assert!(!Metadata::is_printable(&format!("{:?}", [0u8; 20])));
+        assert_eq!(
+            Metadata::bytes_summary(b"Nikon\0"),
+            "<6 bytes> 4e 69 6b 6f 6e 00"
+        );
+        assert_eq!(
+            Metadata::bytes_summary(&[0xff; 17]),
+            format!("<17 bytes> {} ...", ["ff"; 16].join(" "))
+        );
+    }
+
+    #[test]
+    fn user_comment() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(metadata
+            .set_user_comment("Été", CommentEncoding::Ascii)
+            .is_err());
+        assert!(metadata
+            .set_user_comment("Été à Toulouse", CommentEncoding::Unicode)
+            .is_ok());
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Comment)));
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.user_comment(), Some("Été à Toulouse".to_string()));
+
+        assert!(metadata
+            .set_user_comment("Summer", CommentEncoding::Ascii)
+            .is_ok());
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Comment)));
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.user_comment(), Some("Summer".to_string()));
+
+        // Unchanged comment is not written again, unless its encoding changes
+        assert!(metadata
+            .set_user_comment("Summer", CommentEncoding::Ascii)
+            .is_ok());
         assert_eq!(metadata.save().ok(), Some(enum_set!()));
+        assert!(metadata
+            .set_user_comment("Summer", CommentEncoding::Unicode)
+            .is_ok());
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Comment)));
+
+        metadata.clear_user_comment();
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Comment)));
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.user_comment(), None);
+
+        let endian = Endian::Big;
+        assert_eq!(Metadata::decode_user_comment(b"ASCII", &endian), None);
+        assert_eq!(
+            Metadata::decode_user_comment(b"\0\0\0\0\0\0\0\0Text  ", &endian),
+            Some("Text".to_string())
+        );
+        assert_eq!(
+            Metadata::decode_user_comment(b"UNICODE\0\0T\0e\0x\0t", &endian),
+            Some("Text".to_string())
+        );
+    }
+
+    #[test]
+    fn iptc_title_caption() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        let description = metadata.description();
+        assert_eq!(metadata.title(), None);
+        assert!(metadata.set_title("Capitole").is_ok());
+        assert!(metadata.set_caption("Été à Toulouse").is_ok());
+        assert_eq!(
+            metadata.save().ok(),
+            Some(enum_set!(Tag::Title | Tag::Caption))
+        );
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.title(), Some("Capitole".to_string()));
+        assert_eq!(metadata.caption(), Some("Été à Toulouse".to_string()));
+        assert_eq!(metadata.description(), description);
+        metadata.clear_title();
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Title)));
+
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.title(), None);
+        assert_eq!(metadata.caption(), Some("Été à Toulouse".to_string()));
+
+        let mut metadata = Metadata::new(Path::new("tests/all_tags.png")).unwrap();
+        assert!(metadata.set_title("Capitole").is_err());
+    }
+
+    #[test]
+    fn typed_tags() {
+        let metadata = Metadata::new(Path::new("tests/all_tags.jpg")).unwrap();
+        let tags: Vec<(String, TagValue)> = metadata.tags().collect();
+        assert!(tags.contains(&(
+            "ImageDescription".to_string(),
+            TagValue::Text("A fun picture!".to_string())
+        )));
+        assert!(tags.contains(&(
+            "ISO".to_string(),
+            TagValue::U16(vec![metadata.camera_info().iso.unwrap()])
+        )));
+        assert!(tags
+            .iter()
+            .any(|(name, value)| name == "FNumber" && matches!(value, TagValue::URational(_))));
+        assert_eq!(tags.len(), metadata.all_tags().len());
+    }
+
+    #[test]
+    fn preserve_maker_note() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        // Sony maker note
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(metadata.has_maker_note());
+        let maker_note = metadata.maker_note().unwrap();
+        assert_eq!(maker_note.len(), 1570);
+        assert!(maker_note.starts_with(b"SONY DSC "));
+        assert!(!metadata.will_write_exif());
+        metadata.fix_file_name();
+        assert!(!metadata.will_write_exif());
+
+        metadata.set_description("Description 1");
+        assert!(metadata.will_write_exif());
+        assert_eq!(metadata.save().ok(), Some(Tag::Description | Tag::FileName));
+        assert!(!metadata.maker_note_dropped());
+
+        let metadata = Metadata::new(metadata.path()).unwrap();
+        assert_eq!(metadata.maker_note(), Some(maker_note));
+    }
+
+    #[test]
+    fn fix_orientation_thumbnail() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path,).is_ok());
+
+        // Add a thumbnail with the stored image aspect (landscape)
+        let mut data: Vec<u8> = Vec::new();
+        assert!(image::DynamicImage::new_rgb8(160, 120)
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Jpeg)
+            .is_ok());
+        let mut litte_metadata = LittleMetadata::new_from_path(&tmp_file_path).unwrap();
+        litte_metadata.set_tag(ExifTag::ThumbnailLength(vec![data.len() as u32]));
+        litte_metadata.set_tag(ExifTag::ThumbnailOffset(vec![0], data));
+        assert!(litte_metadata.write_to_file(&tmp_file_path).is_ok());
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(metadata.fix_orientation().is_ok());
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Orientation)));
+
+        // Image and thumbnail are both portrait now
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.dimensions().ok(), Some((1536, 2048)));
+        let thumbnail = image::load_from_memory(&metadata.thumbnail().unwrap()).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (120, 160));
+    }
+
+    #[test]
+    fn extract_thumbnail() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let folder = tmpdir.path().join("thumbnails");
+
+        let metadata = Metadata::new(Path::new("tests/all_tags.jpg")).unwrap();
+        let thumbnail = image::load_from_memory(&metadata.thumbnail().unwrap()).unwrap();
+        let path = metadata.extract_thumbnail(&folder, false).unwrap();
+        assert_eq!(path, Some(folder.join("all_tags_thumb.jpg")));
+        assert_eq!(fs::read(path.unwrap()).ok(), metadata.thumbnail());
+
+        // Rotated according to Orientation tag (portrait)
+        let path = metadata.extract_thumbnail(&folder, true).unwrap();
+        assert_eq!(path, Some(folder.join("all_tags_thumb-1.jpg")));
+        let rotated = image::open(path.unwrap()).unwrap();
+        assert_eq!(
+            (rotated.width(), rotated.height()),
+            (thumbnail.height(), thumbnail.width())
+        );
+
+        let metadata = Metadata::new(Path::new("tests/webp_with_exif.webp")).unwrap();
+        assert_eq!(metadata.extract_thumbnail(&folder, false).ok(), Some(None));
     }
 
     // This test does not work: the load of PNG files fail
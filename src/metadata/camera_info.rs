@@ -1,7 +1,24 @@
+use little_exif::{
+    exif_tag::ExifTag,
+    metadata::Metadata as LittleMetadata,
+    rational::{iR64, uR64},
+};
+use serde::Serialize;
 use std::fmt;
 use std::fmt::Display;
 use std::fmt::Formatter;
 
+use super::Metadata;
+
+/// Unit used to display focal lengths
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum FocalUnit {
+    #[default]
+    Mm,
+    Cm,
+}
+
+#[derive(Serialize)]
 pub struct CameraInfo {
     pub camera: Option<String>,
     pub exposure: Option<String>,
@@ -9,14 +26,243 @@ pub struct CameraInfo {
     pub aperture: Option<String>,
     pub iso: Option<u16>,
     pub focal: Option<f64>,
+    pub focal_35mm: Option<u16>,
     pub flash: Option<String>,
+    pub color_space: Option<String>,
+    // Display only
+    #[serde(skip)]
+    pub focal_unit: FocalUnit,
+}
+
+impl CameraInfo {
+    /// Remove this software from the camera description: like a custom stamp of this tool
+    /// (see Metadata::stamp_software())
+    pub fn hide_software(&mut self, software: &str) {
+        let suffix = format!(" ({})", software.trim());
+        if let Some(camera) = self.camera.as_mut() {
+            if let Some(length) = camera.strip_suffix(&suffix).map(str::len) {
+                camera.truncate(length);
+            }
+        }
+    }
+
+    /// Load and format camera info from little_exif metadata.
+    /// icc_profile tells if the image embeds an ICC profile (see color_space_to_string).
+    pub fn from_metadata(litte_metadata: &LittleMetadata, icc_profile: bool) -> CameraInfo {
+        let make = Metadata::get_tag_string(litte_metadata, &ExifTag::Make(String::new()));
+        let model = Metadata::get_tag_string(litte_metadata, &ExifTag::Model(String::new()));
+        // Software stamped by this tool (see Metadata::stamp_software()) is not camera related
+        let software = Metadata::get_tag_string(litte_metadata, &ExifTag::Software(String::new()))
+            .filter(|software| !software.starts_with(env!("CARGO_PKG_NAME")));
+        let camera = if make.is_some() && model.is_some() {
+            let mut camera = make.unwrap().clone();
+            camera.push(' ');
+            camera.push_str(&model.unwrap());
+            Some(camera)
+        } else if make.is_some() {
+            make
+        } else if model.is_some() {
+            model
+        } else {
+            None
+        };
+        let camera = if camera.is_some() && software.is_some() {
+            let mut camera = camera.unwrap().clone();
+            camera.push_str(" (");
+            camera.push_str(&software.unwrap());
+            camera.push(')');
+            Some(camera)
+        } else {
+            camera
+        };
+
+        let exposure = Metadata::get_tag_ur64(litte_metadata, &ExifTag::ExposureTime(Vec::new()))
+            .and_then(Self::exposure_time_to_string)
+            .or(
+                Metadata::get_tag_ir64(litte_metadata, &ExifTag::ShutterSpeedValue(Vec::new()))
+                    .and_then(Self::shutter_speed_to_string),
+            );
+
+        let exposure_bias =
+            Metadata::get_tag_ir64(litte_metadata, &ExifTag::ExposureCompensation(Vec::new()))
+                .and_then(Self::exposure_bias_to_string);
+
+        let aperture = Metadata::get_tag_ur64(litte_metadata, &ExifTag::FNumber(Vec::new()))
+            .and_then(Self::f_number_to_f64)
+            .or(
+                Metadata::get_tag_ur64(litte_metadata, &ExifTag::ApertureValue(Vec::new()))
+                    .and_then(Self::aperture_value_to_f64),
+            )
+            .map(|value| format!("{:.1}", value));
+
+        let iso = Metadata::get_tag_u16(litte_metadata, &ExifTag::ISO(Vec::new()));
+
+        let focal = Metadata::get_tag_ur64(litte_metadata, &ExifTag::FocalLength(Vec::new()))
+            .and_then(Self::focal_length_to_f64);
+        let focal_35mm = Metadata::get_tag_u16(
+            litte_metadata,
+            &ExifTag::FocalLengthIn35mmFormat(Vec::new()),
+        );
+
+        let flash = Metadata::get_tag_u16(litte_metadata, &ExifTag::Flash(Vec::new()))
+            .map(Self::flash_code_to_string);
+
+        let color_space = Metadata::get_tag_u16(litte_metadata, &ExifTag::ColorSpace(Vec::new()))
+            .map(|code| Self::color_space_to_string(code, icc_profile));
+
+        CameraInfo {
+            camera,
+            exposure,
+            exposure_bias,
+            aperture,
+            iso,
+            focal,
+            focal_35mm,
+            flash,
+            color_space,
+            focal_unit: FocalUnit::default(),
+        }
+    }
+
+    // Format an exposure in seconds: 1/N below 1/4s, 0.3s, 2s, 30s...
+    fn seconds_to_string(value: f64) -> Option<String> {
+        if !value.is_finite() || value <= 0f64 {
+            None
+        } else if value < 0.25001 {
+            Some(format!("1/{}", (1f64 / value).round()))
+        } else if (value - value.round()).abs() < 0.05 {
+            Some(format!("{}s", value.round()))
+        } else {
+            Some(format!("{:.1}s", value))
+        }
+    }
+
+    // Format ExposureTime (in seconds)
+    fn exposure_time_to_string(rational: uR64) -> Option<String> {
+        if rational.nominator == 0 || rational.denominator == 0 {
+            None
+        } else if rational.nominator < rational.denominator
+            && rational.denominator % rational.nominator == 0
+        {
+            Some(format!("1/{}", rational.denominator / rational.nominator))
+        } else if rational.nominator < rational.denominator {
+            Some(format!("{}/{}", rational.nominator, rational.denominator))
+        } else {
+            Self::seconds_to_string(rational.into())
+        }
+    }
+
+    // Format ShutterSpeedValue (APEX format)
+    fn shutter_speed_to_string(rational: iR64) -> Option<String> {
+        if rational.denominator == 0 {
+            return None;
+        }
+        let value: f64 = rational.into();
+        // Convert APEX format to seconds
+        Self::seconds_to_string(2f64.powf(-value))
+    }
+
+    // Format ExposureCompensation (in EV)
+    fn exposure_bias_to_string(rational: iR64) -> Option<String> {
+        if rational.denominator == 0 {
+            None
+        } else if rational.nominator == 0 {
+            Some("0".to_string())
+        } else {
+            Some(format!("{}/{}", rational.nominator, rational.denominator))
+        }
+    }
+
+    // Read FNumber
+    fn f_number_to_f64(rational: uR64) -> Option<f64> {
+        if rational.denominator == 0 || rational.nominator == 0 {
+            None
+        } else {
+            Some(rational.into())
+        }
+    }
+
+    // Convert ApertureValue (APEX format) to f-number
+    fn aperture_value_to_f64(rational: uR64) -> Option<f64> {
+        if rational.denominator == 0 {
+            None
+        } else {
+            let value: f64 = rational.into();
+            Some(2f64.powf(value / 2f64))
+        }
+    }
+
+    // Read FocalLength (0 means unknown)
+    fn focal_length_to_f64(rational: uR64) -> Option<f64> {
+        if rational.denominator == 0 || rational.nominator == 0 {
+            None
+        } else {
+            Some(rational.into())
+        }
+    }
+
+    // Format a length in mm with the given precision (in focal_unit)
+    fn length_to_string(&self, mm: f64, precision: usize) -> String {
+        match self.focal_unit {
+            FocalUnit::Mm => format!("{:.*} mm", precision, mm),
+            FocalUnit::Cm => format!("{:.*} cm", precision + 1, mm / 10f64),
+        }
+    }
+
+    /// ColorSpace tag. Uncalibrated is usually Adobe RGB, described by an ICC profile.
+    pub fn color_space_to_string(color_space: u16, icc_profile: bool) -> String {
+        match (color_space, icc_profile) {
+            (0x1, _) => "sRGB",
+            (0x2, _) => "Adobe RGB",
+            (0xfffd, _) => "Wide Gamut RGB",
+            (0xfffe, _) => "ICC Profile",
+            (0xffff, true) => "Uncalibrated (ICC present)",
+            (0xffff, false) => "Uncalibrated",
+            _ => "Unknown color space",
+        }
+        .to_string()
+    }
+
+    pub fn flash_code_to_string(flash_code: u16) -> String {
+        match flash_code {
+            0x00 => "No Flash",
+            0x01 => "Fired",
+            0x05 => "Fired, Return not detected",
+            0x07 => "Fired, Return detected",
+            0x08 => "On, Did not fire",
+            0x09 => "On, Fired",
+            0x0d => "On, Return not detected",
+            0x0f => "On, Return detected",
+            0x10 => "Off, Did not fire",
+            0x14 => "Off, Did not fire, Return not detected",
+            0x18 => "Auto, Did not fire",
+            0x19 => "Auto, Fired",
+            0x1d => "Auto, Fired, Return not detected",
+            0x1f => "Auto, Fired, Return detected",
+            0x20 => "No flash function",
+            0x30 => "Off, No flash function",
+            0x41 => "Fired, Red-eye reduction",
+            0x45 => "Fired, Red-eye reduction, Return not detected",
+            0x47 => "Fired, Red-eye reduction, Return detected",
+            0x49 => "On, Red-eye reduction",
+            0x4d => "On, Red-eye reduction, Return not detected",
+            0x4f => "On, Red-eye reduction, Return detected",
+            0x50 => "Off, Red-eye reduction",
+            0x58 => "Auto, Did not fire, Red-eye reduction",
+            0x59 => "Auto, Fired, Red-eye reduction",
+            0x5d => "Auto, Fired, Red-eye reduction, Return not detected",
+            0x5f => "Auto, Fired, Red-eye reduction, Return detected",
+            _ => "Unknown flash code",
+        }
+        .to_string()
+    }
 }
 
 impl Display for CameraInfo {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(
             f,
-            "{}, Exposure: {}, Bias: {}, Aperture: {}, ISO: {}, Focal: {}, Flash: {}",
+            "{}, Exposure: {}, Bias: {}, Aperture: {}, ISO: {}, Focal: {}, Flash: {}, Color space: {}",
             self.camera
                 .as_ref()
                 .unwrap_or(&"Unknown camera".to_string()),
@@ -29,11 +275,172 @@ impl Display for CameraInfo {
                 Some(v) => v.to_string(),
                 None => "Undefined".to_string(),
             },
-            match self.focal {
-                Some(v) => format!("{} mm", v),
-                None => "Undefined".to_string(),
+            match (self.focal, self.focal_35mm) {
+                (Some(v), Some(v35)) => format!(
+                    "{} ({} eq)",
+                    self.length_to_string(v, 1),
+                    self.length_to_string(v35.into(), 0)
+                ),
+                (Some(v), None) => self.length_to_string(v, 1),
+                (None, Some(v35)) => format!("{} eq", self.length_to_string(v35.into(), 0)),
+                (None, None) => "Undefined".to_string(),
             },
             self.flash.as_ref().unwrap_or(&"Undefined".to_string()),
+            self.color_space
+                .as_ref()
+                .unwrap_or(&"Undefined".to_string()),
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera_info(focal: Option<f64>, focal_35mm: Option<u16>) -> CameraInfo {
+        CameraInfo {
+            camera: None,
+            exposure: None,
+            exposure_bias: None,
+            aperture: None,
+            iso: None,
+            focal,
+            focal_35mm,
+            flash: None,
+            color_space: None,
+            focal_unit: FocalUnit::Mm,
+        }
+    }
+
+    #[test]
+    fn exposure_conversions() {
+        let ur64 = |nominator, denominator| uR64 {
+            nominator,
+            denominator,
+        };
+        let ir64 = |nominator, denominator| iR64 {
+            nominator,
+            denominator,
+        };
+
+        assert_eq!(
+            CameraInfo::exposure_time_to_string(ur64(1, 32)),
+            Some("1/32".to_string())
+        );
+        assert_eq!(
+            CameraInfo::exposure_time_to_string(ur64(10, 320)),
+            Some("1/32".to_string())
+        );
+        assert_eq!(
+            CameraInfo::exposure_time_to_string(ur64(3, 10)),
+            Some("3/10".to_string())
+        );
+        assert_eq!(
+            CameraInfo::exposure_time_to_string(ur64(30, 1)),
+            Some("30s".to_string())
+        );
+        assert_eq!(
+            CameraInfo::exposure_time_to_string(ur64(25, 10)),
+            Some("2.5s".to_string())
+        );
+        assert_eq!(CameraInfo::exposure_time_to_string(ur64(1, 0)), None);
+        assert_eq!(CameraInfo::exposure_time_to_string(ur64(0, 1)), None);
+
+        assert_eq!(
+            CameraInfo::shutter_speed_to_string(ir64(5, 1)),
+            Some("1/32".to_string())
+        );
+        // Negative APEX: long exposures
+        assert_eq!(
+            CameraInfo::shutter_speed_to_string(ir64(-5, 1)),
+            Some("32s".to_string())
+        );
+        assert_eq!(
+            CameraInfo::shutter_speed_to_string(ir64(0, 1)),
+            Some("1s".to_string())
+        );
+        assert_eq!(CameraInfo::shutter_speed_to_string(ir64(5, 0)), None);
+        // Bulb: exposure too long to be represented
+        assert_eq!(CameraInfo::shutter_speed_to_string(ir64(-2000, 1)), None);
+
+        assert_eq!(CameraInfo::exposure_bias_to_string(ir64(0, 0)), None);
+        assert_eq!(
+            CameraInfo::exposure_bias_to_string(ir64(0, 1)),
+            Some("0".to_string())
+        );
+        assert_eq!(
+            CameraInfo::exposure_bias_to_string(ir64(-1, 3)),
+            Some("-1/3".to_string())
+        );
+
+        assert_eq!(CameraInfo::f_number_to_f64(ur64(56, 10)), Some(5.6));
+        assert_eq!(CameraInfo::f_number_to_f64(ur64(56, 0)), None);
+        assert_eq!(CameraInfo::aperture_value_to_f64(ur64(4, 1)), Some(4.0));
+        assert_eq!(CameraInfo::aperture_value_to_f64(ur64(4, 0)), None);
+    }
+
+    #[test]
+    fn color_space() {
+        assert_eq!(CameraInfo::color_space_to_string(1, false), "sRGB");
+        assert_eq!(CameraInfo::color_space_to_string(1, true), "sRGB");
+        assert_eq!(
+            CameraInfo::color_space_to_string(0xffff, true),
+            "Uncalibrated (ICC present)"
+        );
+        assert_eq!(
+            CameraInfo::color_space_to_string(0xffff, false),
+            "Uncalibrated"
+        );
+        assert_eq!(
+            CameraInfo::color_space_to_string(3, false),
+            "Unknown color space"
+        );
+        assert!(camera_info(None, None)
+            .to_string()
+            .ends_with("Color space: Undefined"));
+    }
+
+    #[test]
+    fn display_focal() {
+        let focal = |info: CameraInfo| {
+            info.to_string()
+                .split("Focal: ")
+                .nth(1)
+                .unwrap()
+                .split(',')
+                .next()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(focal(camera_info(Some(7.9), Some(28))), "7.9 mm (28 mm eq)");
+        assert_eq!(focal(camera_info(Some(7.9), None)), "7.9 mm");
+        assert_eq!(focal(camera_info(None, Some(28))), "28 mm eq");
+        assert_eq!(focal(camera_info(None, None)), "Undefined");
+
+        // Float artifacts are rounded
+        assert_eq!(focal(camera_info(Some(7.900000000001), None)), "7.9 mm");
+        assert_eq!(focal(camera_info(Some(50.0), None)), "50.0 mm");
+        let mut info = camera_info(Some(7.9), Some(28));
+        info.focal_unit = FocalUnit::Cm;
+        assert_eq!(focal(info), "0.79 cm (2.8 cm eq)");
+
+        // 0/0 would be NaN
+        let ur64 = |nominator, denominator| uR64 {
+            nominator,
+            denominator,
+        };
+        assert_eq!(CameraInfo::focal_length_to_f64(ur64(0, 0)), None);
+        assert_eq!(CameraInfo::focal_length_to_f64(ur64(79, 0)), None);
+        assert_eq!(CameraInfo::focal_length_to_f64(ur64(0, 1)), None);
+        assert_eq!(CameraInfo::focal_length_to_f64(ur64(79, 10)), Some(7.9));
+    }
+
+    #[test]
+    fn serialize() {
+        let value = serde_json::to_value(camera_info(Some(7.9), Some(28))).unwrap();
+        assert_eq!(value["focal"], 7.9);
+        assert_eq!(value["focal_35mm"], 28);
+        assert!(value["iso"].is_null());
+        assert!(value.get("focal_unit").is_none());
+    }
+}
@@ -0,0 +1,248 @@
+use chrono::NaiveDateTime;
+use little_exif::{exif_tag::ExifTag, metadata::Metadata as LittleMetadata};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::{
+    io::Error,
+    path::{Path, PathBuf},
+};
+
+use crate::file_list;
+
+use super::Metadata;
+
+/// A tag that differs between two images
+#[derive(Debug, PartialEq)]
+pub struct TagDiff {
+    pub id: u16,
+    pub name: String,
+    pub a: Option<String>,
+    pub b: Option<String>,
+}
+
+impl TagDiff {
+    pub fn is_added(&self) -> bool {
+        self.a.is_none()
+    }
+    pub fn is_removed(&self) -> bool {
+        self.b.is_none()
+    }
+}
+
+/// Tag name (from little_exif ExifTag name)
+pub fn tag_name(tag: &ExifTag) -> String {
+    let debug = format!("{:?}", tag);
+    match debug.split_once('(') {
+        Some((name, _)) => name.to_string(),
+        None => debug,
+    }
+}
+
+/// Tag value, as displayed by little_exif
+pub fn tag_value(tag: &ExifTag) -> String {
+    let debug = format!("{:?}", tag);
+    match debug.split_once('(') {
+        Some((_, value)) => value.strip_suffix(')').unwrap_or(value).to_string(),
+        None => String::new(),
+    }
+}
+
+// All tags by ID. If a tag is defined several times (like in thumbnail IFD), keep the first one.
+fn tags_by_id(metadata: &LittleMetadata) -> BTreeMap<u16, &ExifTag> {
+    let mut tags = BTreeMap::new();
+    for tag in metadata {
+        tags.entry(tag.as_u16()).or_insert(tag);
+    }
+    tags
+}
+
+/// Compare all tags of two images
+pub fn diff(a: &LittleMetadata, b: &LittleMetadata) -> Vec<TagDiff> {
+    let a = tags_by_id(a);
+    let b = tags_by_id(b);
+    let mut ids: Vec<u16> = a.keys().chain(b.keys()).copied().collect();
+    ids.sort();
+    ids.dedup();
+
+    let mut diffs = Vec::new();
+    for id in ids {
+        let tag_a = a.get(&id);
+        let tag_b = b.get(&id);
+        let value_a = tag_a.map(|tag| tag_value(tag));
+        let value_b = tag_b.map(|tag| tag_value(tag));
+        if value_a != value_b {
+            diffs.push(TagDiff {
+                id,
+                name: tag_name(tag_a.or(tag_b).unwrap()),
+                a: value_a,
+                b: value_b,
+            });
+        }
+    }
+    diffs
+}
+
+/// Compare all tags of two image files
+pub fn diff_files(a: &Path, b: &Path) -> Result<Vec<TagDiff>, Error> {
+    let a = Metadata::new(a)?;
+    let b = Metadata::new(b)?;
+    Ok(diff(&a.litte_metadata, &b.litte_metadata))
+}
+
+/// Images of a reference folder (non-recursive), to compare other images with
+pub struct ReferenceFolder {
+    by_name: HashMap<OsString, PathBuf>,
+    by_date: HashMap<NaiveDateTime, PathBuf>,
+}
+
+impl ReferenceFolder {
+    /// List images of the folder. Images are loaded to match them by date if requested
+    /// (two-digit years with the given pivot, see Metadata::set_year_pivot()).
+    pub fn load(folder: &Path, match_by_date: bool, year_pivot: i32) -> ReferenceFolder {
+        let (images, _, _) = file_list::expand(&[folder.to_path_buf()], false);
+        let mut by_name = HashMap::new();
+        let mut by_date = HashMap::new();
+        for image in images {
+            if match_by_date {
+                let date = Metadata::new(&image).ok().and_then(|mut metadata| {
+                    metadata.set_year_pivot(year_pivot);
+                    metadata.date()
+                });
+                if let Some(date) = date {
+                    // Keep the first image of a given date
+                    by_date.entry(date).or_insert(image.clone());
+                }
+            }
+            if let Some(name) = image.file_name() {
+                by_name.insert(name.to_os_string(), image);
+            }
+        }
+        ReferenceFolder { by_name, by_date }
+    }
+
+    /// Reference image with the same file name, or else with the same date (see load())
+    pub fn find(&self, metadata: &Metadata) -> Option<&PathBuf> {
+        metadata
+            .path()
+            .file_name()
+            .and_then(|name| self.by_name.get(name))
+            .or_else(|| self.by_date.get(&metadata.date()?))
+    }
+}
+
+/// Statistics of the comparison of images with their reference images
+#[derive(Default)]
+pub struct DriftSummary {
+    pub compared: usize,
+    pub differ: usize,
+    pub no_reference: usize,
+    /// Number of images by differing tag name
+    pub tags: BTreeMap<String, usize>,
+}
+
+impl DriftSummary {
+    pub fn add(&mut self, diffs: &[TagDiff]) {
+        self.compared += 1;
+        if !diffs.is_empty() {
+            self.differ += 1;
+        }
+        for diff in diffs {
+            *self.tags.entry(diff.name.clone()).or_default() += 1;
+        }
+    }
+    pub fn add_no_reference(&mut self) {
+        self.no_reference += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::YEAR_PIVOT;
+
+    #[test]
+    fn reference_folder() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let reference = tmpdir.path().join("reference");
+        assert!(std::fs::create_dir(&reference).is_ok());
+        assert!(std::fs::copy("tests/all_tags.jpg", reference.join("a.jpg")).is_ok());
+        assert!(std::fs::copy("tests/no_date.jpg", reference.join("b.jpg")).is_ok());
+        let renamed = tmpdir.path().join("2006_10_29-16_27_21.jpg");
+        assert!(std::fs::copy("tests/all_tags.jpg", &renamed).is_ok());
+        let same_name = tmpdir.path().join("b.jpg");
+        assert!(std::fs::copy("tests/all_tags.jpg", &same_name).is_ok());
+
+        let folder = ReferenceFolder::load(&reference, false, YEAR_PIVOT);
+        let same_name = Metadata::new(&same_name).unwrap();
+        assert_eq!(folder.find(&same_name), Some(&reference.join("b.jpg")));
+        let renamed = Metadata::new(&renamed).unwrap();
+        assert_eq!(folder.find(&renamed), None);
+
+        // Renamed files are matched by date
+        let folder = ReferenceFolder::load(&reference, true, YEAR_PIVOT);
+        assert_eq!(folder.find(&renamed), Some(&reference.join("a.jpg")));
+        assert_eq!(folder.find(&same_name), Some(&reference.join("b.jpg")));
+
+        let mut summary = DriftSummary::default();
+        summary.add(&diff_files(&reference.join("b.jpg"), same_name.path()).unwrap());
+        summary.add(&diff_files(&reference.join("a.jpg"), renamed.path()).unwrap());
+        summary.add_no_reference();
+        assert_eq!(
+            (summary.compared, summary.differ, summary.no_reference),
+            (2, 1, 1)
+        );
+        assert!(summary.tags.values().all(|count| *count == 1));
+    }
+
+    #[test]
+    fn diff_same_file() {
+        let result = diff_files(
+            Path::new("tests/all_tags.jpg"),
+            Path::new("tests/all_tags.jpg"),
+        );
+        assert_eq!(result.ok(), Some(Vec::new()));
+    }
+
+    #[test]
+    fn diff_removed_and_added() {
+        let result = diff_files(
+            Path::new("tests/all_tags.jpg"),
+            Path::new("tests/no_description.jpg"),
+        );
+        assert!(result.is_ok());
+        let diffs = result.unwrap();
+        let description = diffs
+            .iter()
+            .find(|d| d.id == ExifTag::ImageDescription(String::new()).as_u16());
+        assert!(description.is_some());
+        assert_eq!(description.unwrap().name, "ImageDescription");
+        assert!(description.unwrap().is_removed());
+
+        let result = diff_files(
+            Path::new("tests/no_description.jpg"),
+            Path::new("tests/all_tags.jpg"),
+        );
+        let diffs = result.unwrap();
+        assert!(diffs
+            .iter()
+            .any(|d| d.name == "ImageDescription" && d.is_added()));
+    }
+
+    #[test]
+    fn diff_changed() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(std::fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path).is_ok());
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        metadata.set_description("Description 1");
+        assert!(metadata.save().is_ok());
+
+        let result = diff_files(Path::new("tests/all_tags.jpg"), &tmp_file_path);
+        let diffs = result.unwrap();
+        let description = diffs.iter().find(|d| d.name == "ImageDescription");
+        assert!(description.is_some());
+        let description = description.unwrap();
+        assert!(!description.is_added() && !description.is_removed());
+        assert_eq!(description.b, Some("\"Description 1\"".to_string()));
+    }
+}
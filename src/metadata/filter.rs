@@ -0,0 +1,244 @@
+use chrono::NaiveDateTime;
+use std::io::Error;
+
+use super::{parse_date_flexible, Metadata};
+
+/// Filter: predicates separated by commas, all of them must match.
+/// A predicate is <field><operator><value>, like camera~=Canon, date>=2006-01-01 or iso>800.
+/// Fields: camera, description, keyword, date, iso, rating, width, height.
+/// Operators: ~= (contains, case insensitive), =, !=, <, <=, >, >=.
+/// A predicate on an undefined value (like the iso of an image without ISO tag) never matches.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Filter {
+    predicates: Vec<Predicate>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Predicate {
+    field: Field,
+    operator: Operator,
+    value: Value,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Field {
+    Camera,
+    Description,
+    Keyword,
+    Date,
+    Iso,
+    Rating,
+    Width,
+    Height,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operator {
+    Contains,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Text(String),
+    Date(NaiveDateTime),
+    Number(f64),
+}
+
+// Longest operators first
+const OPERATORS: [(&str, Operator); 7] = [
+    ("~=", Operator::Contains),
+    ("!=", Operator::NotEqual),
+    ("<=", Operator::LessOrEqual),
+    (">=", Operator::GreaterOrEqual),
+    ("=", Operator::Equal),
+    ("<", Operator::Less),
+    (">", Operator::Greater),
+];
+
+impl Filter {
+    /// Parse a filter like "camera~=Canon,date>=2006-01-01"
+    pub fn parse(filter: &str) -> Result<Filter, Error> {
+        let predicates = filter
+            .split(',')
+            .map(str::trim)
+            .filter(|predicate| !predicate.is_empty())
+            .map(Predicate::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        if predicates.is_empty() {
+            return Err(Error::other("Empty filter."));
+        }
+        Ok(Filter { predicates })
+    }
+
+    /// Check if an image matches all predicates
+    pub fn matches(&self, metadata: &Metadata) -> bool {
+        self.predicates
+            .iter()
+            .all(|predicate| predicate.matches(metadata))
+    }
+}
+
+impl Predicate {
+    fn parse(predicate: &str) -> Result<Predicate, Error> {
+        let invalid = |message: &str| Error::other(format!("{}: '{}'.", message, predicate));
+
+        let Some(position) = predicate.find(['~', '!', '<', '>', '=']) else {
+            return Err(invalid("Missing operator"));
+        };
+        let Some((symbol, operator)) = OPERATORS
+            .iter()
+            .find(|(symbol, _)| predicate[position..].starts_with(symbol))
+        else {
+            return Err(invalid("Invalid operator"));
+        };
+        let field = match predicate[..position].trim().to_lowercase().as_str() {
+            "camera" => Field::Camera,
+            "description" => Field::Description,
+            "keyword" => Field::Keyword,
+            "date" => Field::Date,
+            "iso" => Field::Iso,
+            "rating" => Field::Rating,
+            "width" => Field::Width,
+            "height" => Field::Height,
+            _ => return Err(invalid("Unknown field")),
+        };
+        let value = predicate[position + symbol.len()..].trim();
+
+        let value = match field {
+            Field::Camera | Field::Description | Field::Keyword => {
+                if !matches!(
+                    operator,
+                    Operator::Contains | Operator::Equal | Operator::NotEqual
+                ) {
+                    return Err(invalid("Text can only be compared with ~=, = or !="));
+                }
+                Value::Text(value.to_string())
+            }
+            Field::Date => {
+                if *operator == Operator::Contains {
+                    return Err(invalid("Dates cannot be compared with ~="));
+                }
+                Value::Date(parse_date_flexible(value)?)
+            }
+            _ => {
+                if *operator == Operator::Contains {
+                    return Err(invalid("Numbers cannot be compared with ~="));
+                }
+                match value.parse() {
+                    Ok(number) => Value::Number(number),
+                    Err(_) => return Err(invalid("Invalid number")),
+                }
+            }
+        };
+
+        Ok(Predicate {
+            field,
+            operator: *operator,
+            value,
+        })
+    }
+
+    fn matches(&self, metadata: &Metadata) -> bool {
+        match &self.value {
+            Value::Text(value) => {
+                let texts: Vec<String> = match self.field {
+                    Field::Camera => metadata.camera_info().camera.iter().cloned().collect(),
+                    Field::Description => metadata.description().into_iter().collect(),
+                    _ => metadata.keywords(),
+                };
+                let value = value.to_lowercase();
+                let found = texts.iter().any(|text| match self.operator {
+                    Operator::Contains => text.to_lowercase().contains(&value),
+                    _ => text.to_lowercase() == value,
+                });
+                match self.operator {
+                    Operator::NotEqual => !texts.is_empty() && !found,
+                    _ => found,
+                }
+            }
+            Value::Date(value) => match metadata.date() {
+                None => false,
+                Some(date) => self.compare(date.partial_cmp(value)),
+            },
+            Value::Number(value) => {
+                let number = match self.field {
+                    Field::Iso => metadata.camera_info().iso.map(f64::from),
+                    Field::Rating => metadata.rating().map(f64::from),
+                    // Unknown dimensions never match
+                    Field::Width => metadata.dimensions().ok().map(|(width, _)| width.into()),
+                    _ => metadata.dimensions().ok().map(|(_, height)| height.into()),
+                };
+                match number {
+                    None => false,
+                    Some(number) => self.compare(number.partial_cmp(value)),
+                }
+            }
+        }
+    }
+
+    fn compare(&self, ordering: Option<std::cmp::Ordering>) -> bool {
+        use std::cmp::Ordering::*;
+        let Some(ordering) = ordering else {
+            return false;
+        };
+        match self.operator {
+            Operator::Equal | Operator::Contains => ordering == Equal,
+            Operator::NotEqual => ordering != Equal,
+            Operator::Less => ordering == Less,
+            Operator::LessOrEqual => ordering != Greater,
+            Operator::Greater => ordering == Greater,
+            Operator::GreaterOrEqual => ordering != Less,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::DateSource;
+    use std::path::Path;
+
+    #[test]
+    fn parse_filter() {
+        assert!(Filter::parse("camera~=Canon, date>=2006-01-01,iso>800").is_ok());
+        assert!(Filter::parse("").is_err());
+        assert!(Filter::parse("camera").is_err());
+        assert!(Filter::parse("lens=50mm").is_err());
+        assert!(Filter::parse("iso~=800").is_err());
+        assert!(Filter::parse("iso>high").is_err());
+        assert!(Filter::parse("camera>Canon").is_err());
+        assert!(Filter::parse("date<yesterday").is_err());
+        assert!(Filter::parse("iso=>800").is_err());
+    }
+
+    #[test]
+    fn match_filter() {
+        let metadata = Metadata::new(Path::new("tests/all_tags.jpg")).unwrap();
+        let matches = |filter: &str| Filter::parse(filter).unwrap().matches(&metadata);
+
+        assert!(matches("camera~=picasso"));
+        assert!(!matches("camera~=Canon"));
+        assert!(matches("camera!=Canon"));
+        assert!(matches("description=a fun picture!"));
+        assert!(matches("date>=2006-01-01"));
+        assert!(matches("date>=2006-01-01,date<2007-01-01"));
+        assert!(!matches("date>=2006-01-01,date<2006-10-29"));
+        assert!(!matches("rating>0"));
+        assert!(!matches("keyword!=beach"));
+        assert!(matches("width=2048"));
+        assert!(matches("height<=1536"));
+        assert!(!matches("height<1536"));
+
+        // Dimensions not read
+        let metadata =
+            Metadata::new_exif_only(Path::new("tests/all_tags.jpg"), DateSource::default())
+                .unwrap();
+        assert!(!Filter::parse("width>=0").unwrap().matches(&metadata));
+    }
+}
@@ -0,0 +1,313 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use little_exif::{exif_tag::ExifTag, metadata::Metadata as LittleMetadata, rational::uR64};
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::io::Error;
+
+use super::Metadata;
+
+// GPSDateStamp format
+const GPS_DATE_FORMAT: &str = "%Y:%m:%d";
+
+/// Parse decimal degrees coordinates like "43.604,1.444" (latitude, longitude)
+pub fn parse_gps(input: &str) -> Result<(f64, f64), Error> {
+    let invalid = || {
+        Error::other(format!(
+            "Invalid GPS coordinates: '{}'. Expected LAT,LON in decimal degrees.",
+            input
+        ))
+    };
+    let (latitude, longitude) = input.split_once(',').ok_or_else(invalid)?;
+    let latitude: f64 = latitude.trim().parse().map_err(|_| invalid())?;
+    let longitude: f64 = longitude.trim().parse().map_err(|_| invalid())?;
+    check_gps(latitude, longitude).map_err(|_| invalid())?;
+    Ok((latitude, longitude))
+}
+
+/// Check decimal degrees coordinates (latitude, longitude)
+pub fn check_gps(latitude: f64, longitude: f64) -> Result<(), Error> {
+    if !(-90f64..=90f64).contains(&latitude) || !(-180f64..=180f64).contains(&longitude) {
+        return Err(Error::other(format!(
+            "Invalid GPS coordinates: {}, {}.",
+            latitude, longitude
+        )));
+    }
+    Ok(())
+}
+
+pub struct GpsInfo {
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub altitude: Option<f64>,
+    pub direction: Option<f64>,
+    // GPSDateStamp and GPSTimeStamp (UTC)
+    pub timestamp: Option<NaiveDateTime>,
+}
+
+impl GpsInfo {
+    /// Load GPS location from little_exif metadata
+    pub fn from_metadata(litte_metadata: &LittleMetadata) -> GpsInfo {
+        let coordinate = |tag, ref_tag, negative_ref| {
+            let value = Metadata::get_tag_ur64_vec(litte_metadata, &tag)
+                .and_then(|dms| Self::dms_to_f64(&dms))?;
+            // Missing ref: assume North / East
+            match Metadata::get_tag_string(litte_metadata, &ref_tag) {
+                Some(reference) if reference.starts_with(negative_ref) => Some(-value),
+                _ => Some(value),
+            }
+        };
+        let latitude = coordinate(
+            ExifTag::GPSLatitude(Vec::new()),
+            ExifTag::GPSLatitudeRef(String::new()),
+            'S',
+        );
+        let longitude = coordinate(
+            ExifTag::GPSLongitude(Vec::new()),
+            ExifTag::GPSLongitudeRef(String::new()),
+            'W',
+        );
+
+        // Missing ref: assume above sea level
+        let altitude = Metadata::get_tag_ur64(litte_metadata, &ExifTag::GPSAltitude(Vec::new()))
+            .and_then(Self::rational_to_f64)
+            .map(|altitude| {
+                match Metadata::get_tag_u8(litte_metadata, &ExifTag::GPSAltitudeRef(Vec::new())) {
+                    Some(1) => -altitude,
+                    _ => altitude,
+                }
+            });
+
+        let direction =
+            Metadata::get_tag_ur64(litte_metadata, &ExifTag::GPSImgDirection(Vec::new()))
+                .and_then(Self::rational_to_f64);
+
+        let date = Metadata::get_tag_string(litte_metadata, &ExifTag::GPSDateStamp(String::new()))
+            .and_then(|date| NaiveDate::parse_from_str(&date, GPS_DATE_FORMAT).ok());
+        let time = Metadata::get_tag_ur64_vec(litte_metadata, &ExifTag::GPSTimeStamp(Vec::new()))
+            .and_then(|time| Self::rationals_to_time(&time));
+        let timestamp = date.zip(time).map(|(date, time)| date.and_time(time));
+
+        GpsInfo {
+            latitude,
+            longitude,
+            altitude,
+            direction,
+            timestamp,
+        }
+    }
+
+    /// Return true if no GPS data is defined
+    pub fn is_empty(&self) -> bool {
+        self.latitude.is_none()
+            && self.longitude.is_none()
+            && self.altitude.is_none()
+            && self.direction.is_none()
+            && self.timestamp.is_none()
+    }
+
+    /// GPSDateStamp string (like 2006:10:29)
+    pub fn date_stamp(timestamp: NaiveDateTime) -> String {
+        timestamp.format(GPS_DATE_FORMAT).to_string()
+    }
+
+    /// GPSTimeStamp: hours, minutes and seconds
+    pub fn time_stamp(timestamp: NaiveDateTime) -> Vec<uR64> {
+        [timestamp.hour(), timestamp.minute(), timestamp.second()]
+            .into_iter()
+            .map(|value| uR64 {
+                nominator: value,
+                denominator: 1,
+            })
+            .collect()
+    }
+
+    // Convert hours, minutes and seconds (1/1000 s precision) to a time
+    fn rationals_to_time(time: &[uR64]) -> Option<NaiveTime> {
+        if time.len() != 3 {
+            return None;
+        }
+        let mut seconds = 0f64;
+        for (rational, unit) in time.iter().zip([3600f64, 60f64, 1f64]) {
+            seconds += Self::rational_to_f64(*rational)? * unit;
+        }
+        let milliseconds = (seconds * 1000f64).round() as u32;
+        NaiveTime::from_num_seconds_from_midnight_opt(
+            milliseconds / 1000,
+            milliseconds % 1000 * 1_000_000,
+        )
+    }
+
+    /// Convert decimal degrees to degrees, minutes, seconds (1/1000 s precision).
+    /// Sign is ignored (see GPSLatitudeRef and GPSLongitudeRef).
+    pub fn f64_to_dms(value: f64) -> Vec<uR64> {
+        let milliseconds = (value.abs() * 3_600_000f64).round() as u32;
+        vec![
+            uR64 {
+                nominator: milliseconds / 3_600_000,
+                denominator: 1,
+            },
+            uR64 {
+                nominator: milliseconds / 60_000 % 60,
+                denominator: 1,
+            },
+            uR64 {
+                nominator: milliseconds % 60_000,
+                denominator: 1000,
+            },
+        ]
+    }
+
+    fn rational_to_f64(rational: uR64) -> Option<f64> {
+        if rational.denominator == 0 {
+            None
+        } else {
+            Some(rational.into())
+        }
+    }
+
+    // Convert degrees, minutes, seconds to decimal degrees
+    fn dms_to_f64(dms: &[uR64]) -> Option<f64> {
+        let mut value = 0f64;
+        for (rational, unit) in dms.iter().zip([1f64, 60f64, 3600f64]) {
+            value += Self::rational_to_f64(*rational)? / unit;
+        }
+        if dms.is_empty() {
+            None
+        } else {
+            Some(value)
+        }
+    }
+}
+
+impl Display for GpsInfo {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let (Some(latitude), Some(longitude)) = (self.latitude, self.longitude) {
+            parts.push(format!("{:.6}, {:.6}", latitude, longitude));
+        }
+        if let Some(altitude) = self.altitude {
+            parts.push(format!("Altitude: {:.0} m", altitude));
+        }
+        if let Some(direction) = self.direction {
+            parts.push(format!("Direction: {:.0}°", direction));
+        }
+        if let Some(timestamp) = self.timestamp {
+            parts.push(format!(
+                "Time: {} UTC",
+                timestamp.format("%Y:%m:%d %H:%M:%S")
+            ));
+        }
+        if parts.is_empty() {
+            write!(f, "Undefined")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ur64(nominator: u32, denominator: u32) -> uR64 {
+        uR64 {
+            nominator,
+            denominator,
+        }
+    }
+
+    #[test]
+    fn gps_conversions() {
+        assert_eq!(
+            GpsInfo::dms_to_f64(&[ur64(43, 1), ur64(36, 1), ur64(1440, 100)]),
+            Some(43.604)
+        );
+        assert_eq!(GpsInfo::dms_to_f64(&[ur64(43, 1), ur64(36, 0)]), None);
+        assert_eq!(GpsInfo::dms_to_f64(&[]), None);
+        assert_eq!(GpsInfo::rational_to_f64(ur64(4125, 10)), Some(412.5));
+        assert_eq!(GpsInfo::rational_to_f64(ur64(1, 0)), None);
+
+        assert_eq!(
+            GpsInfo::f64_to_dms(-43.604),
+            vec![ur64(43, 1), ur64(36, 1), ur64(14400, 1000)]
+        );
+        assert_eq!(
+            GpsInfo::f64_to_dms(1.9999999),
+            vec![ur64(2, 1), ur64(0, 1), ur64(0, 1000)]
+        );
+    }
+
+    #[test]
+    fn gps_timestamp() {
+        let timestamp = NaiveDate::from_ymd_opt(2006, 10, 29)
+            .unwrap()
+            .and_hms_opt(14, 27, 21)
+            .unwrap();
+        assert_eq!(GpsInfo::date_stamp(timestamp), "2006:10:29");
+        let time = GpsInfo::time_stamp(timestamp);
+        assert_eq!(time, vec![ur64(14, 1), ur64(27, 1), ur64(21, 1)]);
+        assert_eq!(GpsInfo::rationals_to_time(&time), Some(timestamp.time()));
+
+        assert_eq!(
+            GpsInfo::rationals_to_time(&[ur64(14, 1), ur64(27, 1), ur64(2150, 100)]),
+            NaiveTime::from_hms_milli_opt(14, 27, 21, 500)
+        );
+        assert_eq!(
+            GpsInfo::rationals_to_time(&[ur64(24, 1), ur64(0, 1), ur64(0, 1)]),
+            None
+        );
+        assert_eq!(
+            GpsInfo::rationals_to_time(&[ur64(14, 1), ur64(27, 0), ur64(0, 1)]),
+            None
+        );
+        assert_eq!(GpsInfo::rationals_to_time(&[ur64(14, 1)]), None);
+    }
+
+    #[test]
+    fn parse_coordinates() {
+        assert_eq!(parse_gps("43.604,1.444").ok(), Some((43.604, 1.444)));
+        assert_eq!(parse_gps(" -43.6 , -1.4 ").ok(), Some((-43.6, -1.4)));
+        assert!(parse_gps("43.604").is_err());
+        assert!(parse_gps("north,east").is_err());
+        assert!(parse_gps("91,0").is_err());
+        assert!(parse_gps("0,-181").is_err());
+    }
+
+    #[test]
+    fn display_gps() {
+        let gps_info = GpsInfo {
+            latitude: Some(43.604),
+            longitude: Some(-1.444),
+            altitude: Some(412.2),
+            direction: Some(287.0),
+            timestamp: None,
+        };
+        assert_eq!(
+            gps_info.to_string(),
+            "43.604000, -1.444000, Altitude: 412 m, Direction: 287°"
+        );
+        let gps_info = GpsInfo {
+            latitude: None,
+            longitude: None,
+            altitude: Some(-3.0),
+            direction: None,
+            timestamp: NaiveDate::from_ymd_opt(2006, 10, 29)
+                .unwrap()
+                .and_hms_opt(14, 27, 21),
+        };
+        assert_eq!(
+            gps_info.to_string(),
+            "Altitude: -3 m, Time: 2006:10:29 14:27:21 UTC"
+        );
+        let gps_info = GpsInfo {
+            latitude: None,
+            longitude: None,
+            altitude: None,
+            direction: None,
+            timestamp: None,
+        };
+        assert!(gps_info.is_empty());
+        assert_eq!(gps_info.to_string(), "Undefined");
+    }
+}
@@ -0,0 +1,251 @@
+use little_exif::metadata::Metadata as LittleMetadata;
+use std::io::Error;
+
+use super::raw;
+
+/// True for the MIME types of HEIF files (HEIC from iPhones)
+pub fn is_heif(mime: &str) -> bool {
+    matches!(mime, "image/heif" | "image/heic")
+}
+
+/// Read dimensions and EXIF tags of a HEIF file (file data).
+/// Dimensions are the ones of the primary image.
+/// HEIF files are read-only: only the tags used by this tool are loaded (see raw::load_tags()).
+pub fn load(data: &[u8]) -> Result<((u32, u32), LittleMetadata), Error> {
+    let meta = find_box(data, b"meta")?.ok_or_else(|| Error::other("No HEIF meta box."))?;
+    // meta is a full box: skip version and flags
+    let meta = bytes(meta, 4, meta.len().saturating_sub(4))?;
+
+    let primary = match find_box(meta, b"pitm")? {
+        Some(pitm) if pitm.first() == Some(&0) => Some(uint(pitm, 4, 2)?),
+        Some(pitm) => Some(uint(pitm, 4, 4)?),
+        None => None,
+    };
+
+    // Dimensions: ispe property of the primary image (or largest one)
+    let iprp = find_box(meta, b"iprp")?.ok_or_else(|| Error::other("No HEIF properties."))?;
+    let ipco = find_box(iprp, b"ipco")?.ok_or_else(|| Error::other("No HEIF properties."))?;
+    let properties = boxes(ipco)?;
+    let ispe = |property: &(&[u8; 4], &[u8])| -> Option<(u32, u32)> {
+        if property.0 != b"ispe" {
+            return None;
+        }
+        Some((
+            uint(property.1, 4, 4).ok()? as u32,
+            uint(property.1, 8, 4).ok()? as u32,
+        ))
+    };
+    let primary_ispe = match (primary, find_box(iprp, b"ipma")?) {
+        (Some(primary), Some(ipma)) => associations(ipma, primary)?
+            .into_iter()
+            .filter_map(|index| properties.get(index.checked_sub(1)?))
+            .find_map(ispe),
+        _ => None,
+    };
+    let dimentions = primary_ispe
+        .or_else(|| {
+            properties
+                .iter()
+                .filter_map(ispe)
+                .max_by_key(|(width, height)| u64::from(*width) * u64::from(*height))
+        })
+        .ok_or_else(|| Error::other("Cannot read image dimentions."))?;
+
+    // EXIF item: offset of the TIFF header, then EXIF data
+    let exif_item = match find_box(meta, b"iinf")? {
+        Some(iinf) => exif_item_id(iinf)?,
+        None => None,
+    };
+    let litte_metadata = match (exif_item, find_box(meta, b"iloc")?) {
+        (Some(item), Some(iloc)) => {
+            let exif = item_data(data, iloc, item)?;
+            let tiff_offset = uint(exif, 0, 4)? as usize;
+            raw::load_tags(bytes(
+                exif,
+                4 + tiff_offset,
+                exif.len().saturating_sub(4 + tiff_offset),
+            )?)?
+        }
+        _ => LittleMetadata::new(),
+    };
+    Ok((dimentions, litte_metadata))
+}
+
+fn bytes(data: &[u8], offset: usize, length: usize) -> Result<&[u8], Error> {
+    data.get(offset..offset.saturating_add(length))
+        .ok_or_else(|| Error::other("Truncated HEIF file."))
+}
+
+// Big endian unsigned integer of size bytes (0 to 8)
+fn uint(data: &[u8], offset: usize, size: usize) -> Result<u64, Error> {
+    Ok(bytes(data, offset, size)?
+        .iter()
+        .fold(0, |value, byte| (value << 8) | u64::from(*byte)))
+}
+
+// Boxes (type and content) within data
+fn boxes(data: &[u8]) -> Result<Vec<(&[u8; 4], &[u8])>, Error> {
+    let mut result = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let mut size = uint(data, offset, 4)? as usize;
+        let kind: &[u8; 4] = bytes(data, offset + 4, 4)?.try_into().unwrap();
+        let mut header = 8;
+        if size == 1 {
+            size = uint(data, offset + 8, 8)? as usize;
+            header = 16;
+        } else if size == 0 {
+            // Up to the end
+            size = data.len() - offset;
+        }
+        if size < header {
+            return Err(Error::other("Invalid HEIF box size."));
+        }
+        result.push((kind, bytes(data, offset + header, size - header)?));
+        offset += size;
+    }
+    Ok(result)
+}
+
+fn find_box<'a>(data: &'a [u8], kind: &[u8; 4]) -> Result<Option<&'a [u8]>, Error> {
+    Ok(boxes(data)?
+        .into_iter()
+        .find(|(box_kind, _)| *box_kind == kind)
+        .map(|(_, content)| content))
+}
+
+// Property indexes (starting at 1) associated with an item
+fn associations(ipma: &[u8], item: u64) -> Result<Vec<usize>, Error> {
+    let version = uint(ipma, 0, 1)?;
+    let large_index = uint(ipma, 3, 1)? & 1 == 1;
+    let count = uint(ipma, 4, 4)?;
+    let mut offset = 8;
+    for _ in 0..count {
+        let id_size = if version < 1 { 2 } else { 4 };
+        let id = uint(ipma, offset, id_size)?;
+        let association_count = uint(ipma, offset + id_size, 1)? as usize;
+        offset += id_size + 1;
+        let index_size = if large_index { 2 } else { 1 };
+        if id == item {
+            // The highest bit tells if the property is essential
+            return (0..association_count)
+                .map(|index| {
+                    let value = uint(ipma, offset + index * index_size, index_size)?;
+                    Ok((value & ((1 << (index_size * 8 - 1)) - 1)) as usize)
+                })
+                .collect();
+        }
+        offset += association_count * index_size;
+    }
+    Ok(Vec::new())
+}
+
+// ID of the EXIF item (infe boxes of version 2 or 3)
+fn exif_item_id(iinf: &[u8]) -> Result<Option<u64>, Error> {
+    let entries = if uint(iinf, 0, 1)? == 0 { 6 } else { 8 };
+    for (kind, infe) in boxes(bytes(iinf, entries, iinf.len().saturating_sub(entries))?)? {
+        let version = uint(infe, 0, 1)?;
+        if kind != b"infe" || version < 2 {
+            continue;
+        }
+        let id_size = if version == 2 { 2 } else { 4 };
+        if bytes(infe, 4 + id_size + 2, 4)? == b"Exif" {
+            return Ok(Some(uint(infe, 4, id_size)?));
+        }
+    }
+    Ok(None)
+}
+
+// Data of an item stored in the file (construction method 0)
+fn item_data<'a>(data: &'a [u8], iloc: &[u8], item: u64) -> Result<&'a [u8], Error> {
+    let version = uint(iloc, 0, 1)?;
+    let sizes = uint(iloc, 4, 1)?;
+    let (offset_size, length_size) = ((sizes >> 4) as usize, (sizes & 0xf) as usize);
+    let sizes = uint(iloc, 5, 1)?;
+    let base_offset_size = (sizes >> 4) as usize;
+    let index_size = if version >= 1 {
+        (sizes & 0xf) as usize
+    } else {
+        0
+    };
+    let id_size = if version < 2 { 2 } else { 4 };
+    let count = uint(iloc, 6, id_size)?;
+    let mut offset = 6 + id_size;
+    for _ in 0..count {
+        let id = uint(iloc, offset, id_size)?;
+        offset += id_size;
+        let construction_method = if version >= 1 {
+            offset += 2;
+            uint(iloc, offset - 2, 2)? & 0xf
+        } else {
+            0
+        };
+        // Data reference index
+        offset += 2;
+        let base_offset = uint(iloc, offset, base_offset_size)?;
+        offset += base_offset_size;
+        let extent_count = uint(iloc, offset, 2)?;
+        offset += 2;
+        let extent_size = index_size + offset_size + length_size;
+        if id == item {
+            if construction_method != 0 || extent_count != 1 {
+                return Err(Error::other("Unsupported HEIF EXIF location."));
+            }
+            let extent_offset = uint(iloc, offset + index_size, offset_size)?;
+            let extent_length = uint(iloc, offset + index_size + offset_size, length_size)?;
+            // Offsets and length come from the file: check them before slicing
+            let invalid = || Error::other("Invalid HEIF EXIF location.");
+            let start = base_offset
+                .checked_add(extent_offset)
+                .and_then(|start| usize::try_from(start).ok())
+                .ok_or_else(invalid)?;
+            let end = usize::try_from(extent_length)
+                .ok()
+                .and_then(|length| start.checked_add(length))
+                .ok_or_else(invalid)?;
+            return data.get(start..end).ok_or_else(invalid);
+        }
+        offset += extent_count as usize * extent_size;
+    }
+    Err(Error::other("No HEIF EXIF data."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use little_exif::exif_tag::ExifTag;
+    use std::fs;
+
+    #[test]
+    fn load_heic() {
+        let data = fs::read("tests/heic_with_exif.heic").unwrap();
+        let (dimentions, litte_metadata) = load(&data).unwrap();
+        assert_eq!(dimentions, (2048, 1536));
+        assert!(litte_metadata
+            .get_tag(&ExifTag::ImageDescription(String::new()))
+            .next()
+            .is_some());
+
+        assert!(load(&data[..100]).is_err());
+        assert!(load(b"not a heif file").is_err());
+    }
+
+    #[test]
+    fn item_location() {
+        // Version 0, 8 bytes offsets and lengths, item 1: base offset, extent offset, length
+        let iloc = |base_offset: u64, extent_offset: u64, length: u64| {
+            let mut iloc = vec![0, 0, 0, 0, 0x88, 0x80, 0, 1, 0, 1, 0, 0];
+            iloc.extend_from_slice(&base_offset.to_be_bytes());
+            iloc.extend_from_slice(&[0, 1]);
+            iloc.extend_from_slice(&extent_offset.to_be_bytes());
+            iloc.extend_from_slice(&length.to_be_bytes());
+            iloc
+        };
+        let data: Vec<u8> = (0..16).collect();
+        assert_eq!(item_data(&data, &iloc(2, 3, 4), 1).ok(), Some(&data[5..9]));
+        assert!(item_data(&data, &iloc(2, 3, 4), 2).is_err());
+        assert!(item_data(&data, &iloc(8, 4, 5), 1).is_err());
+        assert!(item_data(&data, &iloc(u64::MAX, 1, 1), 1).is_err());
+        assert!(item_data(&data, &iloc(1, 1, u64::MAX), 1).is_err());
+    }
+}
@@ -0,0 +1,342 @@
+use std::io::Error;
+
+// JPEG markers
+const SOI: [u8; 2] = [0xff, 0xd8];
+const SOS: u8 = 0xda;
+const EOI: u8 = 0xd9;
+const APP0: u8 = 0xe0;
+const APP13: u8 = 0xed;
+const APP15: u8 = 0xef;
+// Photoshop image resources (APP13 segment)
+const PHOTOSHOP: &[u8] = b"Photoshop 3.0\0";
+const RESOURCE_TYPE: &[u8] = b"8BIM";
+const IPTC_RESOURCE: u16 = 0x0404;
+// IPTC IIM datasets (record, dataset)
+const TAG_MARKER: u8 = 0x1c;
+const CODED_CHARACTER_SET: (u8, u8) = (1, 90);
+const RECORD_VERSION: (u8, u8) = (2, 0);
+const OBJECT_NAME: (u8, u8) = (2, 5);
+const CAPTION_ABSTRACT: (u8, u8) = (2, 120);
+const UTF8: &[u8] = b"\x1b%G";
+
+/// IPTC title and caption of a JPEG file (IIM datasets in the APP13 Photoshop segment)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Iptc {
+    /// ObjectName
+    pub title: Option<String>,
+    /// Caption-Abstract
+    pub caption: Option<String>,
+}
+
+// IIM dataset: record, dataset number and value
+type DataSet = (u8, u8, Vec<u8>);
+
+// Photoshop image resource: id, name (pascal string with its padding) and data
+struct Resource {
+    id: u16,
+    name: Vec<u8>,
+    data: Vec<u8>,
+}
+
+impl Iptc {
+    /// Read IPTC title and caption of a JPEG file (file data)
+    pub fn from_data(data: &[u8]) -> Result<Iptc, Error> {
+        let datasets = match Self::find_segment(data)? {
+            Some((start, end)) => Self::parse_resources(&data[start + 4..end])
+                .into_iter()
+                .find(|resource| resource.id == IPTC_RESOURCE)
+                .map(|resource| Self::parse_datasets(&resource.data))
+                .unwrap_or_default(),
+            None => Vec::new(),
+        };
+
+        // Without character set, values are UTF-8 or Latin-1
+        let utf8 = datasets.iter().any(|(record, dataset, value)| {
+            (*record, *dataset) == CODED_CHARACTER_SET && value == UTF8
+        });
+        let value = |key: (u8, u8)| {
+            let (_, _, value) = datasets
+                .iter()
+                .find(|(record, dataset, _)| (*record, *dataset) == key)?;
+            let value = match String::from_utf8(value.clone()) {
+                Ok(value) => value,
+                Err(_) if utf8 => String::from_utf8_lossy(value).to_string(),
+                Err(_) => value.iter().map(|c| *c as char).collect(),
+            };
+            let value = value.trim_end_matches('\0').trim();
+            (!value.is_empty()).then(|| value.to_string())
+        };
+        Ok(Iptc {
+            title: value(OBJECT_NAME),
+            caption: value(CAPTION_ABSTRACT),
+        })
+    }
+
+    /// Write IPTC title and caption (as UTF-8) to JPEG file data.
+    /// Other IPTC datasets and Photoshop resources are kept.
+    /// Return the new file data.
+    pub fn update(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let segment = Self::find_segment(data)?;
+        let mut resources = match segment {
+            Some((start, end)) => Self::parse_resources(&data[start + 4..end]),
+            None => Vec::new(),
+        };
+
+        // Update datasets
+        let mut datasets = resources
+            .iter()
+            .find(|resource| resource.id == IPTC_RESOURCE)
+            .map(|resource| Self::parse_datasets(&resource.data))
+            .unwrap_or_default();
+        datasets.retain(|(record, dataset, _)| {
+            ![CODED_CHARACTER_SET, OBJECT_NAME, CAPTION_ABSTRACT].contains(&(*record, *dataset))
+        });
+        if let Some(title) = &self.title {
+            datasets.push((OBJECT_NAME.0, OBJECT_NAME.1, title.as_bytes().to_vec()));
+        }
+        if let Some(caption) = &self.caption {
+            datasets.push((
+                CAPTION_ABSTRACT.0,
+                CAPTION_ABSTRACT.1,
+                caption.as_bytes().to_vec(),
+            ));
+        }
+        let is_empty = datasets
+            .iter()
+            .all(|(record, dataset, _)| (*record, *dataset) == RECORD_VERSION);
+        resources.retain(|resource| resource.id != IPTC_RESOURCE);
+        if !is_empty {
+            datasets.push((CODED_CHARACTER_SET.0, CODED_CHARACTER_SET.1, UTF8.to_vec()));
+            if !datasets
+                .iter()
+                .any(|(record, dataset, _)| (*record, *dataset) == RECORD_VERSION)
+            {
+                datasets.push((RECORD_VERSION.0, RECORD_VERSION.1, vec![0, 4]));
+            }
+            // Datasets are stored in ascending order (stable sort for repeated ones)
+            datasets.sort_by_key(|(record, dataset, _)| (*record, *dataset));
+            resources.push(Resource {
+                id: IPTC_RESOURCE,
+                name: vec![0, 0],
+                data: Self::write_datasets(&datasets)?,
+            });
+        }
+
+        // Replace APP13 segment, or insert it after the other APPn segments
+        let (start, end) = match segment {
+            Some(segment) => segment,
+            None => {
+                let mut offset = SOI.len();
+                for (marker, _, end) in Self::segments(data)? {
+                    if !(APP0..=APP15).contains(&marker) {
+                        break;
+                    }
+                    offset = end;
+                }
+                (offset, offset)
+            }
+        };
+        let mut new_data = data[..start].to_vec();
+        if !resources.is_empty() {
+            let mut payload = PHOTOSHOP.to_vec();
+            for resource in resources.iter() {
+                payload.extend_from_slice(RESOURCE_TYPE);
+                payload.extend_from_slice(&resource.id.to_be_bytes());
+                payload.extend_from_slice(&resource.name);
+                payload.extend_from_slice(&(resource.data.len() as u32).to_be_bytes());
+                payload.extend_from_slice(&resource.data);
+                if resource.data.len() % 2 == 1 {
+                    payload.push(0);
+                }
+            }
+            let Ok(length) = u16::try_from(payload.len() + 2) else {
+                return Err(Error::other("IPTC data is too large."));
+            };
+            new_data.extend_from_slice(&[0xff, APP13]);
+            new_data.extend_from_slice(&length.to_be_bytes());
+            new_data.extend_from_slice(&payload);
+        }
+        new_data.extend_from_slice(&data[end..]);
+        Ok(new_data)
+    }
+
+    // Metadata segments of a JPEG file: marker, start (of marker) and end
+    fn segments(data: &[u8]) -> Result<Vec<(u8, usize, usize)>, Error> {
+        if !data.starts_with(&SOI) {
+            return Err(Error::other("IPTC is only supported for JPEG files."));
+        }
+        let truncated = || Error::other("Truncated JPEG file.");
+        let mut segments = Vec::new();
+        let mut offset = SOI.len();
+        loop {
+            let marker = match data.get(offset..offset + 2) {
+                Some([0xff, marker]) => *marker,
+                _ => return Err(truncated()),
+            };
+            // Image data follows SOS: no more metadata
+            if marker == SOS || marker == EOI {
+                return Ok(segments);
+            }
+            let length = data
+                .get(offset + 2..offset + 4)
+                .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]) as usize)
+                .ok_or_else(truncated)?;
+            let end = offset + 2 + length;
+            if length < 2 || end > data.len() {
+                return Err(truncated());
+            }
+            segments.push((marker, offset, end));
+            offset = end;
+        }
+    }
+
+    // Start and end of the Photoshop APP13 segment
+    fn find_segment(data: &[u8]) -> Result<Option<(usize, usize)>, Error> {
+        Ok(Self::segments(data)?
+            .into_iter()
+            .find(|(marker, start, _)| *marker == APP13 && data[start + 4..].starts_with(PHOTOSHOP))
+            .map(|(_, start, end)| (start, end)))
+    }
+
+    // Photoshop image resources of an APP13 segment. Parsing stops on invalid data.
+    fn parse_resources(payload: &[u8]) -> Vec<Resource> {
+        let mut resources = Vec::new();
+        let mut offset = PHOTOSHOP.len();
+        while payload[offset..].starts_with(RESOURCE_TYPE) {
+            let Some(id) = payload.get(offset + 4..offset + 6) else {
+                break;
+            };
+            let id = u16::from_be_bytes([id[0], id[1]]);
+            // Name is a pascal string, padded to an even size
+            let Some(name_length) = payload.get(offset + 6) else {
+                break;
+            };
+            let name_length = (*name_length as usize + 2) & !1;
+            let name_end = offset + 6 + name_length;
+            let Some(size) = payload.get(name_end..name_end + 4) else {
+                break;
+            };
+            let size = u32::from_be_bytes([size[0], size[1], size[2], size[3]]) as usize;
+            let Some(data) = payload.get(name_end + 4..name_end + 4 + size) else {
+                break;
+            };
+            resources.push(Resource {
+                id,
+                name: payload[offset + 6..name_end].to_vec(),
+                data: data.to_vec(),
+            });
+            offset = name_end + 4 + size + size % 2;
+            if offset > payload.len() {
+                break;
+            }
+        }
+        resources
+    }
+
+    // IIM datasets of the IPTC resource. Parsing stops on invalid data.
+    fn parse_datasets(data: &[u8]) -> Vec<DataSet> {
+        let mut datasets = Vec::new();
+        let mut offset = 0;
+        while let Some([TAG_MARKER, record, dataset, size_1, size_2]) = data.get(offset..offset + 5)
+        {
+            let mut size = u16::from_be_bytes([*size_1, *size_2]) as usize;
+            offset += 5;
+            // Extended dataset: the size is stored in the next (size & 0x7fff) bytes
+            if size & 0x8000 != 0 {
+                let length = size & 0x7fff;
+                if length > std::mem::size_of::<usize>() {
+                    break;
+                }
+                let Some(bytes) = data.get(offset..offset + length) else {
+                    break;
+                };
+                offset += length;
+                size = bytes
+                    .iter()
+                    .fold(0, |size, byte| (size << 8) | *byte as usize);
+            }
+            // Sizes come from the file: check them before slicing
+            let Some(value) = offset
+                .checked_add(size)
+                .and_then(|end| data.get(offset..end))
+            else {
+                break;
+            };
+            datasets.push((*record, *dataset, value.to_vec()));
+            offset += size;
+        }
+        datasets
+    }
+
+    fn write_datasets(datasets: &[DataSet]) -> Result<Vec<u8>, Error> {
+        let mut data = Vec::new();
+        for (record, dataset, value) in datasets {
+            let size = match u16::try_from(value.len()) {
+                Ok(size) if size < 0x8000 => size,
+                _ => return Err(Error::other("IPTC value is too long.")),
+            };
+            data.extend_from_slice(&[TAG_MARKER, *record, *dataset]);
+            data.extend_from_slice(&size.to_be_bytes());
+            data.extend_from_slice(value);
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn iptc_round_trip() {
+        let original = fs::read("tests/all_tags.jpg").unwrap();
+        assert_eq!(Iptc::from_data(&original).ok(), Some(Iptc::default()));
+        assert!(Iptc::from_data(&fs::read("tests/all_tags.png").unwrap()).is_err());
+
+        let iptc = Iptc {
+            title: Some("Capitole".to_string()),
+            caption: Some("Été à Toulouse".to_string()),
+        };
+        let data = iptc.update(&original).unwrap();
+        assert_eq!(Iptc::from_data(&data).ok(), Some(iptc));
+        assert!(image::load_from_memory(&data).is_ok());
+
+        // Character set and record version come first
+        let (start, end) = Iptc::find_segment(&data).unwrap().unwrap();
+        let datasets = Iptc::parse_datasets(&Iptc::parse_resources(&data[start + 4..end])[0].data);
+        assert_eq!(datasets[0], (1, 90, UTF8.to_vec()));
+        assert_eq!(datasets[1], (2, 0, vec![0, 4]));
+        let iptc = Iptc {
+            title: None,
+            caption: Some("Summer".to_string()),
+        };
+        let data = iptc.update(&data).unwrap();
+        assert_eq!(Iptc::from_data(&data).ok(), Some(iptc));
+
+        // Remove segment once empty
+        let data = Iptc::default().update(&data).unwrap();
+        assert_eq!(Iptc::find_segment(&data).ok(), Some(None));
+        assert_eq!(data, original);
+    }
+
+    #[test]
+    fn parse_datasets() {
+        let datasets =
+            Iptc::parse_datasets(b"\x1c\x02\x05\x00\x03abc\x1c\x02\x78\x80\x02\x00\x01d\x1c");
+        assert_eq!(
+            datasets,
+            vec![(2, 5, b"abc".to_vec()), (2, 120, b"d".to_vec())]
+        );
+        assert!(Iptc::write_datasets(&[(2, 120, vec![0; 0x8000])]).is_err());
+        // Corrupted sizes stop parsing
+        let datasets = Iptc::parse_datasets(
+            b"\x1c\x02\x05\x00\x01a\x1c\x02\x78\x80\x08\xff\xff\xff\xff\xff\xff\xff\xffd",
+        );
+        assert_eq!(datasets, vec![(2, 5, b"a".to_vec())]);
+        assert!(
+            Iptc::parse_datasets(b"\x1c\x02\x78\x80\x09\x01\x00\x00\x00\x00\x00\x00\x00\x00")
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufReader, Error};
+use std::path::{Path, PathBuf};
+
+use super::{gps_info, CommentEncoding, Metadata};
+
+/// Tag values to apply to an image, read from a JSON object of info --format json.
+/// Absent fields are left untouched, null fields are cleared.
+/// Other fields (like width or camera) are ignored.
+#[derive(Deserialize, Debug, Default, PartialEq)]
+pub struct JsonPatch {
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub description: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub date: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub comment: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub title: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub caption: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub keywords: Option<Option<Vec<String>>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub rating: Option<Option<u16>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub gps: Option<Option<GpsPatch>>,
+}
+
+/// GPS location of info --format json. Only coordinates are applied.
+#[derive(Deserialize, Debug, Default, PartialEq)]
+pub struct GpsPatch {
+    #[serde(default)]
+    pub latitude: Option<f64>,
+    #[serde(default)]
+    pub longitude: Option<f64>,
+}
+
+// A present field is Some, even if null: Some(None)
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+impl JsonPatch {
+    /// Apply tag values to metadata. Unchanged values are not rewritten.
+    /// Note: file will not be modified unless you call save().
+    pub fn apply(&self, metadata: &mut Metadata) -> Result<(), Error> {
+        match &self.description {
+            Some(Some(description)) => metadata.set_description(description),
+            Some(None) => metadata.clear_description(),
+            None => (),
+        }
+        match &self.date {
+            Some(Some(date)) if metadata.exif_date().as_ref() != Some(date) => {
+                metadata.set_date_flexible(date)?
+            }
+            Some(None) => metadata.clear_date(),
+            _ => (),
+        }
+        match &self.comment {
+            Some(Some(comment)) if metadata.user_comment().as_ref() != Some(comment) => {
+                metadata.set_user_comment(comment, CommentEncoding::Unicode)?
+            }
+            Some(None) => metadata.clear_user_comment(),
+            _ => (),
+        }
+        match &self.title {
+            Some(Some(title)) => metadata.set_title(title)?,
+            Some(None) => metadata.clear_title(),
+            None => (),
+        }
+        match &self.caption {
+            Some(Some(caption)) => metadata.set_caption(caption)?,
+            Some(None) => metadata.clear_caption(),
+            None => (),
+        }
+        match &self.keywords {
+            Some(Some(keywords)) => metadata.set_keywords(keywords),
+            Some(None) => metadata.set_keywords(&[]),
+            None => (),
+        }
+        match self.rating {
+            Some(Some(rating)) => metadata.set_rating(rating)?,
+            Some(None) => metadata.clear_rating(),
+            None => (),
+        }
+        match &self.gps {
+            Some(Some(GpsPatch {
+                latitude: Some(latitude),
+                longitude: Some(longitude),
+            })) => {
+                gps_info::check_gps(*latitude, *longitude)?;
+                let gps_info = metadata.gps_info();
+                let same = |value: Option<f64>, expected: f64| {
+                    value.is_some_and(|value| (value - expected).abs() < 1e-6)
+                };
+                if !(same(gps_info.latitude, *latitude) && same(gps_info.longitude, *longitude)) {
+                    metadata.set_gps(*latitude, *longitude);
+                }
+            }
+            // No coordinates (like altitude only): nothing to apply
+            Some(Some(GpsPatch {
+                latitude: None,
+                longitude: None,
+            })) => (),
+            Some(Some(_)) => {
+                return Err(Error::other(
+                    "GPS latitude and longitude must be both defined.",
+                ))
+            }
+            Some(None) => metadata.clear_gps(),
+            None => (),
+        }
+        Ok(())
+    }
+}
+
+/// Content of a JSON file: one object applied to all images,
+/// or an array of objects applied to the images of the same path (or file name).
+pub struct JsonPatches {
+    patches: Vec<JsonPatch>,
+    single: bool,
+}
+
+impl JsonPatches {
+    pub fn load(path: &Path) -> Result<JsonPatches, Error> {
+        let value: Value = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        Ok(match value {
+            Value::Array(values) => JsonPatches {
+                patches: values
+                    .into_iter()
+                    .map(serde_json::from_value)
+                    .collect::<Result<_, _>>()?,
+                single: false,
+            },
+            value => JsonPatches {
+                patches: vec![serde_json::from_value(value)?],
+                single: true,
+            },
+        })
+    }
+
+    /// True if patches hold the values of each image (JSON array)
+    pub fn per_image(&self) -> bool {
+        !self.single
+    }
+
+    /// Patch to apply to an image, if any
+    pub fn find(&self, path: &Path) -> Option<&JsonPatch> {
+        if self.single {
+            return self.patches.first();
+        }
+        self.patches
+            .iter()
+            .find(|patch| patch.file.as_deref() == Some(path))
+            .or_else(|| {
+                self.patches.iter().find(|patch| {
+                    patch.file.as_ref().and_then(|file| file.file_name()) == path.file_name()
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enumset::enum_set;
+    use std::fs;
+
+    use crate::metadata::tag::Tag;
+
+    #[test]
+    fn nullable_fields() {
+        let patch: JsonPatch =
+            serde_json::from_str(r#"{"description": "Capitole", "rating": null, "width": 10}"#)
+                .unwrap();
+        assert_eq!(
+            patch,
+            JsonPatch {
+                description: Some(Some("Capitole".to_string())),
+                rating: Some(None),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn apply_patch() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path).is_ok());
+        let json_path = tmpdir.path().join("patch.json");
+
+        // Unchanged values are not written
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        let mut value = crate::info_to_json(&metadata);
+        value["description"] = "Capitole".into();
+        value["rating"] = Value::Null;
+        assert!(fs::write(&json_path, Value::from(vec![value]).to_string()).is_ok());
+
+        let patches = JsonPatches::load(&json_path).unwrap();
+        assert!(patches.per_image());
+        assert!(patches.find(Path::new("other.jpg")).is_none());
+        let patch = patches.find(&tmp_file_path).unwrap();
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        let rating = metadata.rating();
+        assert!(patch.apply(&mut metadata).is_ok());
+        let expected = match rating {
+            Some(_) => enum_set!(Tag::Description | Tag::Rating),
+            None => enum_set!(Tag::Description),
+        };
+        assert_eq!(metadata.save().ok(), Some(expected));
+
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert_eq!(metadata.description(), Some("Capitole".to_string()));
+        assert_eq!(metadata.rating(), None);
+
+        // A single object applies to all images
+        assert!(fs::write(&json_path, r#"{"keywords": ["a", "b"]}"#).is_ok());
+        let patches = JsonPatches::load(&json_path).unwrap();
+        assert!(!patches.per_image());
+        assert!(patches.find(Path::new("other.jpg")).is_some());
+
+        assert!(fs::write(&json_path, r#"{"rating": "high"}"#).is_ok());
+        assert!(JsonPatches::load(&json_path).is_err());
+    }
+
+    #[test]
+    fn apply_gps() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp_file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path).is_ok());
+        let parse = |json| serde_json::from_str::<JsonPatch>(json).unwrap();
+
+        // all_tags.jpg has no GPS data
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(parse(r#"{"gps": {"altitude": 150}}"#)
+            .apply(&mut metadata)
+            .is_ok());
+        assert!(parse(r#"{"gps": {"latitude": 43.6}}"#)
+            .apply(&mut metadata)
+            .is_err());
+        assert!(parse(r#"{"gps": {"latitude": 95, "longitude": 1.44}}"#)
+            .apply(&mut metadata)
+            .is_err());
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+
+        // Values written by info are applied back
+        metadata.set_gps(43.604, 1.444);
+        let value = crate::info_to_json(&metadata);
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        let patch: JsonPatch = serde_json::from_value(value).unwrap();
+        assert!(patch.apply(&mut metadata).is_ok());
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Gps)));
+        let metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!((metadata.gps_info().latitude.unwrap() - 43.604).abs() < 1e-6);
+
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(patch.apply(&mut metadata).is_ok());
+        assert_eq!(metadata.save().ok(), Some(enum_set!()));
+        let mut metadata = Metadata::new(&tmp_file_path).unwrap();
+        assert!(parse(r#"{"gps": null}"#).apply(&mut metadata).is_ok());
+        assert_eq!(metadata.save().ok(), Some(enum_set!(Tag::Gps)));
+    }
+}
@@ -0,0 +1,284 @@
+use little_exif::{
+    exif_tag::ExifTag,
+    rational::{iR64, uR64},
+};
+use std::io::Error;
+use std::str::FromStr;
+
+// Build a tag from its typed value
+#[derive(Clone, Copy)]
+enum Builder {
+    Text(fn(String) -> ExifTag),
+    U8(fn(Vec<u8>) -> ExifTag),
+    U16(fn(Vec<u16>) -> ExifTag),
+    U32(fn(Vec<u32>) -> ExifTag),
+    URational(fn(Vec<uR64>) -> ExifTag),
+    IRational(fn(Vec<iR64>) -> ExifTag),
+}
+
+// Common tags that can be read and written by name (see --tag)
+const TAGS: [(&str, Builder); 51] = [
+    ("ImageDescription", Builder::Text(ExifTag::ImageDescription)),
+    ("Make", Builder::Text(ExifTag::Make)),
+    ("Model", Builder::Text(ExifTag::Model)),
+    ("Software", Builder::Text(ExifTag::Software)),
+    ("Artist", Builder::Text(ExifTag::Artist)),
+    ("Copyright", Builder::Text(ExifTag::Copyright)),
+    ("ModifyDate", Builder::Text(ExifTag::ModifyDate)),
+    ("DateTimeOriginal", Builder::Text(ExifTag::DateTimeOriginal)),
+    ("CreateDate", Builder::Text(ExifTag::CreateDate)),
+    ("OffsetTime", Builder::Text(ExifTag::OffsetTime)),
+    (
+        "OffsetTimeOriginal",
+        Builder::Text(ExifTag::OffsetTimeOriginal),
+    ),
+    (
+        "OffsetTimeDigitized",
+        Builder::Text(ExifTag::OffsetTimeDigitized),
+    ),
+    ("ImageUniqueID", Builder::Text(ExifTag::ImageUniqueID)),
+    ("OwnerName", Builder::Text(ExifTag::OwnerName)),
+    ("SerialNumber", Builder::Text(ExifTag::SerialNumber)),
+    ("LensMake", Builder::Text(ExifTag::LensMake)),
+    ("LensModel", Builder::Text(ExifTag::LensModel)),
+    ("LensSerialNumber", Builder::Text(ExifTag::LensSerialNumber)),
+    ("Orientation", Builder::U16(ExifTag::Orientation)),
+    ("ResolutionUnit", Builder::U16(ExifTag::ResolutionUnit)),
+    ("Rating", Builder::U16(ExifTag::Rating)),
+    ("RatingPercent", Builder::U16(ExifTag::RatingPercent)),
+    ("ExposureProgram", Builder::U16(ExifTag::ExposureProgram)),
+    ("ISO", Builder::U16(ExifTag::ISO)),
+    ("MeteringMode", Builder::U16(ExifTag::MeteringMode)),
+    ("Flash", Builder::U16(ExifTag::Flash)),
+    ("ColorSpace", Builder::U16(ExifTag::ColorSpace)),
+    ("WhiteBalance", Builder::U16(ExifTag::WhiteBalance)),
+    (
+        "FocalLengthIn35mmFormat",
+        Builder::U16(ExifTag::FocalLengthIn35mmFormat),
+    ),
+    ("SceneCaptureType", Builder::U16(ExifTag::SceneCaptureType)),
+    ("ExifImageWidth", Builder::U32(ExifTag::ExifImageWidth)),
+    ("ExifImageHeight", Builder::U32(ExifTag::ExifImageHeight)),
+    ("XResolution", Builder::URational(ExifTag::XResolution)),
+    ("YResolution", Builder::URational(ExifTag::YResolution)),
+    ("ExposureTime", Builder::URational(ExifTag::ExposureTime)),
+    ("FNumber", Builder::URational(ExifTag::FNumber)),
+    ("ApertureValue", Builder::URational(ExifTag::ApertureValue)),
+    ("FocalLength", Builder::URational(ExifTag::FocalLength)),
+    (
+        "ShutterSpeedValue",
+        Builder::IRational(ExifTag::ShutterSpeedValue),
+    ),
+    (
+        "ExposureCompensation",
+        Builder::IRational(ExifTag::ExposureCompensation),
+    ),
+    ("GPSVersionID", Builder::U8(ExifTag::GPSVersionID)),
+    ("GPSLatitudeRef", Builder::Text(ExifTag::GPSLatitudeRef)),
+    ("GPSLatitude", Builder::URational(ExifTag::GPSLatitude)),
+    ("GPSLongitudeRef", Builder::Text(ExifTag::GPSLongitudeRef)),
+    ("GPSLongitude", Builder::URational(ExifTag::GPSLongitude)),
+    ("GPSAltitudeRef", Builder::U8(ExifTag::GPSAltitudeRef)),
+    ("GPSAltitude", Builder::URational(ExifTag::GPSAltitude)),
+    ("GPSTimeStamp", Builder::URational(ExifTag::GPSTimeStamp)),
+    ("GPSDateStamp", Builder::Text(ExifTag::GPSDateStamp)),
+    (
+        "GPSImgDirectionRef",
+        Builder::Text(ExifTag::GPSImgDirectionRef),
+    ),
+    (
+        "GPSImgDirection",
+        Builder::URational(ExifTag::GPSImgDirection),
+    ),
+];
+
+/// Names of the tags known by lookup() and parse()
+pub fn names() -> impl Iterator<Item = &'static str> {
+    TAGS.iter().map(|(name, _)| *name)
+}
+
+// Find a tag by name (case insensitive)
+fn builder(name: &str) -> Result<Builder, Error> {
+    TAGS.iter()
+        .find(|(tag_name, _)| tag_name.eq_ignore_ascii_case(name.trim()))
+        .map(|(_, builder)| *builder)
+        .ok_or_else(|| {
+            Error::other(format!(
+                "Unknown tag: '{}'. Known tags are {}.",
+                name,
+                names().collect::<Vec<_>>().join(", ")
+            ))
+        })
+}
+
+/// Tag with this name and an empty value (to read it)
+pub fn lookup(name: &str) -> Result<ExifTag, Error> {
+    Ok(match builder(name)? {
+        Builder::Text(build) => build(String::new()),
+        Builder::U8(build) => build(Vec::new()),
+        Builder::U16(build) => build(Vec::new()),
+        Builder::U32(build) => build(Vec::new()),
+        Builder::URational(build) => build(Vec::new()),
+        Builder::IRational(build) => build(Vec::new()),
+    })
+}
+
+/// Tag with this name and value. The value is parsed according to the tag type:
+/// text, integers or rationals (like 1/250 or 5.6). Lists are comma separated.
+pub fn parse(name: &str, value: &str) -> Result<ExifTag, Error> {
+    let invalid = |expected: &str| {
+        Error::other(format!(
+            "Invalid value for {}: '{}'. Expected {}.",
+            name, value, expected
+        ))
+    };
+    Ok(match builder(name)? {
+        Builder::Text(build) => build(value.to_string()),
+        Builder::U8(build) => build(parse_list(value).map_err(|_| invalid("integers"))?),
+        Builder::U16(build) => build(parse_list(value).map_err(|_| invalid("integers"))?),
+        Builder::U32(build) => build(parse_list(value).map_err(|_| invalid("integers"))?),
+        Builder::URational(build) => build(
+            parse_list::<Rational>(value)
+                .ok()
+                .and_then(|values| values.iter().map(Rational::to_ur64).collect())
+                .ok_or_else(|| invalid("positive rationals (like 1/250 or 5.6)"))?,
+        ),
+        Builder::IRational(build) => build(
+            parse_list::<Rational>(value)
+                .ok()
+                .and_then(|values| values.iter().map(Rational::to_ir64).collect())
+                .ok_or_else(|| invalid("rationals (like -1/3 or 0.7)"))?,
+        ),
+    })
+}
+
+/// Parse NAME=VALUE
+pub fn parse_assignment(input: &str) -> Result<ExifTag, Error> {
+    let (name, value) = input
+        .split_once('=')
+        .ok_or_else(|| Error::other(format!("Invalid tag: '{}'. Expected NAME=VALUE.", input)))?;
+    parse(name, value)
+}
+
+// Parse a comma separated list
+fn parse_list<T: FromStr>(input: &str) -> Result<Vec<T>, T::Err> {
+    input.split(',').map(|value| value.trim().parse()).collect()
+}
+
+// A rational as written by the user: n/d or decimal (3 digits kept)
+struct Rational {
+    nominator: i64,
+    denominator: i64,
+}
+
+impl FromStr for Rational {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if let Some((nominator, denominator)) = input.split_once('/') {
+            let nominator = nominator.trim().parse().map_err(|_| ())?;
+            let denominator = denominator.trim().parse().map_err(|_| ())?;
+            if denominator == 0 {
+                return Err(());
+            }
+            return Ok(Rational {
+                nominator,
+                denominator,
+            });
+        }
+        if let Ok(nominator) = input.parse() {
+            return Ok(Rational {
+                nominator,
+                denominator: 1,
+            });
+        }
+        let value: f64 = input.parse().map_err(|_| ())?;
+        if !value.is_finite() {
+            return Err(());
+        }
+        Ok(Rational {
+            nominator: (value * 1000.0).round() as i64,
+            denominator: 1000,
+        })
+    }
+}
+
+impl Rational {
+    fn to_ur64(&self) -> Option<uR64> {
+        Some(uR64 {
+            nominator: self.nominator.try_into().ok()?,
+            denominator: self.denominator.try_into().ok()?,
+        })
+    }
+    fn to_ir64(&self) -> Option<iR64> {
+        Some(iR64 {
+            nominator: self.nominator.try_into().ok()?,
+            denominator: self.denominator.try_into().ok()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_by_name() {
+        assert_eq!(
+            lookup("artist").ok().map(|tag| tag.as_u16()),
+            Some(ExifTag::Artist(String::new()).as_u16())
+        );
+        assert!(lookup("NotATag").is_err());
+        assert_eq!(names().count(), TAGS.len());
+    }
+
+    #[test]
+    fn parse_values() {
+        let tag = parse_assignment("Artist=John Doe").unwrap();
+        assert_eq!(format!("{:?}", tag), "Artist(\"John Doe\")");
+        let tag = parse("ISO", "800").unwrap();
+        assert_eq!(format!("{:?}", tag), "ISO([800])");
+        let tag = parse("ExposureTime", "1/250").unwrap();
+        assert_eq!(
+            format!("{:?}", tag),
+            format!(
+                "{:?}",
+                ExifTag::ExposureTime(vec![uR64 {
+                    nominator: 1,
+                    denominator: 250
+                }])
+            )
+        );
+        let tag = parse("FNumber", "5.6").unwrap();
+        assert_eq!(
+            format!("{:?}", tag),
+            format!(
+                "{:?}",
+                ExifTag::FNumber(vec![uR64 {
+                    nominator: 5600,
+                    denominator: 1000
+                }])
+            )
+        );
+        let tag = parse("ExposureCompensation", "-1/3").unwrap();
+        assert_eq!(
+            format!("{:?}", tag),
+            format!(
+                "{:?}",
+                ExifTag::ExposureCompensation(vec![iR64 {
+                    nominator: -1,
+                    denominator: 3
+                }])
+            )
+        );
+
+        let tag = parse("GPSVersionID", "2, 3, 0, 0").unwrap();
+        assert_eq!(format!("{:?}", tag), "GPSVersionID([2, 3, 0, 0])");
+
+        assert!(parse("ISO", "high").is_err());
+        assert!(parse("ISO", "-100").is_err());
+        assert!(parse("FNumber", "-5.6").is_err());
+        assert!(parse("FNumber", "1/0").is_err());
+        assert!(parse_assignment("Artist").is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use std::io::Error;
+
+const SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+// Text chunk keywords used by ImageMagick/exiftool to store EXIF data
+// before the eXIf chunk was standardized
+const EXIF_PROFILES: [&[u8]; 2] = [b"Raw profile type exif", b"Raw profile type APP1"];
+
+/// Remove all EXIF data of PNG file data: eXIf chunks and EXIF raw profile text chunks.
+/// EXIF data is then written as a single eXIf chunk, that is read first by all tools.
+pub fn remove_exif(data: &[u8]) -> Result<Vec<u8>, Error> {
+    if !data.starts_with(SIGNATURE) {
+        return Err(Error::other("Not a PNG file."));
+    }
+    let mut output = SIGNATURE.to_vec();
+    let mut offset = SIGNATURE.len();
+    while offset < data.len() {
+        // Chunk: length (4 bytes), type (4 bytes), data and CRC (4 bytes)
+        let length = data
+            .get(offset..offset + 4)
+            .map(|length| u32::from_be_bytes(length.try_into().unwrap()) as usize)
+            .ok_or_else(|| Error::other("Truncated PNG chunk."))?;
+        let end = offset + 12 + length;
+        let chunk = data
+            .get(offset..end)
+            .ok_or_else(|| Error::other("Truncated PNG chunk."))?;
+        if !is_exif(&chunk[4..8], &chunk[8..8 + length]) {
+            output.extend_from_slice(chunk);
+        }
+        offset = end;
+    }
+    Ok(output)
+}
+
+fn is_exif(chunk_type: &[u8], chunk_data: &[u8]) -> bool {
+    match chunk_type {
+        b"eXIf" => true,
+        b"tEXt" | b"zTXt" | b"iTXt" => {
+            let keyword = chunk_data.split(|b| *b == 0).next().unwrap_or_default();
+            EXIF_PROFILES.contains(&keyword)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn remove_exif_chunks() {
+        let data = fs::read("tests/png_with_exif.png").unwrap();
+        let cleaned = remove_exif(&data).unwrap();
+        assert!(cleaned.len() < data.len());
+        assert!(!cleaned.windows(4).any(|window| window == b"eXIf"));
+        assert!(image::load_from_memory(&cleaned).is_ok());
+
+        // Other text chunks are kept
+        let data = fs::read("tests/no_exif.png").unwrap();
+        assert_eq!(remove_exif(&data).ok(), Some(data));
+
+        assert!(remove_exif(b"not a png").is_err());
+        assert!(remove_exif(&fs::read("tests/png_with_exif.png").unwrap()[..40]).is_err());
+    }
+}
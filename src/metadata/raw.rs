@@ -0,0 +1,318 @@
+use little_exif::{
+    exif_tag::ExifTag,
+    metadata::Metadata as LittleMetadata,
+    rational::{iR64, uR64},
+};
+use std::io::Error;
+use std::path::Path;
+
+// TIFF tags
+const IMAGE_WIDTH: u16 = 0x0100;
+const IMAGE_HEIGHT: u16 = 0x0101;
+const COMPRESSION: u16 = 0x0103;
+const IMAGE_DESCRIPTION: u16 = 0x010e;
+const MAKE: u16 = 0x010f;
+const MODEL: u16 = 0x0110;
+const SOFTWARE: u16 = 0x0131;
+const SUB_IFDS: u16 = 0x014a;
+const EXIF_IFD: u16 = 0x8769;
+// EXIF tags
+const EXPOSURE_TIME: u16 = 0x829a;
+const F_NUMBER: u16 = 0x829d;
+const ISO: u16 = 0x8827;
+const DATE_TIME_ORIGINAL: u16 = 0x9003;
+const CREATE_DATE: u16 = 0x9004;
+const OFFSET_TIME_ORIGINAL: u16 = 0x9011;
+const EXPOSURE_COMPENSATION: u16 = 0x9204;
+const FLASH: u16 = 0x9209;
+const FOCAL_LENGTH: u16 = 0x920a;
+const EXIF_IMAGE_WIDTH: u16 = 0xa002;
+const EXIF_IMAGE_HEIGHT: u16 = 0xa003;
+
+/// MIME type of the supported RAW files (TIFF based), from file extention
+pub fn mime_from_extention(path: &Path) -> Option<&'static str> {
+    let extention = path.extension()?.to_str()?.to_lowercase();
+    match extention.as_str() {
+        "cr2" => Some("image/x-canon-cr2"),
+        "nef" => Some("image/x-nikon-nef"),
+        "arw" => Some("image/x-sony-arw"),
+        "dng" => Some("image/x-adobe-dng"),
+        _ => None,
+    }
+}
+
+/// Read dimensions and EXIF tags of a RAW file (file data).
+/// Dimensions are the ExifImageWidth/Height tags or the ones of the largest image.
+/// Only the tags used by this tool are loaded.
+pub fn load(data: &[u8]) -> Result<((u32, u32), LittleMetadata), Error> {
+    let tiff = Tiff::new(data)?;
+
+    // IFD0, next IFDs and sub IFDs hold the images (thumbnails, previews and raw data)
+    let mut ifds = Vec::new();
+    let mut offset = tiff.u32(4)?;
+    while offset != 0 && ifds.len() < 8 {
+        let (entries, next) = tiff.ifd(offset)?;
+        ifds.push(entries);
+        offset = next;
+    }
+    let ifd0 = ifds.first().cloned().unwrap_or_default();
+    for offset in tiff.u32_values(&ifd0, SUB_IFDS) {
+        ifds.push(tiff.ifd(offset)?.0);
+    }
+    let exif = match tiff.u32_values(&ifd0, EXIF_IFD).first() {
+        Some(offset) => tiff.ifd(*offset)?.0,
+        None => Vec::new(),
+    };
+
+    let dimentions = match (
+        tiff.u32_values(&exif, EXIF_IMAGE_WIDTH).first(),
+        tiff.u32_values(&exif, EXIF_IMAGE_HEIGHT).first(),
+    ) {
+        (Some(width), Some(height)) => (*width as u32, *height as u32),
+        _ => ifds
+            .iter()
+            .filter_map(|ifd| {
+                Some((
+                    *tiff.u32_values(ifd, IMAGE_WIDTH).first()? as u32,
+                    *tiff.u32_values(ifd, IMAGE_HEIGHT).first()? as u32,
+                ))
+            })
+            .max_by_key(|(width, height)| u64::from(*width) * u64::from(*height))
+            .ok_or_else(|| Error::other("Cannot read image dimentions."))?,
+    };
+
+    Ok((dimentions, read_tags(&tiff, &ifd0, &exif)))
+}
+
+/// Read EXIF tags of TIFF data, like the EXIF block of other files (see load())
+pub fn load_tags(data: &[u8]) -> Result<LittleMetadata, Error> {
+    let tiff = Tiff::new(data)?;
+    let (ifd0, _) = tiff.ifd(tiff.u32(4)?)?;
+    let exif = match tiff.u32_values(&ifd0, EXIF_IFD).first() {
+        Some(offset) => tiff.ifd(*offset)?.0,
+        None => Vec::new(),
+    };
+    Ok(read_tags(&tiff, &ifd0, &exif))
+}
+
+/// Compression of the first image of TIFF data (1: uncompressed)
+pub fn compression(data: &[u8]) -> Option<u16> {
+    let tiff = Tiff::new(data).ok()?;
+    let (ifd0, _) = tiff.ifd(tiff.u32(4).ok()?).ok()?;
+    let compression = *tiff.u32_values(&ifd0, COMPRESSION).first()?;
+    u16::try_from(compression).ok()
+}
+
+// Tags used by this tool
+fn read_tags(tiff: &Tiff, ifd0: &[Entry], exif: &[Entry]) -> LittleMetadata {
+    let mut litte_metadata = LittleMetadata::new();
+
+    // IFD0 tags
+    for (tag, exif_tag) in [
+        (
+            IMAGE_DESCRIPTION,
+            ExifTag::ImageDescription as fn(String) -> ExifTag,
+        ),
+        (MAKE, ExifTag::Make),
+        (MODEL, ExifTag::Model),
+        (SOFTWARE, ExifTag::Software),
+    ] {
+        if let Some(value) = tiff.string(ifd0, tag) {
+            litte_metadata.set_tag(exif_tag(value));
+        }
+    }
+
+    // EXIF tags
+    for (tag, exif_tag) in [
+        (
+            DATE_TIME_ORIGINAL,
+            ExifTag::DateTimeOriginal as fn(String) -> ExifTag,
+        ),
+        (CREATE_DATE, ExifTag::CreateDate),
+        (OFFSET_TIME_ORIGINAL, ExifTag::OffsetTimeOriginal),
+    ] {
+        if let Some(value) = tiff.string(exif, tag) {
+            litte_metadata.set_tag(exif_tag(value));
+        }
+    }
+    for (tag, exif_tag) in [
+        (
+            EXPOSURE_TIME,
+            ExifTag::ExposureTime as fn(Vec<uR64>) -> ExifTag,
+        ),
+        (F_NUMBER, ExifTag::FNumber),
+        (FOCAL_LENGTH, ExifTag::FocalLength),
+    ] {
+        if let Some(value) = tiff.rational(exif, tag) {
+            litte_metadata.set_tag(exif_tag(vec![value]));
+        }
+    }
+    if let Some(value) = tiff.rational(exif, EXPOSURE_COMPENSATION) {
+        litte_metadata.set_tag(ExifTag::ExposureCompensation(vec![iR64 {
+            nominator: value.nominator as i32,
+            denominator: value.denominator as i32,
+        }]));
+    }
+    for (tag, exif_tag) in [
+        (ISO, ExifTag::ISO as fn(Vec<u16>) -> ExifTag),
+        (FLASH, ExifTag::Flash),
+    ] {
+        if let Some(value) = tiff.u32_values(exif, tag).first() {
+            litte_metadata.set_tag(exif_tag(vec![*value as u16]));
+        }
+    }
+
+    litte_metadata
+}
+
+// IFD entry: tag, type, count and value (or offset of value)
+#[derive(Clone, Copy)]
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: u32,
+    value: usize,
+}
+
+// Minimal TIFF reader
+struct Tiff<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl Tiff<'_> {
+    fn new(data: &[u8]) -> Result<Tiff<'_>, Error> {
+        let big_endian = match data.get(..4) {
+            Some([b'I', b'I', 42, 0]) => false,
+            Some([b'M', b'M', 0, 42]) => true,
+            _ => return Err(Error::other("Unsupported RAW file (not TIFF based).")),
+        };
+        Ok(Tiff { data, big_endian })
+    }
+
+    fn bytes(&self, offset: usize, length: usize) -> Result<&[u8], Error> {
+        self.data
+            .get(offset..offset.saturating_add(length))
+            .ok_or_else(|| Error::other("Truncated RAW file."))
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16, Error> {
+        let bytes: [u8; 2] = self.bytes(offset, 2)?.try_into().unwrap();
+        Ok(match self.big_endian {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Result<usize, Error> {
+        let bytes: [u8; 4] = self.bytes(offset, 4)?.try_into().unwrap();
+        Ok(match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        } as usize)
+    }
+
+    // Read IFD entries and offset of next IFD
+    fn ifd(&self, offset: usize) -> Result<(Vec<Entry>, usize), Error> {
+        let count = self.u16(offset)? as usize;
+        let mut entries = Vec::with_capacity(count);
+        for index in 0..count {
+            let start = offset + 2 + index * 12;
+            let kind = self.u16(start + 2)?;
+            let count = self.u32(start + 4)? as u32;
+            // Values of 4 bytes or less are stored in the entry itself
+            let value = if Self::type_size(kind) * count as usize <= 4 {
+                start + 8
+            } else {
+                self.u32(start + 8)?
+            };
+            entries.push(Entry {
+                tag: self.u16(start)?,
+                kind,
+                count,
+                value,
+            });
+        }
+        let next = self.u32(offset + 2 + count * 12)?;
+        Ok((entries, next))
+    }
+
+    fn type_size(kind: u16) -> usize {
+        match kind {
+            3 | 8 => 2,
+            4 | 9 | 11 | 13 => 4,
+            5 | 10 | 12 => 8,
+            _ => 1,
+        }
+    }
+
+    // Values of a BYTE, SHORT or LONG tag (also used for IFD offsets)
+    fn u32_values(&self, ifd: &[Entry], tag: u16) -> Vec<usize> {
+        let Some(entry) = ifd.iter().find(|entry| entry.tag == tag) else {
+            return Vec::new();
+        };
+        (0..entry.count as usize)
+            .map_while(|index| match entry.kind {
+                1 => self
+                    .bytes(entry.value + index, 1)
+                    .ok()
+                    .map(|b| b[0] as usize),
+                3 => self.u16(entry.value + index * 2).ok().map(usize::from),
+                4 | 13 => self.u32(entry.value + index * 4).ok(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn string(&self, ifd: &[Entry], tag: u16) -> Option<String> {
+        let entry = ifd
+            .iter()
+            .find(|entry| entry.tag == tag && entry.kind == 2)?;
+        let bytes = self.bytes(entry.value, entry.count as usize).ok()?;
+        let value = String::from_utf8_lossy(bytes);
+        let value = value.trim_end_matches('\0').trim();
+        (!value.is_empty()).then(|| value.to_string())
+    }
+
+    fn rational(&self, ifd: &[Entry], tag: u16) -> Option<uR64> {
+        let entry = ifd
+            .iter()
+            .find(|entry| entry.tag == tag && matches!(entry.kind, 5 | 10))?;
+        Some(uR64 {
+            nominator: self.u32(entry.value).ok()? as u32,
+            denominator: self.u32(entry.value + 4).ok()? as u32,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn load_raw() {
+        assert_eq!(
+            mime_from_extention(Path::new("tests/raw.NEF")),
+            Some("image/x-nikon-nef")
+        );
+        assert_eq!(mime_from_extention(Path::new("tests/all_tags.jpg")), None);
+        assert!(load(&fs::read("tests/all_tags.jpg").unwrap()).is_err());
+        assert_eq!(
+            compression(&fs::read("tests/rotated.tiff").unwrap()),
+            Some(1)
+        );
+        assert_eq!(compression(&fs::read("tests/all_tags.jpg").unwrap()), None);
+
+        let (dimentions, litte_metadata) = load(&fs::read("tests/raw.nef").unwrap()).unwrap();
+        assert_eq!(dimentions, (4256, 2832));
+        assert!(litte_metadata
+            .get_tag(&ExifTag::Model(String::new()))
+            .next()
+            .is_some());
+        assert!(litte_metadata
+            .get_tag(&ExifTag::DateTimeOriginal(String::new()))
+            .next()
+            .is_some());
+    }
+}
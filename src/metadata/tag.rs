@@ -1,17 +1,30 @@
 use enumset::EnumSetType;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::path::Path;
 
 use super::Metadata;
 
-#[derive(EnumSetType, Debug)]
+#[derive(EnumSetType, Debug, Serialize, Deserialize)]
 pub enum Tag {
     Description,
     Date,
+    Keywords,
+    Gps,
     Dimensions,
     FileName,
+    Folder,
     Orientation,
+    Rating,
+    Mtime,
+    Comment,
+    Title,
+    Caption,
+    Strings,
+    MakerNote,
+    Others,
 }
 
 impl Display for Tag {
@@ -32,6 +45,11 @@ impl DisplayWithComment for Tag {
                 self,
                 metadata.path.file_name().unwrap().to_string_lossy()
             ),
+            Tag::Folder => format!(
+                "{}({})",
+                self,
+                metadata.path.parent().unwrap_or(Path::new("")).display()
+            ),
             _ => self.to_string(),
         }
     }
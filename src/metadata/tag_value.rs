@@ -0,0 +1,114 @@
+use little_exif::{
+    endian::Endian,
+    exif_tag::ExifTag,
+    exif_tag_format::ExifTagFormat,
+    rational::{iR64, uR64},
+    u8conversion::U8conversion,
+};
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+/// Typed value of an EXIF tag.
+/// Formats without a dedicated variant (signed integers, floats, undefined) are kept as bytes.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TagValue {
+    Text(String),
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+    URational(Vec<uR64>),
+    IRational(Vec<iR64>),
+    Bytes(Vec<u8>),
+}
+
+impl TagValue {
+    /// Decode the value of a little_exif tag
+    pub fn from_exif_tag(tag: &ExifTag, endian: &Endian) -> TagValue {
+        let data = tag.value_as_u8_vec(endian);
+        match tag.format() {
+            ExifTagFormat::STRING => TagValue::Text(String::from_u8_vec(&data, endian)),
+            ExifTagFormat::INT8U => TagValue::U8(data),
+            ExifTagFormat::INT16U => TagValue::U16(Self::decode(&data, 2, endian)),
+            ExifTagFormat::INT32U => TagValue::U32(Self::decode(&data, 4, endian)),
+            ExifTagFormat::RATIONAL64U => TagValue::URational(Self::decode(&data, 8, endian)),
+            ExifTagFormat::RATIONAL64S => TagValue::IRational(Self::decode(&data, 8, endian)),
+            _ => TagValue::Bytes(data),
+        }
+    }
+
+    // Decode each value of an array
+    fn decode<T: U8conversion<T>>(data: &[u8], size: usize, endian: &Endian) -> Vec<T> {
+        data.chunks_exact(size)
+            .map(|chunk| T::from_u8_vec(&chunk.to_vec(), endian))
+            .collect()
+    }
+}
+
+impl Display for TagValue {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let join = |values: Vec<String>| values.join(", ");
+        match self {
+            TagValue::Text(text) => write!(f, "{}", text),
+            TagValue::U8(values) => {
+                write!(f, "{}", join(values.iter().map(u8::to_string).collect()))
+            }
+            TagValue::U16(values) => {
+                write!(f, "{}", join(values.iter().map(u16::to_string).collect()))
+            }
+            TagValue::U32(values) => {
+                write!(f, "{}", join(values.iter().map(u32::to_string).collect()))
+            }
+            TagValue::URational(values) => write!(
+                f,
+                "{}",
+                join(
+                    values
+                        .iter()
+                        .map(|value| format!("{}/{}", value.nominator, value.denominator))
+                        .collect()
+                )
+            ),
+            TagValue::IRational(values) => write!(
+                f,
+                "{}",
+                join(
+                    values
+                        .iter()
+                        .map(|value| format!("{}/{}", value.nominator, value.denominator))
+                        .collect()
+                )
+            ),
+            TagValue::Bytes(data) => write!(f, "<{} bytes>", data.len()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_tag_value() {
+        assert_eq!(TagValue::Text("Canon".to_string()).to_string(), "Canon");
+        assert_eq!(TagValue::U8(vec![2, 3, 0, 0]).to_string(), "2, 3, 0, 0");
+        assert_eq!(TagValue::U16(vec![1, 2]).to_string(), "1, 2");
+        assert_eq!(
+            TagValue::URational(vec![uR64 {
+                nominator: 1,
+                denominator: 32
+            }])
+            .to_string(),
+            "1/32"
+        );
+        assert_eq!(
+            TagValue::IRational(vec![iR64 {
+                nominator: -1,
+                denominator: 3
+            }])
+            .to_string(),
+            "-1/3"
+        );
+        assert_eq!(TagValue::Bytes(vec![0; 4]).to_string(), "<4 bytes>");
+    }
+}
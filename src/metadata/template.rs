@@ -0,0 +1,148 @@
+use std::io::Error;
+
+use super::Metadata;
+
+/// Text with placeholders substituted from image metadata, like "Shot on {camera} at {date}".
+/// Placeholders: camera, date, iso, aperture, focal. Use {{ and }} for literal braces.
+/// Undefined values are replaced by an empty string.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Text(String),
+    Camera,
+    Date,
+    Iso,
+    Aperture,
+    Focal,
+}
+
+impl Template {
+    /// Parse a template, failing on unknown placeholders
+    pub fn parse(template: &str) -> Result<Template, Error> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(Error::other("Unmatched '{' in template.")),
+                        }
+                    }
+                    let part = match name.trim() {
+                        "camera" => Part::Camera,
+                        "date" => Part::Date,
+                        "iso" => Part::Iso,
+                        "aperture" => Part::Aperture,
+                        "focal" => Part::Focal,
+                        _ => {
+                            return Err(Error::other(format!(
+                                "Unknown placeholder: '{{{}}}'.",
+                                name
+                            )))
+                        }
+                    };
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(part);
+                }
+                '}' => return Err(Error::other("Unmatched '}' in template.")),
+                _ => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Template { parts })
+    }
+
+    /// Text of a template without placeholders (the same for all images)
+    pub fn text(&self) -> Option<String> {
+        match self.parts.as_slice() {
+            [] => Some(String::new()),
+            [Part::Text(text)] => Some(text.clone()),
+            _ => None,
+        }
+    }
+
+    /// Substitute placeholders with image values
+    pub fn render(&self, metadata: &Metadata) -> String {
+        let camera_info = metadata.camera_info();
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => Some(text.clone()),
+                Part::Camera => camera_info.camera.clone(),
+                Part::Date => metadata
+                    .date()
+                    .map(|date| date.format("%Y-%m-%d %H:%M:%S").to_string()),
+                Part::Iso => camera_info.iso.map(|iso| iso.to_string()),
+                Part::Aperture => camera_info
+                    .aperture
+                    .as_ref()
+                    .map(|aperture| format!("f/{}", aperture)),
+                Part::Focal => camera_info.focal.map(|focal| format!("{}mm", focal)),
+            })
+            .map(Option::unwrap_or_default)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn parse_template() {
+        assert!(Template::parse("A fun picture").is_ok());
+        assert!(Template::parse("Shot on {camera} at {date}").is_ok());
+        assert!(Template::parse("{{literal}}").is_ok());
+        assert!(Template::parse("{lens}").is_err());
+        assert!(Template::parse("{camera").is_err());
+        assert!(Template::parse("camera}").is_err());
+    }
+
+    #[test]
+    fn template_text() {
+        let text = |template: &str| Template::parse(template).unwrap().text();
+        assert_eq!(text("A fun picture"), Some("A fun picture".to_string()));
+        assert_eq!(text("{{literal}}"), Some("{literal}".to_string()));
+        assert_eq!(text(""), Some(String::new()));
+        assert_eq!(text("Shot on {camera}"), None);
+    }
+
+    #[test]
+    fn render_template() {
+        let metadata = Metadata::new(Path::new("tests/all_tags.jpg")).unwrap();
+        let render = |template: &str| Template::parse(template).unwrap().render(&metadata);
+
+        assert_eq!(render("A fun picture"), "A fun picture");
+        assert_eq!(render("{{camera}}"), "{camera}");
+        assert_eq!(
+            render("Shot on {camera} at {date}"),
+            format!(
+                "Shot on {} at {}",
+                metadata.camera_info().camera.clone().unwrap(),
+                metadata.date().unwrap().format("%Y-%m-%d %H:%M:%S")
+            )
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use chrono::NaiveDateTime;
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+use super::parse_date_flexible;
+use crate::file_list;
+
+const NAMESPACES: [(&str, &str); 2] = [
+    ("dc", "http://purl.org/dc/elements/1.1/"),
+    ("xmp", "http://ns.adobe.com/xap/1.0/"),
+];
+
+/// XMP sidecar: <name>.xmp next to the image.
+/// Only dc:description, xmp:Rating and xmp:CreateDate are handled,
+/// other properties are kept as is.
+#[derive(Debug, Clone)]
+pub struct Xmp {
+    path: PathBuf,
+    text: String,
+}
+
+impl Xmp {
+    /// Path of the sidecar of an image
+    pub fn sidecar_path(image: &Path) -> PathBuf {
+        image.with_extension("xmp")
+    }
+
+    /// Load the sidecar of an image, if any
+    pub fn load(image: &Path) -> Result<Option<Xmp>, Error> {
+        let path = Self::sidecar_path(image);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let text = fs::read_to_string(&path)?;
+        Ok(Some(Xmp { path, text }))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn description(&self) -> Option<String> {
+        let value = self.property("dc:description")?;
+        // Language alternative: keep the first (default) one
+        let value = match value.find("<rdf:li") {
+            None => value.as_str(),
+            Some(start) => {
+                let start = start + value[start..].find('>')? + 1;
+                let end = start + value[start..].find("</rdf:li>")?;
+                &value[start..end]
+            }
+        };
+        Some(unescape(value.trim()))
+    }
+
+    pub fn rating(&self) -> Option<u16> {
+        self.property("xmp:Rating")?.trim().parse().ok()
+    }
+
+    pub fn create_date(&self) -> Option<NaiveDateTime> {
+        // Drop fractional seconds and time zone (2006-10-29T16:27:21.00+02:00)
+        let value = self.property("xmp:CreateDate")?;
+        let value = value.trim();
+        parse_date_flexible(value.get(..19).unwrap_or(value)).ok()
+    }
+
+    /// Update (or remove) dc:description.
+    /// Note: file will not be modified unless you call save().
+    pub fn set_description(&mut self, description: Option<&str>) -> Result<(), Error> {
+        self.set_property(
+            "dc:description",
+            description.map(|description| {
+                format!(
+                    "<rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt>",
+                    escape(description)
+                )
+            }),
+        )
+    }
+
+    /// Update (or remove) xmp:Rating.
+    /// Note: file will not be modified unless you call save().
+    pub fn set_rating(&mut self, rating: Option<u16>) -> Result<(), Error> {
+        self.set_property("xmp:Rating", rating.map(|rating| rating.to_string()))
+    }
+
+    /// Update (or remove) xmp:CreateDate.
+    /// Note: file will not be modified unless you call save().
+    pub fn set_create_date(&mut self, date: Option<NaiveDateTime>) -> Result<(), Error> {
+        self.set_property(
+            "xmp:CreateDate",
+            date.map(|date| date.format("%Y-%m-%dT%H:%M:%S").to_string()),
+        )
+    }
+
+    /// Write the sidecar
+    pub fn save(&self) -> Result<(), Error> {
+        fs::write(&self.path, &self.text)
+    }
+
+    /// Move (or copy) the sidecar next to a moved image.
+    /// An existing sidecar at destination is never overwritten.
+    pub fn follow(&mut self, image: &Path, copy: bool) -> Result<(), Error> {
+        let path = Self::sidecar_path(image);
+        if path == self.path || path.exists() {
+            return Ok(());
+        }
+        if copy {
+            fs::copy(&self.path, &path)?;
+        } else {
+            file_list::move_file(&self.path, &path)?;
+        }
+        self.path = path;
+        Ok(())
+    }
+
+    // Find a property, either as an element (<name>value</name>)
+    // or as an attribute of rdf:Description (name="value")
+    fn property(&self, name: &str) -> Option<String> {
+        if let Some((start, end)) = self.element_range(name) {
+            let element = &self.text[start..end];
+            let content_start = element.find('>')? + 1;
+            let content_end = element.rfind("</")?;
+            return Some(element[content_start..content_end].to_string());
+        }
+        let (start, end) = self.attribute_range(name)?;
+        let attribute = &self.text[start..end];
+        let value_start = attribute.find('"')? + 1;
+        Some(attribute[value_start..attribute.len() - 1].to_string())
+    }
+
+    // Replace a property by a new element (or remove it)
+    fn set_property(&mut self, name: &str, value: Option<String>) -> Result<(), Error> {
+        if let Some((start, end)) = self.element_range(name) {
+            self.text.replace_range(start..end, "");
+        } else if let Some((start, end)) = self.attribute_range(name) {
+            // Also remove the leading white space
+            let start = self.text[..start].trim_end().len();
+            self.text.replace_range(start..end, "");
+        }
+
+        let Some(value) = value else {
+            return Ok(());
+        };
+        let Some(end) = self.text.find("</rdf:Description>") else {
+            return Err(Error::other(format!(
+                "Cannot update {}: unsupported XMP layout.",
+                self.path.display()
+            )));
+        };
+        self.text
+            .insert_str(end, &format!(" <{0}>{1}</{0}>\n  ", name, value));
+        self.declare_namespace(name)
+    }
+
+    // Declare namespace of property on rdf:Description if needed
+    fn declare_namespace(&mut self, name: &str) -> Result<(), Error> {
+        let prefix = name.split(':').next().unwrap_or_default();
+        let Some((_, uri)) = NAMESPACES.iter().find(|(p, _)| *p == prefix) else {
+            return Ok(());
+        };
+        if self.text.contains(&format!("xmlns:{}=", prefix)) {
+            return Ok(());
+        }
+        let Some(start) = self.text.find("<rdf:Description") else {
+            return Err(Error::other("rdf:Description not found."));
+        };
+        let end = start + "<rdf:Description".len();
+        self.text
+            .insert_str(end, &format!(" xmlns:{}=\"{}\"", prefix, uri));
+        Ok(())
+    }
+
+    // Byte range of <name ...>...</name>
+    fn element_range(&self, name: &str) -> Option<(usize, usize)> {
+        let open = format!("<{}", name);
+        let close = format!("</{}>", name);
+        let mut from = 0;
+        while let Some(found) = self.text[from..].find(&open) {
+            let start = from + found;
+            let after = self.text[start + open.len()..].chars().next()?;
+            if after == '>' || after.is_whitespace() {
+                let end = start + self.text[start..].find(&close)? + close.len();
+                return Some((start, end));
+            }
+            from = start + open.len();
+        }
+        None
+    }
+
+    // Byte range of name="value"
+    fn attribute_range(&self, name: &str) -> Option<(usize, usize)> {
+        let pattern = format!("{}=\"", name);
+        let mut from = 0;
+        while let Some(found) = self.text[from..].find(&pattern) {
+            let start = from + found;
+            let before = self.text[..start].chars().next_back()?;
+            if before.is_whitespace() {
+                let value_start = start + pattern.len();
+                let end = value_start + self.text[value_start..].find('"')? + 1;
+                return Some((start, end));
+            }
+            from = start + pattern.len();
+        }
+        None
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn read_sidecar() {
+        assert!(Xmp::load(Path::new("tests/all_tags.jpg"))
+            .unwrap()
+            .is_none());
+
+        let xmp = Xmp::load(Path::new("tests/sidecar.jpg")).unwrap().unwrap();
+        assert_eq!(xmp.path(), Path::new("tests/sidecar.xmp"));
+        assert_eq!(
+            xmp.description(),
+            Some("A fun picture & a sidecar".to_string())
+        );
+        assert_eq!(xmp.rating(), Some(4));
+        assert_eq!(
+            xmp.create_date(),
+            NaiveDate::from_ymd_opt(2006, 10, 29)
+                .unwrap()
+                .and_hms_opt(16, 27, 21)
+        );
+    }
+
+    #[test]
+    fn write_sidecar() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let image = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy("tests/sidecar.xmp", Xmp::sidecar_path(&image)).is_ok());
+
+        let mut xmp = Xmp::load(&image).unwrap().unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2)
+            .unwrap()
+            .and_hms_opt(3, 4, 5);
+        assert!(xmp.set_description(Some("<New> description")).is_ok());
+        assert!(xmp.set_create_date(date).is_ok());
+        assert!(xmp.set_rating(Some(2)).is_ok());
+        assert!(xmp.save().is_ok());
+
+        let mut xmp = Xmp::load(&image).unwrap().unwrap();
+        assert_eq!(xmp.description(), Some("<New> description".to_string()));
+        assert_eq!(xmp.create_date(), date);
+        assert_eq!(xmp.rating(), Some(2));
+
+        assert!(xmp.set_description(None).is_ok());
+        assert!(xmp.save().is_ok());
+        let xmp = Xmp::load(&image).unwrap().unwrap();
+        assert_eq!(xmp.description(), None);
+        assert_eq!(xmp.create_date(), date);
+    }
+
+    #[test]
+    fn declare_namespace() {
+        let mut xmp = Xmp {
+            path: PathBuf::from("test.xmp"),
+            text: "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\
+                   <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\
+                   <rdf:Description rdf:about=\"\"></rdf:Description>\
+                   </rdf:RDF></x:xmpmeta>"
+                .to_string(),
+        };
+        assert!(xmp.set_description(Some("Description 1")).is_ok());
+        assert!(xmp
+            .text
+            .contains("xmlns:dc=\"http://purl.org/dc/elements/1.1/\""));
+        assert_eq!(xmp.description(), Some("Description 1".to_string()));
+    }
+}
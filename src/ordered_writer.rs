@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+use std::io::{Result, Write};
+
+/// Write per-file results (one line each) in input order, whatever their completion order.
+/// When ordered, a result is buffered until the results of all previous inputs are written:
+/// each input index must be pushed once (an empty result writes nothing).
+/// Otherwise (single thread), results are written as soon as they are pushed.
+pub struct OrderedWriter<W: Write> {
+    output: W,
+    ordered: bool,
+    next: usize,
+    pending: BTreeMap<usize, String>,
+}
+
+impl<W: Write> OrderedWriter<W> {
+    pub fn new(output: W, ordered: bool) -> OrderedWriter<W> {
+        OrderedWriter {
+            output,
+            ordered,
+            next: 0,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Push the result of the input at index and write all the results that are ready
+    pub fn push(&mut self, index: usize, result: String) -> Result<()> {
+        if !self.ordered {
+            return self.write(&result);
+        }
+        self.pending.insert(index, result);
+        while let Some(result) = self.pending.remove(&self.next) {
+            self.write(&result)?;
+            self.next += 1;
+        }
+        Ok(())
+    }
+
+    // One write per result: lines are never interleaved
+    fn write(&mut self, result: &str) -> Result<()> {
+        if result.is_empty() {
+            return Ok(());
+        }
+        writeln!(self.output, "{}", result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ordered_output() {
+        let mut output: Vec<u8> = Vec::new();
+        let mut writer = OrderedWriter::new(&mut output, true);
+        assert!(writer.push(2, "c".to_string()).is_ok());
+        assert!(writer.push(1, String::new()).is_ok());
+        assert_eq!(writer.pending.len(), 2);
+        assert!(writer.push(0, "a".to_string()).is_ok());
+        assert!(writer.pending.is_empty());
+        assert!(writer.push(3, "d".to_string()).is_ok());
+        assert_eq!(String::from_utf8(output).unwrap(), "a\nc\nd\n");
+    }
+
+    #[test]
+    fn passthrough_output() {
+        let mut output: Vec<u8> = Vec::new();
+        let mut writer = OrderedWriter::new(&mut output, false);
+        assert!(writer.push(2, "c".to_string()).is_ok());
+        assert!(writer.push(0, "a".to_string()).is_ok());
+        assert!(writer.pending.is_empty());
+        assert_eq!(String::from_utf8(output).unwrap(), "c\na\n");
+    }
+}
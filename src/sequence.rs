@@ -0,0 +1,108 @@
+use chrono::NaiveDateTime;
+
+/// Plan sequence names (PREFIX_001, PREFIX_002, ...) of a set of images, in date order.
+/// dates are the image dates in input order; images with the same date keep this order.
+/// Images without date are not numbered (None).
+/// Counters have 3 digits, or more if needed by the number of images.
+pub fn plan(dates: &[Option<NaiveDateTime>], prefix: &str) -> Vec<Option<String>> {
+    let mut dated: Vec<(usize, NaiveDateTime)> = dates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, date)| Some((index, (*date)?)))
+        .collect();
+    dated.sort_by_key(|(_, date)| *date);
+
+    let width = dated.len().to_string().len().max(3);
+    let mut names = vec![None; dates.len()];
+    for (counter, (index, _)) in dated.into_iter().enumerate() {
+        names[index] = Some(format!("{}_{:0width$}", prefix, counter + 1, width = width));
+    }
+    names
+}
+
+/// Order to rename images so that no image is renamed over another one not renamed yet.
+/// conflicts holds, for each image, the index of the image currently at its new path (if any):
+/// the latter is renamed first. Other images keep their input order.
+/// Images that would take each other's name (like two images swapping their numbers)
+/// cannot be renamed, nor the images waiting for them: they are returned as blocked.
+pub fn rename_order(conflicts: &[Option<usize>]) -> (Vec<usize>, Vec<bool>) {
+    let mut order = Vec::with_capacity(conflicts.len());
+    let mut placed = vec![false; conflicts.len()];
+    let mut blocked = vec![false; conflicts.len()];
+    let mut in_chain = vec![false; conflicts.len()];
+    for first in 0..conflicts.len() {
+        // Follow the images to rename before this one
+        let mut chain = Vec::new();
+        let mut next = Some(first);
+        let mut chain_blocked = false;
+        while let Some(index) = next {
+            if placed[index] {
+                chain_blocked = blocked[index];
+                break;
+            }
+            if in_chain[index] {
+                chain_blocked = true;
+                break;
+            }
+            in_chain[index] = true;
+            chain.push(index);
+            next = conflicts[index].filter(|&other| other != index);
+        }
+        for &index in chain.iter().rev() {
+            placed[index] = true;
+            blocked[index] = chain_blocked;
+            order.push(index);
+        }
+    }
+    (order, blocked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn date(day: u32) -> Option<NaiveDateTime> {
+        NaiveDate::from_ymd_opt(2006, 10, day)?.and_hms_opt(16, 27, 21)
+    }
+
+    #[test]
+    fn plan_names() {
+        let names = plan(&[date(29), None, date(28), date(29)], "Wedding");
+        assert_eq!(
+            names,
+            vec![
+                Some("Wedding_002".to_string()),
+                None,
+                Some("Wedding_001".to_string()),
+                Some("Wedding_003".to_string()),
+            ]
+        );
+
+        // Width adapts to the number of images
+        let names = plan(&vec![date(29); 1000], "Wedding");
+        assert_eq!(names[0], Some("Wedding_0001".to_string()));
+        assert_eq!(names[999], Some("Wedding_1000".to_string()));
+
+        assert!(plan(&[], "Wedding").is_empty());
+    }
+
+    #[test]
+    fn order_renames() {
+        // Wedding_001 to Wedding_003 (images 1 to 3) after adding an earlier image (0)
+        let (order, blocked) = rename_order(&[Some(1), Some(2), Some(3), None]);
+        assert_eq!(order, vec![3, 2, 1, 0]);
+        assert_eq!(blocked, vec![false; 4]);
+
+        // No conflict: input order
+        let (order, _) = rename_order(&[None, None, Some(1)]);
+        assert_eq!(order, vec![0, 1, 2]);
+
+        // Images 0 and 1 swap their names, image 3 waits for image 0
+        let (order, blocked) = rename_order(&[Some(1), Some(0), None, Some(0)]);
+        assert_eq!(order, vec![1, 0, 2, 3]);
+        assert_eq!(blocked, vec![true, true, false, true]);
+
+        assert_eq!(rename_order(&[]), (vec![], vec![]));
+    }
+}
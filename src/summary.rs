@@ -0,0 +1,86 @@
+use chrono::NaiveDateTime;
+use std::collections::BTreeMap;
+
+use crate::metadata::Metadata;
+
+/// Statistics over a set of images
+#[derive(Default, Debug)]
+pub struct Summary {
+    pub files: usize,
+    pub errors: usize,
+    pub skipped_no_exif: usize,
+    pub with_date: usize,
+    pub with_description: usize,
+    pub earliest: Option<NaiveDateTime>,
+    pub latest: Option<NaiveDateTime>,
+    pub cameras: BTreeMap<String, usize>,
+}
+
+impl Summary {
+    /// Account for an image
+    pub fn add(&mut self, metadata: &Metadata) {
+        self.files += 1;
+        if let Some(date) = metadata.date() {
+            self.with_date += 1;
+            self.earliest = Some(self.earliest.map_or(date, |d| d.min(date)));
+            self.latest = Some(self.latest.map_or(date, |d| d.max(date)));
+        }
+        if metadata.description().is_some() {
+            self.with_description += 1;
+        }
+        let camera = metadata
+            .camera_info()
+            .camera
+            .clone()
+            .unwrap_or("Unknown camera".to_string());
+        *self.cameras.entry(camera).or_insert(0) += 1;
+    }
+
+    /// Account for a file that cannot be loaded
+    pub fn add_error(&mut self) {
+        self.files += 1;
+        self.errors += 1;
+    }
+
+    /// Account for a file skipped because it has no EXIF data
+    pub fn add_skipped_no_exif(&mut self) {
+        self.files += 1;
+        self.skipped_no_exif += 1;
+    }
+
+    pub fn without_date(&self) -> usize {
+        self.files - self.errors - self.skipped_no_exif - self.with_date
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::path::Path;
+
+    #[test]
+    fn summary() {
+        let mut summary = Summary::default();
+        summary.add(&Metadata::new(Path::new("tests/all_tags.jpg")).unwrap());
+        summary.add(&Metadata::new(Path::new("tests/no_date.jpg")).unwrap());
+        summary.add(&Metadata::new(Path::new("tests/no_description.jpg")).unwrap());
+        summary.add(&Metadata::new(Path::new("tests/no_camera.jpg")).unwrap());
+        summary.add_error();
+        summary.add_skipped_no_exif();
+
+        assert_eq!(summary.files, 6);
+        assert_eq!(summary.errors, 1);
+        assert_eq!(summary.skipped_no_exif, 1);
+        assert_eq!(summary.with_date, 3);
+        assert_eq!(summary.without_date(), 1);
+        assert_eq!(summary.with_description, 3);
+        let date = NaiveDate::from_ymd_opt(2006, 10, 29)
+            .unwrap()
+            .and_hms_opt(16, 27, 21);
+        assert_eq!(summary.earliest, date);
+        assert_eq!(summary.latest, date);
+        assert_eq!(summary.cameras.get("Pablo Picasso (1.4)"), Some(&3));
+        assert_eq!(summary.cameras.get("Unknown camera"), Some(&1));
+    }
+}
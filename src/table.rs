@@ -0,0 +1,229 @@
+use std::fmt::Display;
+
+/// Minimal width of the label column
+const LABEL_WIDTH: usize = 15;
+
+/// Minimal width of the last column of a grid
+const GRID_MIN_WIDTH: usize = 8;
+
+/// Two columns (label, value) table, aligned on its longest label.
+/// Rows are displayed by print().
+#[derive(Default)]
+pub struct Table {
+    rows: Vec<(String, String)>,
+}
+
+impl Table {
+    pub fn row(&mut self, label: impl Display, value: impl Display) {
+        self.rows.push((label.to_string(), value.to_string()));
+    }
+
+    /// Print and remove all rows
+    pub fn print(&mut self) {
+        let width = self
+            .rows
+            .iter()
+            .map(|(label, _)| visible_width(label))
+            .max()
+            .unwrap_or_default()
+            .max(LABEL_WIDTH);
+        for (label, value) in self.rows.drain(..) {
+            println!("{}", format_row(&label, &value, width));
+        }
+    }
+}
+
+/// Print a single row right away
+pub fn print_row(label: impl Display, value: impl Display) {
+    println!(
+        "{}",
+        format_row(&label.to_string(), &value.to_string(), LABEL_WIDTH)
+    );
+}
+
+fn format_row(label: &str, value: &str, width: usize) -> String {
+    let padding = width.saturating_sub(visible_width(label));
+    format!("{}{} {}", label, " ".repeat(padding), value)
+}
+
+/// Multi-column table with a header, columns aligned on their longest cell.
+/// The last column is truncated to fit in the terminal width.
+pub struct Grid {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Grid {
+    pub fn new(header: &[&str]) -> Grid {
+        Grid {
+            header: header.iter().map(|title| title.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    pub fn print(&self) {
+        for line in self.format(terminal_width()) {
+            println!("{}", line);
+        }
+    }
+
+    fn format(&self, width: usize) -> Vec<String> {
+        let mut widths: Vec<usize> = self
+            .header
+            .iter()
+            .map(|title| visible_width(title))
+            .collect();
+        for row in self.rows.iter() {
+            for (column, cell) in row.iter().enumerate().take(widths.len()) {
+                widths[column] = widths[column].max(visible_width(cell));
+            }
+        }
+        // Shrink the last column to fit in width (but keep it readable)
+        if let Some((last, others)) = widths.split_last_mut() {
+            let used: usize = others.iter().map(|width| width + 3).sum();
+            *last = (*last).min(width.saturating_sub(used).max(GRID_MIN_WIDTH));
+        }
+
+        let format_line = |cells: &[String]| {
+            let line: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(column, width)| {
+                    let cell = truncate(cells.get(column).map_or("", |cell| cell), *width);
+                    let padding = width.saturating_sub(visible_width(&cell));
+                    format!("{}{}", cell, " ".repeat(padding))
+                })
+                .collect();
+            line.join(" | ").trim_end().to_string()
+        };
+        let separator: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+        let mut lines = vec![format_line(&self.header), separator.join("-+-")];
+        lines.extend(self.rows.iter().map(|row| format_line(row)));
+        lines
+    }
+}
+
+// Truncate a text (without color codes) to width characters, ending with '…' when truncated
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Terminal width from the COLUMNS environment variable (120 by default)
+pub fn terminal_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(120)
+}
+
+/// Width of a text in a terminal: color codes (ANSI escape sequences) do not count
+pub fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            width += 1;
+        } else if chars.next() == Some('[') {
+            // Skip parameters up to the final byte of the sequence
+            for c in chars.by_ref() {
+                if ('\x40'..='\x7e').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    width
+}
+
+/// Format a file size in bytes, or in KiB, MiB, ... (one decimal) if human is set
+pub fn format_size(bytes: u64, human: bool) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if !human || bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    // Sizes displayed as 1024.0 go to the next unit
+    while size >= 1023.95 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn row_alignment() {
+        // "Warning:".yellow()
+        let warning = "\x1b[33mWarning:\x1b[0m";
+        assert_eq!(visible_width(warning), "Warning:".len());
+        assert_eq!(visible_width("Dimensions:"), 11);
+
+        assert_eq!(
+            format_row(warning, "value", LABEL_WIDTH),
+            format!("{}{} value", warning, " ".repeat(7))
+        );
+        assert_eq!(format_row("Date:", "value", 6), "Date:  value");
+        assert_eq!(
+            format_row("Too long label:", "value", 6),
+            "Too long label: value"
+        );
+    }
+
+    #[test]
+    fn grid() {
+        let mut grid = Grid::new(&["File", "ISO", "Description"]);
+        grid.row(vec![
+            "a.jpg".to_string(),
+            "100".to_string(),
+            "A long description".to_string(),
+        ]);
+        grid.row(vec![
+            "photo.jpg".to_string(),
+            "-".to_string(),
+            "".to_string(),
+        ]);
+        assert_eq!(
+            grid.format(80),
+            vec![
+                "File      | ISO | Description",
+                "----------+-----+-------------------",
+                "a.jpg     | 100 | A long description",
+                "photo.jpg | -   |",
+            ]
+        );
+        assert_eq!(
+            grid.format(28),
+            vec![
+                "File      | ISO | Descripti…",
+                "----------+-----+-----------",
+                "a.jpg     | 100 | A long de…",
+                "photo.jpg | -   |",
+            ]
+        );
+    }
+
+    #[test]
+    fn file_size() {
+        assert_eq!(format_size(1024, false), "1024 B");
+        assert_eq!(format_size(0, true), "0 B");
+        assert_eq!(format_size(1023, true), "1023 B");
+        assert_eq!(format_size(1024, true), "1.0 KiB");
+        assert_eq!(format_size(1536, true), "1.5 KiB");
+        assert_eq!(format_size(1024 * 1024 - 1, true), "1.0 MiB");
+        assert_eq!(format_size(1024 * 1024, true), "1.0 MiB");
+        assert_eq!(format_size(5 * 1024 * 1024 * 1024, true), "5.0 GiB");
+    }
+}
@@ -0,0 +1,210 @@
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Report files created (or renamed) in a folder (non-recursive) once they are stable:
+/// same size and no modification for the settle duration.
+/// Hidden files (like temporary files) are ignored.
+pub struct FolderWatcher {
+    // Events are sent as long as the watcher lives
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    folder: PathBuf,
+    settle: Duration,
+    // New files: last known size and time of last change
+    pending: HashMap<PathBuf, (u64, Instant)>,
+    // Files written while processing new files (see ignore_changes())
+    ignored: HashMap<PathBuf, FileState>,
+}
+
+/// Size and modification time of a file
+pub type FileState = (u64, SystemTime);
+
+impl FolderWatcher {
+    pub fn new(folder: &Path, settle: Duration) -> Result<FolderWatcher, Error> {
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(Error::other)?;
+        watcher
+            .watch(folder, RecursiveMode::NonRecursive)
+            .map_err(Error::other)?;
+        Ok(FolderWatcher {
+            _watcher: watcher,
+            events,
+            folder: folder.to_path_buf(),
+            settle,
+            pending: HashMap::new(),
+            ignored: HashMap::new(),
+        })
+    }
+
+    /// State of the files of the folder, to be given to ignore_changes()
+    pub fn files(&self) -> Result<HashMap<PathBuf, FileState>, Error> {
+        let mut files = HashMap::new();
+        for entry in fs::read_dir(&self.folder)? {
+            let path = entry?.path();
+            if let Some(state) = file_state(&path) {
+                files.insert(path, state);
+            }
+        }
+        Ok(files)
+    }
+
+    /// Ignore the files created or modified since files() returned before,
+    /// like the files written or renamed by the command run on a new file:
+    /// they are not reported, unless modified again later.
+    pub fn ignore_changes(&mut self, before: &HashMap<PathBuf, FileState>) -> Result<(), Error> {
+        for (path, state) in self.files()? {
+            if before.get(&path) != Some(&state) {
+                self.ignored.insert(path, state);
+            }
+        }
+        Ok(())
+    }
+
+    /// Wait up to timeout for events, then return the new files that are stable (maybe none).
+    /// Each file is returned once.
+    pub fn ready(&mut self, timeout: Duration) -> Result<Vec<PathBuf>, Error> {
+        match self.events.recv_timeout(timeout) {
+            Ok(event) => self.add(event.map_err(Error::other)?),
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(Error::other("Folder watcher stopped."))
+            }
+        }
+        while let Ok(event) = self.events.try_recv() {
+            self.add(event.map_err(Error::other)?);
+        }
+
+        let now = Instant::now();
+        let mut ready = Vec::new();
+        self.pending.retain(|path, (size, since)| {
+            let Ok(file) = fs::metadata(path) else {
+                // Removed or renamed before being stable
+                return false;
+            };
+            if file.len() != *size {
+                *size = file.len();
+                *since = now;
+                return true;
+            }
+            if now.duration_since(*since) < self.settle {
+                return true;
+            }
+            ready.push(path.clone());
+            false
+        });
+        ready.sort();
+        Ok(ready)
+    }
+
+    fn add(&mut self, event: Event) {
+        let now = Instant::now();
+        for path in event.paths {
+            match event.kind {
+                // Also files renamed into place (like a download renamed once complete)
+                EventKind::Create(_)
+                | EventKind::Modify(ModifyKind::Name(RenameMode::To | RenameMode::Both))
+                    if !is_hidden(&path) && path.is_file() && !self.is_ignored(&path) =>
+                {
+                    self.pending.insert(path, (0, now));
+                }
+                EventKind::Modify(_) => {
+                    if let Some((_, since)) = self.pending.get_mut(&path) {
+                        *since = now;
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // Unchanged since ignored
+    fn is_ignored(&mut self, path: &Path) -> bool {
+        match self.ignored.get(path) {
+            None => false,
+            Some(state) if file_state(path).as_ref() == Some(state) => true,
+            Some(_) => {
+                self.ignored.remove(path);
+                false
+            }
+        }
+    }
+}
+
+fn file_state(path: &Path) -> Option<FileState> {
+    let file = fs::metadata(path)
+        .ok()
+        .filter(|file| file.is_file() && !is_hidden(path))?;
+    Some((file.len(), file.modified().ok()?))
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_new_files() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let mut watcher = FolderWatcher::new(tmpdir.path(), Duration::from_millis(200)).unwrap();
+
+        let file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &file_path).is_ok());
+        assert!(fs::write(tmpdir.path().join(".hidden.jpg"), b"partial").is_ok());
+        let download_path = tmpdir.path().join(".download.jpg");
+        let downloaded_path = tmpdir.path().join("downloaded.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &download_path).is_ok());
+        assert!(fs::rename(&download_path, &downloaded_path).is_ok());
+
+        // Not ready until stable
+        assert_eq!(watcher.ready(Duration::from_millis(50)).ok(), Some(vec![]));
+
+        let mut ready = Vec::new();
+        for _ in 0..20 {
+            ready.extend(watcher.ready(Duration::from_millis(100)).unwrap());
+        }
+        ready.sort();
+        assert_eq!(ready, vec![downloaded_path, file_path]);
+    }
+
+    #[test]
+    fn ignore_processed_files() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let mut watcher = FolderWatcher::new(tmpdir.path(), Duration::from_millis(200)).unwrap();
+        let wait_ready = |watcher: &mut FolderWatcher| {
+            let mut ready = Vec::new();
+            for _ in 0..10 {
+                ready.extend(watcher.ready(Duration::from_millis(100)).unwrap());
+            }
+            ready
+        };
+
+        let file_path = tmpdir.path().join("photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &file_path).is_ok());
+        assert_eq!(wait_ready(&mut watcher), vec![file_path.clone()]);
+
+        // Processed like save() (temporary file persisted) and fix --name (renamed)
+        let before = watcher.files().unwrap();
+        let tmp_file_path = tmpdir.path().join(".tmp_photo_norm_test.jpg");
+        assert!(fs::copy(Path::new("tests/no_date.jpg"), &tmp_file_path).is_ok());
+        assert!(fs::rename(&tmp_file_path, &file_path).is_ok());
+        let renamed_path = tmpdir.path().join("renamed.jpg");
+        assert!(fs::rename(&file_path, &renamed_path).is_ok());
+        assert!(watcher.ignore_changes(&before).is_ok());
+        assert!(wait_ready(&mut watcher).is_empty());
+
+        // Replaced later: new file
+        assert!(fs::copy(Path::new("tests/all_tags.jpg"), &tmp_file_path).is_ok());
+        assert!(fs::rename(&tmp_file_path, &renamed_path).is_ok());
+        assert_eq!(wait_ready(&mut watcher), vec![renamed_path]);
+    }
+}
@@ -0,0 +1,379 @@
+//! End-to-end tests of the PhotosNorm binary: command line parsing, output and exit codes.
+//! Images are copied from the tests folder into a temporary folder before being modified.
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// PhotosNorm command with a deterministic output (no colors, fixed width)
+fn photos_norm() -> Command {
+    let mut command = Command::cargo_bin("PhotosNorm").unwrap();
+    command.env("NO_COLOR", "1").env("COLUMNS", "120");
+    command
+}
+
+// Copy fixtures into folder, return their new paths
+fn copy_fixtures(folder: &Path, names: &[&str]) -> Vec<PathBuf> {
+    names
+        .iter()
+        .map(|name| {
+            let path = folder.join(name);
+            fs::copy(Path::new("tests").join(name), &path).unwrap();
+            path
+        })
+        .collect()
+}
+
+#[test]
+fn info() {
+    photos_norm()
+        .args(["info", "tests/all_tags.jpg"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("tests/all_tags.jpg"))
+        .stdout(predicate::str::contains("A fun picture!"))
+        .stdout(predicate::str::contains("2006:10:29 16:27:21"))
+        .stdout(predicate::str::contains("2048, 1536"));
+}
+
+#[test]
+fn info_json() {
+    let output = photos_norm()
+        .args(["info", "--format", "json", "tests/all_tags.jpg"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value[0]["description"], "A fun picture!");
+    assert_eq!(value[0]["width"], 2048);
+}
+
+#[test]
+fn info_exif_only() {
+    photos_norm()
+        .args(["info", "--exif-only", "tests/all_tags.jpg"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("A fun picture!"))
+        .stdout(predicate::str::contains("2006:10:29 16:27:21"))
+        .stdout(predicate::str::contains("{Not read}"))
+        .stdout(predicate::str::contains("2048, 1536").not());
+
+    let output = photos_norm()
+        .args([
+            "info",
+            "--exif-only",
+            "--format",
+            "json",
+            "tests/all_tags.jpg",
+        ])
+        .output()
+        .unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value[0]["description"], "A fun picture!");
+    assert!(value[0]["width"].is_null());
+}
+
+#[test]
+fn set_description() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let images = copy_fixtures(tmpdir.path(), &["all_tags.jpg"]);
+
+    photos_norm()
+        .args(["set", "--description", "Capitole"])
+        .arg(&images[0])
+        .assert()
+        .success();
+    photos_norm()
+        .arg("info")
+        .arg(&images[0])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Capitole"));
+}
+
+#[test]
+fn set_invalid_date() {
+    // all_tags.jpg has a date: the value is still checked with --if-missing
+    photos_norm()
+        .args(["set", "--if-missing", "--date", "29/10/2006"])
+        .arg("tests/all_tags.jpg")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Cannot parse date"));
+}
+
+#[test]
+fn set_several_images() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    copy_fixtures(tmpdir.path(), &["all_tags.jpg", "no_description.jpg"]);
+
+    photos_norm()
+        .args(["set", "--description", "Capitole"])
+        .arg(tmpdir.path())
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("--force"));
+
+    photos_norm()
+        .args(["set", "--force", "--description", "Capitole"])
+        .arg(tmpdir.path())
+        .assert()
+        .success();
+}
+
+#[test]
+fn fix_name() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    copy_fixtures(tmpdir.path(), &["all_tags.jpg"]);
+
+    photos_norm()
+        .args(["fix", "--name"])
+        .arg(tmpdir.path())
+        .assert()
+        .success();
+    assert!(tmpdir
+        .path()
+        .join("2006_10_29-16_27_21 - A fun picture!.jpg")
+        .exists());
+    assert!(!tmpdir.path().join("all_tags.jpg").exists());
+}
+
+#[test]
+fn fix_sequence() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    copy_fixtures(tmpdir.path(), &["all_tags.jpg", "no_date.jpg"]);
+
+    photos_norm()
+        .args(["fix", "--name", "--sequence", "Wedding"])
+        .arg(tmpdir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No date, file not renamed."));
+    assert!(tmpdir.path().join("Wedding_001.jpg").exists());
+    assert!(tmpdir.path().join("no_date.jpg").exists());
+
+    // An earlier image shifts the numbers: images are renamed without replacing each other
+    let earlier = tmpdir.path().join("earlier.jpg");
+    fs::copy("tests/no_description.jpg", &earlier).unwrap();
+    photos_norm()
+        .args(["set", "--force", "--date", "2006:10:28 10:00:00"])
+        .arg(&earlier)
+        .assert()
+        .success();
+    photos_norm()
+        .args(["fix", "--all", "--sequence", "Wedding"])
+        .arg(tmpdir.path())
+        .assert()
+        .success();
+    assert!(!earlier.exists());
+    assert!(!tmpdir.path().join("Wedding_001-1.jpg").exists());
+    assert!(!tmpdir.path().join("Wedding_002-1.jpg").exists());
+    let mut names: Vec<_> = fs::read_dir(tmpdir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["Wedding_001.jpg", "Wedding_002.jpg", "no_date.jpg"]);
+    photos_norm()
+        .arg("info")
+        .arg(tmpdir.path().join("Wedding_002.jpg"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("A fun picture!"));
+
+    // --sequence is a --name mode
+    photos_norm()
+        .args(["fix", "--orientation", "--sequence", "Wedding"])
+        .arg(tmpdir.path())
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn fix_migrate_keywords() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let images = copy_fixtures(tmpdir.path(), &["all_tags.jpg"]);
+    photos_norm()
+        .args(["set", "--description", "plage, 2006, famille"])
+        .arg(&images[0])
+        .assert()
+        .success();
+
+    // The description is removed: only with --force
+    photos_norm()
+        .args(["fix", "--migrate-keywords"])
+        .arg(&images[0])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Description looks like keywords"));
+    photos_norm()
+        .args(["fix", "--migrate-keywords", "--force"])
+        .arg(&images[0])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Description looks like keywords").not());
+    photos_norm()
+        .args(["fix", "--migrate-keywords"])
+        .arg(&images[0])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Description looks like keywords").not());
+}
+
+#[test]
+fn watch_once() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let folder = tmpdir.path().join("watched");
+    fs::create_dir(&folder).unwrap();
+    let journal = tmpdir.path().join("journal.jsonl");
+
+    // New image once the folder is watched
+    let image = folder.join("all_tags.jpg");
+    let copy = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_secs(1));
+        fs::copy("tests/all_tags.jpg", image).unwrap();
+    });
+    let output = photos_norm()
+        .arg("--journal")
+        .arg(&journal)
+        .args(["watch", "--settle", "1"])
+        .arg(&folder)
+        .args(["--", "set", "--description", "Capitole"])
+        .timeout(Duration::from_secs(6))
+        .output()
+        .unwrap();
+    copy.join().unwrap();
+
+    // The image written by set is not a new image: processed once, with global options
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches("File:").count(), 1);
+    assert!(journal.exists());
+}
+
+#[test]
+fn file_errors() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    copy_fixtures(tmpdir.path(), &["all_tags.jpg"]);
+    fs::write(tmpdir.path().join("notes.txt"), "Not an image").unwrap();
+
+    photos_norm()
+        .arg("info")
+        .arg(tmpdir.path())
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("Error!"));
+}
+
+#[test]
+fn output_dir() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let output = tmpdir.path().join("output");
+    let images = copy_fixtures(tmpdir.path(), &["all_tags.jpg"]);
+    fs::write(tmpdir.path().join("notes.txt"), "Not an image").unwrap();
+
+    photos_norm()
+        .args(["fix", "--name", "--output-dir"])
+        .arg(&output)
+        .arg(tmpdir.path())
+        .assert()
+        .code(1);
+    assert!(images[0].exists());
+    assert!(output
+        .join("2006_10_29-16_27_21 - A fun picture!.jpg")
+        .exists());
+    // Invalid images are not copied
+    assert!(!output.join("notes.txt").exists());
+}
+
+#[test]
+fn sidecar_report() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let images = copy_fixtures(tmpdir.path(), &["all_tags.jpg"]);
+    let png = tmpdir.path().join("all_tags.png");
+    fs::copy("tests/all_tags.jpg", &png).unwrap();
+
+    photos_norm()
+        .args(["info", "--sidecar-report"])
+        .arg(&images[0])
+        .arg(&png)
+        .assert()
+        .success();
+    let report = fs::read(tmpdir.path().join("all_tags.jpg.json")).unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&report).unwrap();
+    assert_eq!(value["description"], "A fun picture!");
+    assert!(tmpdir.path().join("all_tags.png.json").exists());
+}
+
+#[test]
+fn no_input_files() {
+    let tmpdir = tempfile::tempdir().unwrap();
+
+    photos_norm()
+        .arg("info")
+        .arg(tmpdir.path())
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("No input files."));
+
+    photos_norm()
+        .arg("info")
+        .arg(tmpdir.path().join("*.jpg"))
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains("No files matched."));
+}
+
+#[test]
+fn usage_errors() {
+    photos_norm().arg("info").assert().code(2);
+    photos_norm()
+        .args(["info", "--date-format", "invalid", "tests/all_tags.jpg"])
+        .assert()
+        .code(2);
+    photos_norm()
+        .args(["info", "--sort-by", "date", "tests/all_tags.jpg"])
+        .assert()
+        .code(2);
+    photos_norm()
+        .args([
+            "info",
+            "--format",
+            "json",
+            "-i=always",
+            "tests/all_tags.jpg",
+        ])
+        .assert()
+        .code(2);
+    photos_norm()
+        .args([
+            "set",
+            "--description",
+            "Capitole",
+            "--max-description-length",
+            "0",
+        ])
+        .arg("tests/all_tags.jpg")
+        .assert()
+        .code(2);
+    // Checked before modifying any image
+    photos_norm()
+        .args([
+            "set",
+            "--description",
+            "Capitole",
+            "--max-description-length",
+            "3",
+        ])
+        .arg("tests/all_tags.jpg")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("--truncate"));
+    photos_norm()
+        .args(["set", "--comment", "Café", "--encoding", "ascii"])
+        .arg("tests/all_tags.jpg")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Comment is not ASCII"));
+}